@@ -0,0 +1,60 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Times `Sexp::write_to` against `sexpr::to_string` over a large tree.
+//!
+//! The bench harness needs a nightly compiler, so this runs as a plain
+//! program: `cargo bench --bench write_to`.
+
+extern crate sexpr;
+
+use std::time::{Duration, Instant};
+
+use sexpr::{ParseConfig, Sexp};
+
+const ITERATIONS: u32 = 50;
+
+/// 20,000 records of symbols, escaped strings, floats and vectors.
+fn large_tree() -> Sexp {
+    let mut text = String::from("(");
+    for i in 0..20000 {
+        text.push_str(&format!("((id {}) (label \"line\\n\\\"{}\\\"\") (ratio {}.25) #(x #t #nil)) ", i, i, i));
+    }
+    text.push(')');
+    let mut config = ParseConfig::default();
+    config.hash_vectors = true;
+    sexpr::from_str_with_config(&text, config).unwrap()
+}
+
+fn report(name: &str, bytes: usize, elapsed: Duration) {
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    let per_iter = nanos / ITERATIONS as u64;
+    let mb_per_sec = (bytes as f64 * ITERATIONS as f64) / (nanos as f64 / 1e9) / 1e6;
+    println!("{:10} {:>12} ns/iter {:>8.1} MB/s", name, per_iter, mb_per_sec);
+}
+
+fn main() {
+    let tree = large_tree();
+
+    let mut out = Vec::new();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        out.clear();
+        tree.write_to(&mut out).unwrap();
+    }
+    report("write_to", out.len(), start.elapsed());
+
+    let mut len = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        len = sexpr::to_string(&tree).unwrap().len();
+    }
+    report("to_string", len, start.elapsed());
+
+    assert_eq!(out.len(), len);
+}
@@ -5,12 +5,70 @@
 // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
+use config::ParseConfig;
 use error::Error;
 use serde::de::{self, Visitor};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use sexp::{visit_tagged_name, Sexp, KEYWORD_TOKEN, SEXP_TOKEN, SYMBOL_TOKEN};
 use std::fmt::{self, Debug, Display};
 
 use std::borrow::Cow;
+#[cfg(feature = "intern-keywords")]
+use std::sync::Arc;
+
+/// The name of a symbol. With the `intern` feature enabled, every symbol with
+/// the same name shares a single allocation; see `interner`.
+#[cfg(feature = "intern")]
+type SymbolName = Arc<str>;
+#[cfg(not(feature = "intern"))]
+type SymbolName = String;
+
+#[cfg(feature = "intern")]
+fn symbol_name(s: String) -> SymbolName {
+    interner::intern(s)
+}
+
+#[cfg(not(feature = "intern"))]
+fn symbol_name(s: String) -> SymbolName {
+    s
+}
+
+#[cfg(feature = "intern")]
+fn symbol_into_string(name: SymbolName) -> String {
+    String::from(&*name)
+}
+
+#[cfg(not(feature = "intern"))]
+fn symbol_into_string(name: SymbolName) -> String {
+    name
+}
+
+/// The name of a keyword. Keywords are interned along with symbols by the
+/// `intern` feature, or on their own by the narrower `intern-keywords`.
+#[cfg(feature = "intern-keywords")]
+type KeywordName = Arc<str>;
+#[cfg(not(feature = "intern-keywords"))]
+type KeywordName = String;
+
+#[cfg(feature = "intern-keywords")]
+fn keyword_name(s: String) -> KeywordName {
+    interner::intern(s)
+}
+
+#[cfg(not(feature = "intern-keywords"))]
+fn keyword_name(s: String) -> KeywordName {
+    s
+}
+
+#[cfg(feature = "intern-keywords")]
+fn keyword_into_string(name: KeywordName) -> String {
+    String::from(&*name)
+}
+
+#[cfg(not(feature = "intern-keywords"))]
+fn keyword_into_string(name: KeywordName) -> String {
+    name
+}
 
 /// Represents a Sexp atom, whether symbol, keyword or string.
 #[derive(Clone, PartialEq)]
@@ -21,8 +79,8 @@ pub struct Atom {
 #[cfg_attr(feature = "cargo-clippy", allow(enum_variant_names))]
 #[derive(Clone, Debug, PartialEq)]
 enum A {
-    Symbol(String),
-    Keyword(String),
+    Symbol(SymbolName),
+    Keyword(KeywordName),
     String(String)
 }
 
@@ -56,7 +114,12 @@ impl Atom {
     }
 
     pub fn into_symbol(s: String) -> Self {
-        Atom { a: A::Symbol(s) }
+        Atom { a: A::Symbol(symbol_name(s)) }
+    }
+
+    /// Creates a keyword from its name, without the `#:` prefix.
+    pub fn into_keyword(s: String) -> Self {
+        Atom { a: A::Keyword(keyword_name(s)) }
     }
 
     /// Returns an Atom appropriate for it's contents.
@@ -66,12 +129,32 @@ impl Atom {
     pub fn discriminate(s: String) -> Self {
         if s.starts_with("#:") {
             let (_, keyword) = s.split_at(2);
-            Atom { a: A::Keyword(String::from(keyword)) }
-        } else if (s.starts_with('"') && s.ends_with('"'))
-               || (s.starts_with("'") && s.ends_with("'")) {
-            Atom { a: A::String(String::from(&s[1..s.len()]))}
+            Atom { a: A::Keyword(keyword_name(String::from(keyword))) }
+        } else if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+            // A leading `'` is quote syntax rather than a string delimiter and
+            // is left for the reader to handle.
+            Atom { a: A::String(String::from(&s[1..s.len() - 1]))}
         } else {
-            Atom { a: A::Symbol(s) }
+            Atom { a: A::Symbol(symbol_name(s)) }
+        }
+    }
+
+    /// Reads `s` as a single atom, exactly as the deserializer would read it
+    /// inside a list under `config`: a symbol, a `#:keyword` or a delimited
+    /// string.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `s` is not one whole atom, for instance a number or a list.
+    ///
+    /// ```rust,ignore
+    /// let atom = Atom::parse("#:key", &ParseConfig::default()).unwrap();
+    /// assert_eq!(atom.as_keyword(), Some("key"));
+    /// ```
+    pub fn parse(s: &str, config: &ParseConfig) -> Result<Atom, Error> {
+        match try!(::de::from_str_with_config(s, config.clone())) {
+            Sexp::Atom(atom) => Ok(atom),
+            other => Err(de::Error::invalid_type(other.unexpected(), &"an atom")),
         }
     }
 
@@ -94,15 +177,42 @@ impl Atom {
         }
     }
 
+    /// Returns the name of this atom if it is a symbol.
     #[inline]
-    pub fn as_string(&self) -> String {
-        let s = match self.a {
-            A::Symbol(ref s)  => s,
-            A::Keyword(ref s) => s,
-            A::String(ref s)  => s,
-        };
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self.a {
+            A::Symbol(ref s) => Some(s),
+            _ => None,
+        }
+    }
 
-        s.clone()
+    /// Returns the name of this atom (without the `#:` prefix) if it is a
+    /// keyword.
+    #[inline]
+    pub fn as_keyword(&self) -> Option<&str> {
+        match self.a {
+            A::Keyword(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of this atom if it is a string.
+    #[inline]
+    pub fn as_string(&self) -> Option<&str> {
+        match self.a {
+            A::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Consumes the atom, returning its underlying text regardless of variant.
+    #[inline]
+    pub fn into_inner(self) -> String {
+        match self.a {
+            A::Symbol(s) => symbol_into_string(s),
+            A::Keyword(s) => keyword_into_string(s),
+            A::String(s) => s,
+        }
     }
 }
 
@@ -130,7 +240,7 @@ impl Serialize for Atom {
         S: Serializer,
     {
         match self.a {
-            A::Symbol(ref s)  => serializer.serialize_newtype_struct("Symbol", s),
+            A::Symbol(ref s)  => serializer.serialize_newtype_struct("Symbol", &**s),
             A::Keyword(ref s) => serializer.serialize_str(s),
             A::String(ref s)  => serializer.serialize_str(s),
         }
@@ -138,40 +248,24 @@ impl Serialize for Atom {
 }
 
 impl<'de> Deserialize<'de> for Atom {
+    /// Reads any atom a `Sexp` could hold, keeping symbols, keywords and
+    /// strings apart.
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Atom, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct AtomVisitor;
-
-        impl<'de> Visitor<'de> for AtomVisitor {
-            type Value = Atom;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("an atom")
-            }
-
-            // #[inline]
-            // fn visit_str<E>(self, value: &str) -> Result<Atom, E>
-            // {
-            //     self.visit_string(String::from(value))
-            // }
-
-            #[inline]
-            fn visit_string<E>(self, value: String) -> Result<Atom, E>
-            where
-                E: de::Error,
-            {
-                Ok(Atom::from_string(value))
-            }
+        match try!(Sexp::deserialize(deserializer)) {
+            Sexp::Atom(atom) => Ok(atom),
+            other => Err(de::Error::invalid_type(other.unexpected(), &"an atom")),
         }
-
-        deserializer.deserialize_any(AtomVisitor)
     }
 }
 
 
+/// Symbols and keywords deserialize as their names. Asked for through
+/// `SEXP_TOKEN`, as `Sexp` does, they are tagged the way the text
+/// deserializer tags them.
 impl<'de> Deserializer<'de> for Atom {
     type Error = Error;
 
@@ -179,17 +273,30 @@ impl<'de> Deserializer<'de> for Atom {
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
         where
         V: Visitor<'de>,
+    {
+        visitor.visit_string(self.into_inner())
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+        where
+        V: Visitor<'de>,
     {
         match self.a {
-            A::Symbol(s) => visitor.visit_string(s),
-            A::Keyword(s) => visitor.visit_string(s),
-            A::String(s) => visitor.visit_string(s),
+            A::Symbol(s) if name == SEXP_TOKEN => {
+                visit_tagged_name(SYMBOL_TOKEN, symbol_into_string(s), visitor)
+            }
+            A::Keyword(s) if name == SEXP_TOKEN => {
+                visit_tagged_name(KEYWORD_TOKEN, keyword_into_string(s), visitor)
+            }
+            _ if name == SEXP_TOKEN => self.deserialize_any(visitor),
+            _ => visitor.visit_newtype_struct(self),
         }
     }
 
     forward_to_deserialize_any! {
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-            byte_buf option unit unit_struct newtype_struct seq tuple
+            byte_buf option unit unit_struct seq tuple
             tuple_struct map struct enum identifier ignored_any
     }
 }
@@ -202,17 +309,30 @@ impl<'de, 'a> Deserializer<'de> for &'a Atom {
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
         where
         V: Visitor<'de>,
+    {
+        visitor.visit_str(self.as_str())
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+        where
+        V: Visitor<'de>,
     {
         match self.a {
-            A::Symbol(ref s) => visitor.visit_string(s.clone()),
-            A::Keyword(ref s) => visitor.visit_string(s.clone()),
-            A::String(ref s) => visitor.visit_string(s.clone()),
+            A::Symbol(ref s) if name == SEXP_TOKEN => {
+                visit_tagged_name(SYMBOL_TOKEN, String::from(&**s), visitor)
+            }
+            A::Keyword(ref s) if name == SEXP_TOKEN => {
+                visit_tagged_name(KEYWORD_TOKEN, String::from(&**s), visitor)
+            }
+            _ if name == SEXP_TOKEN => self.deserialize_any(visitor),
+            _ => visitor.visit_newtype_struct(self),
         }
     }
 
     forward_to_deserialize_any! {
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-            byte_buf option unit unit_struct newtype_struct seq tuple
+            byte_buf option unit unit_struct seq tuple
             tuple_struct map struct enum identifier ignored_any
     }
 }
@@ -238,3 +358,51 @@ impl<'a> From<Cow<'a, str>> for Atom {
         Atom::from_string(s.to_string())
     }
 }
+
+/// Deduplicates symbol and keyword names so that equal names share storage.
+///
+/// Each thread keeps its own table of the names it has seen. Names that are
+/// no longer used by any atom are dropped from the table whenever it doubles
+/// in size, so the table stays proportional to the symbols in use.
+#[cfg(feature = "intern-keywords")]
+mod interner {
+    use std::cell::RefCell;
+    use std::cmp;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    const MIN_PRUNE_LEN: usize = 256;
+
+    struct Interner {
+        names: HashSet<Arc<str>>,
+        prune_at: usize,
+    }
+
+    thread_local! {
+        static SYMBOLS: RefCell<Interner> = RefCell::new(Interner {
+            names: HashSet::new(),
+            prune_at: MIN_PRUNE_LEN,
+        });
+    }
+
+    pub fn intern(name: String) -> Arc<str> {
+        SYMBOLS.with(|symbols| symbols.borrow_mut().intern(name))
+    }
+
+    impl Interner {
+        fn intern(&mut self, name: String) -> Arc<str> {
+            if let Some(shared) = self.names.get(&*name) {
+                return shared.clone();
+            }
+
+            if self.names.len() >= self.prune_at {
+                self.names.retain(|name| Arc::strong_count(name) > 1);
+                self.prune_at = cmp::max(MIN_PRUNE_LEN, 2 * self.names.len());
+            }
+
+            let shared: Arc<str> = Arc::from(name);
+            self.names.insert(shared.clone());
+            shared
+        }
+    }
+}
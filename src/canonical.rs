@@ -0,0 +1,344 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encode a `Sexp` in the canonical form of Rivest's S-expressions, and
+//! decode that form back.
+//!
+//! Canonical S-expressions have exactly one encoding per value, which makes
+//! them suitable as input to a digital signature. Every atom is written as a
+//! length-prefixed octet string and lists are written without whitespace:
+//!
+//! ```text
+//! (define (square x) (* x x))  =>  (6:define(6:square1:x)(1:*1:x1:x))
+//! ```
+//!
+//! The canonical form only knows octet strings, so
+//!
+//! - symbols and strings are written as their contents, keywords as `#:name`,
+//! - numbers are written as their decimal representation,
+//! - booleans are written as `#t` and `#f`, and nil as `()`.
+//!
+//! A dotted pair has no canonical equivalent. It is written as an improper
+//! list with a bare `.` before its tail, e.g. `(1:a1:b.1:c)` for `(a b . c)`,
+//! which cannot be confused with any canonical list. Likewise a bracketed
+//! `Sexp::Vector` is written as `#(...)`, e.g. `#(1:a1:b)` for `[a b]`.
+//!
+//! Decoding reads the octets directly and does not require them to be
+//! UTF-8. An octet string that is valid UTF-8 is visited as a string and any
+//! other as bytes. Decoding understands only octet strings, optionally
+//! preceded by a `[hint]` display hint, and lists. It does not understand
+//! the `.` and `#(` extensions above.
+
+use std::str;
+
+use itoa;
+use serde::de::{self, Deserialize, Visitor};
+
+use error::{Error, ErrorCode, Result};
+use sexp::Sexp;
+
+/// Encode `value` in canonical form.
+///
+/// ```rust,ignore
+/// let v: Sexp = sexpr::from_str("(a \"bc\" 42)").unwrap();
+/// assert_eq!(sexpr::to_canonical(&v), "(1:a2:bc2:42)");
+/// ```
+pub fn to_canonical(value: &Sexp) -> String {
+    let mut out = Vec::new();
+    encode(value, &mut |bytes: &[u8]| out.extend_from_slice(bytes));
+    // Every octet string comes from a `str` or from ASCII digits.
+    String::from_utf8(out).unwrap()
+}
+
+/// Encode `value` in canonical form after sorting the entries of every
+/// alist by the canonical encoding of their keys, so alists that differ only
+/// in entry order encode the same. Only lists recognized by
+/// `Sexp::is_alist` are reordered; every other list keeps its order. Entries
+/// with equal keys are ordered by their whole encoding.
+pub fn to_canonical_sorted(value: &Sexp) -> String {
+    to_canonical(&sorted(value))
+}
+
+/// A copy of `value` with the entries of every alist in it sorted.
+fn sorted(value: &Sexp) -> Sexp {
+    match *value {
+        Sexp::List(ref elts) => {
+            let elts: Vec<Sexp> = elts.iter().map(sorted).collect();
+            if !value.is_alist() {
+                return Sexp::List(elts);
+            }
+            let mut keyed: Vec<(String, String, Sexp)> = elts.into_iter()
+                .map(|entry| {
+                    let key = entry.car().map(to_canonical).unwrap_or_default();
+                    (key, to_canonical(&entry), entry)
+                })
+                .collect();
+            keyed.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+            Sexp::List(keyed.into_iter().map(|(_, _, entry)| entry).collect())
+        }
+        Sexp::Vector(ref elts) => Sexp::Vector(elts.iter().map(sorted).collect()),
+        Sexp::Pair(ref car, ref cdr) => {
+            Sexp::Pair(car.as_ref().map(|car| Box::new(sorted(car))),
+                       cdr.as_ref().map(|cdr| Box::new(sorted(cdr))))
+        }
+        ref other => other.clone(),
+    }
+}
+
+/// Feeds the canonical encoding of `value` to `sink` a piece at a time.
+pub fn encode<F>(value: &Sexp, sink: &mut F)
+where
+    F: FnMut(&[u8]),
+{
+    match *value {
+        Sexp::Nil => sink(b"()"),
+        Sexp::Boolean(true) => encode_octets(b"#t", sink),
+        Sexp::Boolean(false) => encode_octets(b"#f", sink),
+        Sexp::Number(ref n) => encode_octets(n.to_string().as_bytes(), sink),
+        Sexp::Atom(ref a) => {
+            match a.as_keyword() {
+                Some(name) => {
+                    encode_length(2 + name.len(), sink);
+                    sink(b"#:");
+                    sink(name.as_bytes());
+                }
+                None => encode_octets(a.as_str().as_bytes(), sink),
+            }
+        }
+        Sexp::List(ref elts) => {
+            sink(b"(");
+            for elt in elts {
+                encode(elt, sink);
+            }
+            sink(b")");
+        }
+        Sexp::Vector(ref elts) => {
+            sink(b"#(");
+            for elt in elts {
+                encode(elt, sink);
+            }
+            sink(b")");
+        }
+        Sexp::Pair(_, _) => {
+            sink(b"(");
+            let mut spine = value;
+            while let Sexp::Pair(ref car, ref cdr) = *spine {
+                encode_cell(car, sink);
+                match *cdr {
+                    Some(ref cdr) if is_pair(cdr) => spine = cdr,
+                    _ => {
+                        sink(b".");
+                        encode_cell(cdr, sink);
+                        break;
+                    }
+                }
+            }
+            sink(b")");
+        }
+    }
+}
+
+fn is_pair(value: &Sexp) -> bool {
+    match *value {
+        Sexp::Pair(_, _) => true,
+        _ => false,
+    }
+}
+
+fn encode_cell<F>(cell: &Option<Box<Sexp>>, sink: &mut F)
+where
+    F: FnMut(&[u8]),
+{
+    match *cell {
+        Some(ref value) => encode(value, sink),
+        None => encode(&Sexp::Nil, sink),
+    }
+}
+
+fn encode_octets<F>(octets: &[u8], sink: &mut F)
+where
+    F: FnMut(&[u8]),
+{
+    encode_length(octets.len(), sink);
+    sink(octets);
+}
+
+fn encode_length<F>(len: usize, sink: &mut F)
+where
+    F: FnMut(&[u8]),
+{
+    let mut buf = [0u8; 21];
+    let n = itoa::write(&mut buf[..], len).unwrap();
+    buf[n] = b':';
+    sink(&buf[..n + 1]);
+}
+
+/// Deserializes an instance of type `T` from a canonical S-expression. Only
+/// ASCII whitespace may follow it.
+pub fn decode<'de, T>(input: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut decoder = Decoder {
+        input: input,
+        index: 0,
+        remaining_depth: 128,
+    };
+    let value = try!(T::deserialize(&mut decoder));
+    let rest = &input[decoder.index..];
+    if rest.iter().any(|&b| b != b' ' && b != b'\n' && b != b'\t' && b != b'\r') {
+        return Err(Error::syntax(ErrorCode::TrailingCharacters, 0, 0));
+    }
+    Ok(value)
+}
+
+struct Decoder<'de> {
+    input: &'de [u8],
+    index: usize,
+    remaining_depth: u8,
+}
+
+impl<'de> Decoder<'de> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.index).cloned()
+    }
+
+    fn error(&self, code: ErrorCode) -> Error {
+        Error::syntax(code, 0, 0)
+    }
+
+    /// Reads an atom, skipping its display hint if it has one.
+    fn parse_atom(&mut self) -> Result<&'de [u8]> {
+        if self.peek() == Some(b'[') {
+            self.index += 1;
+            try!(self.parse_octets());
+            if self.peek() != Some(b']') {
+                return Err(self.error(ErrorCode::ExpectedSomeValue));
+            }
+            self.index += 1;
+        }
+        self.parse_octets()
+    }
+
+    /// Reads a length-prefixed octet string such as `3:abc`.
+    fn parse_octets(&mut self) -> Result<&'de [u8]> {
+        let start = self.index;
+        let mut len = 0usize;
+        while let Some(c @ b'0'...b'9') = self.peek() {
+            len = match len.checked_mul(10).and_then(|len| len.checked_add((c - b'0') as usize)) {
+                Some(len) => len,
+                None => return Err(self.error(ErrorCode::NumberOutOfRange)),
+            };
+            self.index += 1;
+        }
+        match self.peek() {
+            Some(b':') if self.index > start => self.index += 1,
+            Some(_) => return Err(self.error(ErrorCode::ExpectedSomeValue)),
+            None => return Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+        let end = match self.index.checked_add(len) {
+            Some(end) if end <= self.input.len() => end,
+            _ => return Err(self.error(ErrorCode::EofWhileParsingString)),
+        };
+        let octets = &self.input[self.index..end];
+        self.index = end;
+        Ok(octets)
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for &'a mut Decoder<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek() {
+            Some(b'(') => {
+                self.remaining_depth -= 1;
+                if self.remaining_depth == 0 {
+                    return Err(self.error(ErrorCode::RecursionLimitExceeded));
+                }
+                self.index += 1;
+                let value = try!(visitor.visit_seq(ListAccess { de: &mut *self }));
+                self.remaining_depth += 1;
+                match self.peek() {
+                    Some(b')') => {
+                        self.index += 1;
+                        Ok(value)
+                    }
+                    Some(_) => Err(self.error(ErrorCode::TrailingCharacters)),
+                    None => Err(self.error(ErrorCode::EofWhileParsingList)),
+                }
+            }
+            Some(_) => {
+                let octets = try!(self.parse_atom());
+                match str::from_utf8(octets) {
+                    Ok(s) => visitor.visit_borrowed_str(s),
+                    Err(_) => visitor.visit_borrowed_bytes(octets),
+                }
+            }
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek() {
+            Some(b'(') | None => self.deserialize_any(visitor),
+            Some(_) => visitor.visit_borrowed_bytes(try!(self.parse_atom())),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// `()`, the encoding of nil, reads as `None`.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.input[self.index..].starts_with(b"()") {
+            self.index += 2;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+struct ListAccess<'a, 'de: 'a> {
+    de: &'a mut Decoder<'de>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for ListAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.de.peek() {
+            Some(b')') => Ok(None),
+            Some(_) => seed.deserialize(&mut *self.de).map(Some),
+            None => Err(self.de.error(ErrorCode::EofWhileParsingList)),
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Options controlling how S-expression text is read and written.
+
+use number::Number;
+
+/// Describes the S-expression dialect understood by a `Deserializer`.
+///
+/// S-expressions have no single standard, so the syntax accepted by the parser
+/// is configurable. `ParseConfig::default()` accepts the syntax documented in
+/// the crate root.
+///
+/// ```rust,ignore
+/// use sexpr::{ParseConfig, Sexp};
+///
+/// let mut config = ParseConfig::default();
+/// config.string_delimiters.push(('«', '»'));
+///
+/// let v: Sexp = sexpr::from_str_with_config("«quoted»", config).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParseConfig {
+    /// Pairs of opening and closing characters that delimit a string.
+    ///
+    /// The standard `"..."` form is recognized only when `('"', '"')` is
+    /// present. Inside any delimited string a backslash escapes the closing
+    /// delimiter in addition to the usual escape sequences.
+    pub string_delimiters: Vec<(char, char)>,
+
+    /// Accept `_` between the digits of a number, as in `1_000_000`. A `_`
+    /// must sit between two digits, so `1__0` and `1_` are errors and `_1`
+    /// is not a number at all.
+    pub numeric_underscores: bool,
+
+    /// Called with the letters immediately following a number, such as the
+    /// `u` of `42u` or the `f` of `3.0f`, and the number itself. Returns the
+    /// number to use in its place, or `None` to reject the suffix.
+    ///
+    /// Without a hook, letters following a number are a syntax error. Two
+    /// configs compare equal whichever hooks they have, as long as both or
+    /// neither have one, since function pointers cannot be compared reliably.
+    pub number_suffix: Option<fn(&str, Number) -> Option<Number>>,
+
+    /// Prefixes that begin a comment running to the end of the line, `;` by
+    /// default.
+    ///
+    /// A prefix is only recognized where a token could begin, so `a;b` is a
+    /// single symbol. A prefix starting with `#` must also be followed by
+    /// whitespace or the end of input, which keeps `#t`, `#nil` and
+    /// `#:keyword` readable when `#` is configured as a comment starter.
+    pub line_comment_prefixes: Vec<String>,
+
+    /// Symbols read as `true` where a `bool` is expected, such as `yes` or
+    /// `t`. Anywhere else they remain ordinary symbols. `#t` is always true.
+    pub true_symbols: Vec<String>,
+
+    /// Symbols read as `false` where a `bool` is expected, such as `no` or
+    /// `nil`. `#f` is always false.
+    pub false_symbols: Vec<String>,
+
+    /// Read `[a b]` as a `Sexp::Vector`, distinct from the list `(a b)`.
+    /// Otherwise `[` is a syntax error.
+    pub square_brackets: bool,
+
+    /// Read the Scheme vector literal `#(a b)` as a `Sexp::Vector`.
+    /// Otherwise `#(` is a syntax error.
+    pub hash_vectors: bool,
+
+    /// Read Common Lisp datum labels: `#1=datum` reads as `datum` and
+    /// records it, and a later `#1#` reads as a copy of it. A reference to a
+    /// datum from inside itself is an error, since a `Sexp` cannot be cyclic.
+    pub datum_labels: bool,
+
+    /// Treat `,` as whitespace, as EDN does, so `(a, b, c)` reads as
+    /// `(a b c)`. Otherwise a comma is an ordinary symbol character.
+    pub commas_are_whitespace: bool,
+
+    /// Read the empty list `()` as `Sexp::Nil`, as most Lisps do. Otherwise
+    /// it reads as an empty `Sexp::List`.
+    pub empty_list_is_nil: bool,
+
+    /// Read `"""..."""` as a raw string: everything up to the next `"""`,
+    /// newlines and lone quotes included, with no escape processing.
+    pub raw_strings: bool,
+
+    /// Read a token that starts with a digit, `+`, `-` or `.` as a number if
+    /// the whole token is one, and as a symbol if it is not. `+5` and `.5`
+    /// then read as numbers and `1a`, `1e`, `1.2.3` and `1/2` as symbols,
+    /// where normally all but `1/2` are errors.
+    pub aggressive_number_detection: bool,
+
+    /// Stop a stream of top-level forms with an error once this many have
+    /// been read, to bound the work done on untrusted input.
+    pub max_forms: Option<usize>,
+
+    /// Skip `#|...|#` block comments, which may nest.
+    pub block_comments: bool,
+
+    /// Skip `#;` together with the datum that follows it.
+    pub datum_comments: bool,
+
+    /// Keep every comment the deserializer skips, with its position, for
+    /// `Deserializer::comments`.
+    pub retain_comments: bool,
+
+    /// What to make of a `#` followed by a character that starts none of the
+    /// forms above, such as `#z`.
+    pub unknown_hash_policy: UnknownHashPolicy,
+
+    /// Join consecutive strings within a list or vector into one, so
+    /// `("a" "b" c "d")` reads as `("ab" c "d")`. Anything else between two
+    /// strings, a symbol included, keeps them apart. Applies when reading a
+    /// `Sexp`.
+    pub concat_adjacent_strings: bool,
+
+    /// How keywords other than `#:name` are written. `#:name` is always read
+    /// as a keyword.
+    pub keyword_style: KeywordStyle,
+}
+
+impl Default for ParseConfig {
+    fn default() -> Self {
+        ParseConfig {
+            string_delimiters: vec![('"', '"')],
+            numeric_underscores: false,
+            number_suffix: None,
+            line_comment_prefixes: vec![";".to_string()],
+            true_symbols: Vec::new(),
+            false_symbols: Vec::new(),
+            square_brackets: false,
+            hash_vectors: false,
+            datum_labels: false,
+            commas_are_whitespace: false,
+            empty_list_is_nil: false,
+            raw_strings: false,
+            aggressive_number_detection: false,
+            max_forms: None,
+            block_comments: false,
+            datum_comments: false,
+            retain_comments: false,
+            unknown_hash_policy: UnknownHashPolicy::Error,
+            concat_adjacent_strings: false,
+            keyword_style: KeywordStyle::Hash,
+        }
+    }
+}
+
+impl PartialEq for ParseConfig {
+    fn eq(&self, other: &ParseConfig) -> bool {
+        self.string_delimiters == other.string_delimiters &&
+            self.numeric_underscores == other.numeric_underscores &&
+            self.number_suffix.is_some() == other.number_suffix.is_some() &&
+            self.line_comment_prefixes == other.line_comment_prefixes &&
+            self.true_symbols == other.true_symbols &&
+            self.false_symbols == other.false_symbols &&
+            self.square_brackets == other.square_brackets &&
+            self.hash_vectors == other.hash_vectors &&
+            self.datum_labels == other.datum_labels &&
+            self.commas_are_whitespace == other.commas_are_whitespace &&
+            self.empty_list_is_nil == other.empty_list_is_nil &&
+            self.raw_strings == other.raw_strings &&
+            self.aggressive_number_detection == other.aggressive_number_detection &&
+            self.max_forms == other.max_forms &&
+            self.block_comments == other.block_comments &&
+            self.datum_comments == other.datum_comments &&
+            self.retain_comments == other.retain_comments &&
+            self.unknown_hash_policy == other.unknown_hash_policy &&
+            self.concat_adjacent_strings == other.concat_adjacent_strings &&
+            self.keyword_style == other.keyword_style
+    }
+}
+
+impl ParseConfig {
+    /// Reads booleans written in `style` where a `bool` is expected, by adding
+    /// its spellings to `true_symbols` and `false_symbols`. `#t` and `#f` are
+    /// always accepted, so `BoolStyle::HashTF` adds nothing.
+    ///
+    /// ```rust,ignore
+    /// let mut config = ParseConfig::default();
+    /// config.accept_bool_style(BoolStyle::TNil);
+    ///
+    /// let flags: Vec<bool> = sexpr::from_str_with_config("(t nil #t)", config).unwrap();
+    /// assert_eq!(flags, vec![true, false, true]);
+    /// ```
+    pub fn accept_bool_style(&mut self, style: BoolStyle) {
+        fn add(symbols: &mut Vec<String>, spelling: &str) {
+            if !symbols.iter().any(|s| s == spelling) {
+                symbols.push(spelling.to_string());
+            }
+        }
+
+        if style != BoolStyle::HashTF {
+            add(&mut self.true_symbols, style.spelling(true));
+            add(&mut self.false_symbols, style.spelling(false));
+        }
+    }
+}
+
+/// Returns the boolean that the symbol `name` stands for under `config`, if
+/// any.
+pub fn symbol_to_bool(config: &ParseConfig, name: &str) -> Option<bool> {
+    if config.true_symbols.iter().any(|s| s == name) {
+        Some(true)
+    } else if config.false_symbols.iter().any(|s| s == name) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Returns the bytes that end a symbol under `config` in addition to
+/// whitespace and closing brackets.
+pub fn symbol_delimiters(config: &ParseConfig) -> &'static [u8] {
+    if config.commas_are_whitespace {
+        b","
+    } else {
+        b""
+    }
+}
+
+/// Options controlling the S-expression text produced by a `Serializer`.
+///
+/// Token spelling and whitespace are the business of a `Formatter`; a
+/// `SerializeConfig` decides how Rust data structures are laid out.
+///
+/// ```rust,ignore
+/// use sexpr::SerializeConfig;
+///
+/// let mut config = SerializeConfig::default();
+/// config.two_tuples_as_pairs = true;
+///
+/// let s = sexpr::to_string_with_config(&(1, "one"), config).unwrap();
+/// assert_eq!(s, r#"(1 . "one")"#);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SerializeConfig {
+    /// Write two-element tuples and two-field tuple structs as dotted pairs,
+    /// `(a . b)`, rather than as two-element lists, `(a b)`. The deserializer
+    /// accepts either form.
+    pub two_tuples_as_pairs: bool,
+
+    /// Leave out struct fields whose value is written as nil, such as a
+    /// `None`, instead of writing `(field . #nil)`.
+    pub skip_none_fields: bool,
+
+    /// Write a unit struct such as `struct Unit;` as its name, `Unit`, rather
+    /// than as the empty list `()`. The deserializer accepts either form.
+    pub unit_struct_as_name: bool,
+
+    /// How the entries of maps and structs are written.
+    pub map_style: MapStyle,
+
+    /// How a unit enum variant such as `Color::Red` is written.
+    pub unit_variant_style: UnitVariantStyle,
+
+    /// Start a new line between two elements rather than let a line run past
+    /// this many columns, indenting the continuation to just inside the
+    /// enclosing bracket. An element wider than the limit still gets a line
+    /// to itself. Honored by `to_string_with_config`.
+    pub wrap_width: Option<usize>,
+
+    /// How `true` and `false` are spelled. `None` leaves it to the
+    /// `Formatter`, which writes `#t` and `#f` unless it is the Emacs Lisp
+    /// one.
+    pub bool_style: Option<BoolStyle>,
+
+    /// Symbols always written bare, such as `+` or `1+`, for a reader that
+    /// takes them as symbols even though they would otherwise be written
+    /// between `|` for not starting with a letter.
+    pub bare_symbols: Vec<String>,
+
+    /// Characters that cause a symbol containing them to be written between
+    /// `|`, in addition to whitespace, brackets, `"`, `|` and `\\`. Does not
+    /// apply to `bare_symbols`.
+    pub quote_symbol_chars: Vec<char>,
+
+    /// Write every character of a string above U+007F as a `\uXXXX` escape,
+    /// using a surrogate pair outside the Basic Multilingual Plane, so that
+    /// the output is pure ASCII.
+    pub ascii_only: bool,
+
+    /// Write a `Sexp::Vector` as the Scheme vector literal `#(a b)` rather
+    /// than as `[a b]`, for readers set up with `ParseConfig::hash_vectors`
+    /// but not `square_brackets`. A `Sexp` does not remember which syntax a
+    /// vector was read from, so the choice is made here.
+    pub hash_vectors: bool,
+}
+
+impl Default for SerializeConfig {
+    fn default() -> Self {
+        SerializeConfig {
+            two_tuples_as_pairs: false,
+            skip_none_fields: false,
+            unit_struct_as_name: false,
+            map_style: MapStyle::DottedPair,
+            unit_variant_style: UnitVariantStyle::String,
+            wrap_width: None,
+            bool_style: None,
+            bare_symbols: Vec::new(),
+            quote_symbol_chars: Vec::new(),
+            ascii_only: false,
+            hash_vectors: false,
+        }
+    }
+}
+
+/// The layout of a map or struct entry in S-expression text.
+///
+/// The deserializer accepts every style regardless of this setting.
+///
+/// The default, `DottedPair`, is the one representation shared by
+/// `to_string` and `to_value`, and read back by both `from_str` and
+/// `from_value` into either a map or a struct: an entry always keeps its
+/// dot, so `(tags . (a b))` and `(name . #nil)` are never mistaken for the
+/// three element list `(tags a b)` or the one element list `(name)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapStyle {
+    /// An alist of dotted pairs, `((key . value) ...)`.
+    DottedPair,
+    /// A list of two-element lists, `((key value) ...)`, for consumers that
+    /// do not understand dotted pairs.
+    NestedList,
+    /// A property list with keyword keys, `(#:key value ...)`. The keyword
+    /// spelling is up to the `Formatter`.
+    Plist,
+}
+
+/// The form a unit enum variant is written in. Serde's `rename` and
+/// `rename_all` attributes decide the name, so `#[serde(rename_all =
+/// "kebab-case")]` turns `LightBlue` into `light-blue` in every style.
+///
+/// The deserializer accepts all three forms regardless of this setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitVariantStyle {
+    /// A string, `"Red"`.
+    String,
+    /// A symbol, `Red`.
+    Symbol,
+    /// A keyword, `#:Red`, or `:Red` from the Emacs Lisp formatter.
+    Keyword,
+}
+
+/// How the deserializer treats a `#` dispatch it does not recognize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownHashPolicy {
+    /// Fail with an `ExpectedSomeIdent` error.
+    Error,
+    /// Read the whole token, `#` included, as a symbol, so `#zap` reads as
+    /// the symbol `#zap`.
+    Symbol,
+}
+
+/// The keyword syntax the deserializer accepts besides `#:name`. The colon
+/// is never part of the keyword's name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeywordStyle {
+    /// Only `#:name`. A leading `:` is a syntax error and a trailing `:` is
+    /// part of a symbol.
+    Hash,
+    /// `:name`, as Clojure and Emacs Lisp write keywords.
+    LeadingColon,
+    /// `name:`, as some Scheme readers write keywords.
+    TrailingColon,
+}
+
+/// The spelling of a boolean in S-expression text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolStyle {
+    /// `#t` and `#f`, as Scheme writes them.
+    HashTF,
+    /// `true` and `false`, as Clojure writes them.
+    TrueFalse,
+    /// `t` and `nil`, as Emacs Lisp and Common Lisp write them.
+    TNil,
+}
+
+impl BoolStyle {
+    /// The text `value` is written as in this style.
+    pub fn spelling(&self, value: bool) -> &'static str {
+        match (*self, value) {
+            (BoolStyle::HashTF, true) => "#t",
+            (BoolStyle::HashTF, false) => "#f",
+            (BoolStyle::TrueFalse, true) => "true",
+            (BoolStyle::TrueFalse, false) => "false",
+            (BoolStyle::TNil, true) => "t",
+            (BoolStyle::TNil, false) => "nil",
+        }
+    }
+}
+
+/// Options controlling the layout produced by `sexpr::reformat`.
+///
+/// ```rust,ignore
+/// use sexpr::PrettyConfig;
+///
+/// let mut config = PrettyConfig::default();
+/// config.width = 40;
+///
+/// let tidy = sexpr::reformat("(a\n b   c)", &config).unwrap();
+/// assert_eq!(tidy, "(a b c)\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrettyConfig {
+    /// Spaces to indent each element of a list that does not fit on one
+    /// line, relative to its opening bracket.
+    pub indent: usize,
+
+    /// The column a list must end by to be written on a single line.
+    pub width: usize,
+
+    /// The syntax of the text being reformatted. Comments are always kept,
+    /// whatever its `retain_comments` says.
+    pub dialect: ParseConfig,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            indent: 2,
+            width: 80,
+            dialect: ParseConfig::default(),
+        }
+    }
+}
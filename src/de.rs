@@ -9,7 +9,10 @@
 //! Deserialize S-expression data to a Rust data structure.
 
 use std::{i32, u64};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
+use std::str;
 use std::marker::PhantomData;
 
 use serde::de::{self, Unexpected};
@@ -17,10 +20,16 @@ use serde::de::{self, Unexpected};
 use super::error::{Error, ErrorCode, Result};
 
 use read::{self, Reference};
+use config::{symbol_delimiters, symbol_to_bool, KeywordStyle, ParseConfig, UnknownHashPolicy};
 
 pub use read::{Read, IoRead, SliceRead, StrRead};
 use atom::Atom;
-use sexp::Sexp;
+use canonical;
+use sexp::{self, visit_tagged_name, BorrowedSexp, Sexp, SexpDeserializer, KEYWORD_TOKEN, SEXP_TOKEN,
+           SYMBOL_TOKEN};
+
+/// Opens and closes a raw string when `ParseConfig::raw_strings` is set.
+const RAW_STRING: &'static [u8] = b"\"\"\"";
 
 
 //////////////////////////////////////////////////////////////////////////////
@@ -30,6 +39,34 @@ pub struct Deserializer<R> {
     read: R,
     str_buf: Vec<u8>,
     remaining_depth: u8,
+    config: ParseConfig,
+    // Datums recorded by `#n=` labels.
+    labels: HashMap<u64, Sexp>,
+    // Comments skipped so far, when `retain_comments` is set.
+    comments: Vec<Comment>,
+}
+
+/// A comment skipped by the deserializer, kept when
+/// `ParseConfig::retain_comments` is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    /// Line of the first character of the comment, starting from 1.
+    pub line: usize,
+    /// Column of the first character of the comment, starting from 1.
+    pub column: usize,
+    /// What the comment holds.
+    pub body: CommentBody,
+}
+
+/// The contents of a `Comment`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommentBody {
+    /// A comment running to the end of the line, prefix included.
+    Line(String),
+    /// A `#|...|#` block comment, delimiters included.
+    Block(String),
+    /// The datum commented out by `#;`.
+    Datum(Sexp),
 }
 
 impl<'de, R> Deserializer<R>
@@ -45,12 +82,36 @@ impl<'de, R> Deserializer<R>
     ///   - Deserializer::from_bytes
     ///   - Deserializer::from_reader
     pub fn new(read: R) -> Self {
+        Deserializer::with_config(read, ParseConfig::default())
+    }
+
+    /// Create a S-expression deserializer that reads the dialect described by
+    /// `config`.
+    pub fn with_config(read: R, config: ParseConfig) -> Self {
         Deserializer {
             read: read,
             str_buf: Vec::with_capacity(128),
             remaining_depth: 128,
+            config: config,
+            labels: HashMap::new(),
+            comments: Vec::new(),
         }
     }
+
+    /// The comments skipped so far, in the order they appear in the input.
+    /// Always empty unless `ParseConfig::retain_comments` is set.
+    ///
+    /// ```rust,ignore
+    /// let mut config = ParseConfig::default();
+    /// config.retain_comments = true;
+    ///
+    /// let mut de = Deserializer::with_config(StrRead::new("; note\n(a)"), config);
+    /// let _: Sexp = Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(de.comments()[0].body, CommentBody::Line("; note".to_owned()));
+    /// ```
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
 }
 
 impl<R> Deserializer<read::IoRead<R>>
@@ -100,6 +161,14 @@ impl Number {
             Number::I64(x) => visitor.visit_i64(x),
         }
     }
+
+    fn into_sexp_number(self) -> Option<sexp::Number> {
+        match self {
+            Number::F64(x) => sexp::Number::from_f64(x),
+            Number::U64(x) => Some(x.into()),
+            Number::I64(x) => Some(x.into()),
+        }
+    }
 }
 
 impl<'de, R: Read<'de>> Deserializer<R> {
@@ -124,6 +193,7 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         StreamDeserializer {
             de: self,
             offset: offset,
+            forms: 0,
             output: PhantomData,
             lifetime: PhantomData,
         }
@@ -169,6 +239,49 @@ impl<'de, R: Read<'de>> Deserializer<R> {
                 Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') => {
                     self.eat_char();
                 }
+                Some(b',') if self.config.commas_are_whitespace => {
+                    self.eat_char();
+                }
+                Some(ch) if try!(self.at_line_comment(ch)) => {
+                    let pos = self.read.peek_position();
+                    let mut text = Vec::new();
+                    loop {
+                        match try!(self.next_char()) {
+                            Some(b'\n') | None => break,
+                            Some(ch) if self.config.retain_comments => text.push(ch),
+                            Some(_) => {}
+                        }
+                    }
+                    if text.last() == Some(&b'\r') {
+                        text.pop();
+                    }
+                    self.retain_comment(pos, CommentBody::Line, &text);
+                }
+                Some(b'#') if self.config.block_comments
+                    && try!(self.read.starts_with(b"#|").map_err(Error::io)) => {
+                    try!(self.parse_block_comment());
+                }
+                Some(b'#') if self.config.datum_comments
+                    && try!(self.read.starts_with(b"#;").map_err(Error::io)) => {
+                    let pos = self.read.peek_position();
+                    // `#; #; a` nests a datum comment in another's datum.
+                    self.remaining_depth -= 1;
+                    if self.remaining_depth == 0 {
+                        return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+                    }
+                    self.eat_char();
+                    self.eat_char();
+                    let datum = de::Deserialize::deserialize(&mut *self);
+                    self.remaining_depth += 1;
+                    let datum: Sexp = try!(datum);
+                    if self.config.retain_comments {
+                        self.comments.push(Comment {
+                            line: pos.line,
+                            column: pos.column,
+                            body: CommentBody::Datum(datum),
+                        });
+                    }
+                }
                 other => {
                     return Ok(other);
                 }
@@ -176,7 +289,100 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         }
     }
 
-    fn parse_value<V>(&mut self, visitor: V) -> Result<V::Value>
+    /// Skips a `#|...|#` block comment, counting any nested ones, which begins
+    /// at the current position.
+    fn parse_block_comment(&mut self) -> Result<()> {
+        let pos = self.read.peek_position();
+        self.eat_char();
+        self.eat_char();
+        let mut text = b"#|".to_vec();
+        let mut depth = 1;
+        while depth > 0 {
+            let ch = match try!(self.next_char()) {
+                Some(ch) => ch,
+                None => return Err(self.error(ErrorCode::EofWhileParsingComment)),
+            };
+            text.push(ch);
+            match (ch, try!(self.peek())) {
+                (b'|', Some(b'#')) => depth -= 1,
+                (b'#', Some(b'|')) => depth += 1,
+                _ => continue,
+            }
+            self.eat_char();
+            text.push(if ch == b'|' { b'#' } else { b'|' });
+        }
+        self.retain_comment(pos, CommentBody::Block, &text);
+        Ok(())
+    }
+
+    /// Records a comment starting at `pos` if `retain_comments` is set.
+    fn retain_comment<F>(&mut self, pos: read::Position, body: F, text: &[u8])
+        where
+        F: FnOnce(String) -> CommentBody,
+    {
+        if self.config.retain_comments {
+            self.comments.push(Comment {
+                line: pos.line,
+                column: pos.column,
+                body: body(String::from_utf8_lossy(text).into_owned()),
+            });
+        }
+    }
+
+    /// Returns true if one of the configured line comment prefixes begins at
+    /// the current position. `ch` is the byte returned by peek().
+    fn at_line_comment(&mut self, ch: u8) -> Result<bool> {
+        for prefix in &self.config.line_comment_prefixes {
+            let prefix = prefix.as_bytes();
+            if prefix.first() != Some(&ch)
+                || !try!(self.read.starts_with(prefix).map_err(Error::io))
+            {
+                continue;
+            }
+            if ch != b'#' {
+                return Ok(true);
+            }
+            match try!(self.read.peek_nth(prefix.len()).map_err(Error::io)) {
+                Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') | None => return Ok(true),
+                Some(_) => {}
+            }
+        }
+        Ok(false)
+    }
+
+    /// Consumes the opening `"""` of a raw string if `raw_strings` is set and
+    /// one begins at the current position.
+    fn at_raw_string(&mut self) -> Result<bool> {
+        if !self.config.raw_strings || !try!(self.read.starts_with(RAW_STRING).map_err(Error::io)) {
+            return Ok(false);
+        }
+        for _ in RAW_STRING {
+            self.read.discard();
+        }
+        Ok(true)
+    }
+
+    /// Consumes the opening delimiter of a string if one begins at the current
+    /// position, returning the configured `(open, close)` pair.
+    fn parse_string_open(&mut self) -> Result<Option<(char, char)>> {
+        let mut buf = [0; 4];
+        for &(open, close) in &self.config.string_delimiters {
+            let open_bytes = open.encode_utf8(&mut buf).as_bytes();
+            if try!(self.read.starts_with(open_bytes).map_err(Error::io)) {
+                for _ in open_bytes {
+                    self.read.discard();
+                }
+                return Ok(Some((open, close)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses and visits a single value. When `exact` is set the visitor is
+    /// `Sexp::deserialize`, which sees symbols and keywords as tagged names
+    /// and every list as it was written; see `SEXP_TOKEN`. Otherwise symbols
+    /// and keywords are visited as their names.
+    fn parse_value<V>(&mut self, exact: bool, visitor: V) -> Result<V::Value>
         where
         V: de::Visitor<'de>,
     {
@@ -187,7 +393,49 @@ impl<'de, R: Read<'de>> Deserializer<R> {
             }
         };
 
+        if try!(self.at_raw_string()) {
+            self.str_buf.clear();
+            let value = match try!(self.read.parse_raw_str_until(&mut self.str_buf, RAW_STRING)) {
+                Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Reference::Copied(s) => visitor.visit_str(s),
+            };
+            return value.map_err(|err: Error| err.fix_position(|code| self.error(code)));
+        }
+
+        if let Some((open, close)) = try!(self.parse_string_open()) {
+            self.str_buf.clear();
+            let value = if open == '"' && close == '"' {
+                match try!(self.read.parse_str(&mut self.str_buf)) {
+                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Reference::Copied(s) => visitor.visit_str(s),
+                }
+            } else {
+                let mut buf = [0; 4];
+                let close = close.encode_utf8(&mut buf).as_bytes();
+                match try!(self.read.parse_str_until(&mut self.str_buf, close)) {
+                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Reference::Copied(s) => visitor.visit_str(s),
+                }
+            };
+            return value.map_err(|err: Error| err.fix_position(|code| self.error(code)));
+        }
+
+        if self.config.aggressive_number_detection {
+            match peek {
+                b'+' | b'-' | b'.' | b'0'...b'9' => {
+                    return self.parse_numeric_token(exact, visitor)
+                        .map_err(|err| err.fix_position(|code| self.error(code)));
+                }
+                _ => {}
+            }
+        }
+
         let value = match peek {
+            b'#' if try!(self.at_hash_vector()) => {
+                self.eat_char();
+                let vector = try!(self.parse_vector(b')'));
+                self.visit_sexp(vector, exact, visitor)
+            }
             b'#' => {
                 self.eat_char();
                 match try!(self.next_char()) {
@@ -195,50 +443,105 @@ impl<'de, R: Read<'de>> Deserializer<R> {
                     Some(b'f') => visitor.visit_bool(false),
                     Some(b'n') => {
                         try!(self.parse_ident(b"il"));
-                        visitor.visit_bool(true)
+                        visitor.visit_unit()
                     },
+                    Some(b':') => {
+                        self.str_buf.clear();
+                        let delimiters = symbol_delimiters(&self.config);
+                        let name = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                        visit_name(KEYWORD_TOKEN, name, exact, visitor)
+                    },
+                    Some(c @ b'0'...b'9') if self.config.datum_labels => {
+                        let datum = try!(self.parse_datum_label(c));
+                        self.visit_sexp(datum, exact, visitor)
+                    }
+                    Some(c) if self.config.unknown_hash_policy == UnknownHashPolicy::Symbol
+                        && b'!' <= c && c <= b'~'
+                        && !symbol_delimiters(&self.config).contains(&c) => {
+                        self.str_buf.clear();
+                        let delimiters = symbol_delimiters(&self.config);
+                        let rest = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                        let name = format!("#{}{}", c as char, &*rest);
+                        if exact {
+                            visit_tagged_name(SYMBOL_TOKEN, name, visitor)
+                        } else {
+                            visitor.visit_string(name)
+                        }
+                    }
                     Some(_) => Err(self.peek_error(ErrorCode::ExpectedSomeIdent)),
                     None => Err(self.peek_error(ErrorCode::EofWhileParsingValue))
                 }
             }
-            b'-' => {
-                self.eat_char();
-                try!(self.parse_integer(false)).visit(visitor)
-            }
-            b'0'...b'9' => try!(self.parse_integer(true)).visit(visitor),
-            b'"' => {
-                self.eat_char();
-                self.str_buf.clear();
-                match try!(self.read.parse_str(&mut self.str_buf)) {
-                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
-                    Reference::Copied(s) => visitor.visit_str(s),
+            // A `.` that separates the car and cdr of a pair never gets here,
+            // see `at_pair_dot`.
+            b'.' => {
+                let next = try!(self.read.peek_nth(1).map_err(Error::io));
+                if next.map_or(false, |c| b'0' <= c && c <= b'9') {
+                    let n = try!(self.parse_number_literal());
+                    self.visit_number(n, visitor)
+                } else {
+                    self.str_buf.clear();
+                    let delimiters = symbol_delimiters(&self.config);
+                    let name = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                    visit_name(SYMBOL_TOKEN, name, exact, visitor)
                 }
             }
-            b'(' => {
-                self.remaining_depth -= 1;
-                if self.remaining_depth == 0 {
-                    return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+            b'-' | b'0'...b'9' => {
+                match try!(self.parse_number_literal()) {
+                    // A length prefix followed by a colon introduces a
+                    // canonical verbatim string such as `3:abc` or `0:`.
+                    Number::U64(len) if try!(self.peek()) == Some(b':') => {
+                        self.eat_char();
+                        try!(self.parse_verbatim(len));
+                        match str::from_utf8(&self.str_buf) {
+                            Ok(s) => visitor.visit_str(s),
+                            Err(_) => Err(self.error(ErrorCode::InvalidUnicodeCodePoint)),
+                        }
+                    }
+                    n => self.visit_number(n, visitor),
                 }
-
+            }
+            b'(' if exact => {
+                let list = try!(self.parse_list());
+                self.visit_sexp(list, exact, visitor)
+            }
+            b'(' => self.parse_any_list(visitor),
+            b'[' if self.config.square_brackets => {
+                let vector = try!(self.parse_vector(b']'));
+                self.visit_sexp(vector, exact, visitor)
+            }
+            b':' if self.config.keyword_style == KeywordStyle::LeadingColon => {
                 self.eat_char();
-                let ret = visitor.visit_seq(SeqAccess::new(self));
-
-                self.remaining_depth += 1;
-
-                try!(self.parse_whitespace());
-
-                match (ret, self.end_seq()) {
-                    (Ok(ret), Ok(())) => Ok(ret),
-                    (Err(err), _) | (_, Err(err)) => Err(err),
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                let name = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                if name.is_empty() {
+                    return Err(self.peek_error(ErrorCode::ExpectedSomeIdent));
                 }
+                visit_name(KEYWORD_TOKEN, name, exact, visitor)
             }
             b'a' ... b'z' | b'A' ... b'Z' => {
                 self.str_buf.clear();
-                match try!(self.read.parse_symbol(&mut self.str_buf)) {
-                    Reference::Borrowed(s) => visitor.visit_newtype_struct(Atom::from_str(s)),
-                    Reference::Copied(s) => visitor.visit_newtype_struct(Atom::from_str(s)),
+                let delimiters = symbol_delimiters(&self.config);
+                let name = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                match name.len().checked_sub(1) {
+                    Some(end) if self.config.keyword_style == KeywordStyle::TrailingColon
+                        && name.ends_with(':') => {
+                        let name = match name {
+                            Reference::Borrowed(s) => Reference::Borrowed(&s[..end]),
+                            Reference::Copied(s) => Reference::Copied(&s[..end]),
+                        };
+                        visit_name(KEYWORD_TOKEN, name, exact, visitor)
+                    }
+                    _ => visit_name(SYMBOL_TOKEN, name, exact, visitor),
                 }
             }
+            b'|' => {
+                self.eat_char();
+                self.str_buf.clear();
+                let name = try!(self.read.parse_str_until(&mut self.str_buf, b"|"));
+                visit_name(SYMBOL_TOKEN, name, exact, visitor)
+            }
             _ => Err(self.peek_error(ErrorCode::ExpectedSomeValue)),
         };
 
@@ -254,6 +557,466 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         }
     }
 
+    /// Visits a value that had to be built as a `Sexp`, exactly for
+    /// `Sexp::deserialize` or as `deserialize_any` would otherwise.
+    fn visit_sexp<V>(&self, sexp: Sexp, exact: bool, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        let de = SexpDeserializer::new(sexp, Some(&self.config));
+        if exact {
+            de::Deserializer::deserialize_newtype_struct(de, SEXP_TOKEN, visitor)
+        } else {
+            de::Deserializer::deserialize_any(de, visitor)
+        }
+    }
+
+    /// Visits a list for `deserialize_any` as a sequence read straight from
+    /// the input, so that its strings can be borrowed.
+    ///
+    /// A list whose first element is a list may be an alist, which is visited
+    /// as a map, and the empty list may be nil. Those, and every list when
+    /// `concat_adjacent_strings` is set, are still built as a `Sexp` first.
+    fn parse_any_list<V>(&mut self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+        }
+        self.eat_char();
+
+        let buffered = match try!(self.parse_whitespace()) {
+            Some(b'(') | Some(b')') => true,
+            _ => self.config.concat_adjacent_strings,
+        };
+        if buffered {
+            let list = self.parse_list_elements();
+            self.remaining_depth += 1;
+            return self.visit_sexp(try!(list), false, visitor);
+        }
+
+        let ret = visitor.visit_seq(SeqAccess::new(self, None));
+
+        self.remaining_depth += 1;
+
+        match (ret, self.end_seq()) {
+            (Ok(ret), Ok(())) => Ok(ret),
+            (Err(err), _) | (_, Err(err)) => Err(err),
+        }
+    }
+
+    /// Parses a parenthesized list into a `Sexp`. A dotted tail such as
+    /// `(a b . c)` becomes the nested pairs `(a . (b . c))`.
+    fn parse_list(&mut self) -> Result<Sexp> {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+        }
+
+        self.eat_char();
+        let ret = self.parse_list_elements();
+
+        self.remaining_depth += 1;
+
+        ret
+    }
+
+    fn parse_list_elements(&mut self) -> Result<Sexp> {
+        let mut elements = Vec::new();
+        loop {
+            match try!(self.parse_whitespace()) {
+                Some(b')') => {
+                    self.eat_char();
+                    if elements.is_empty() && self.config.empty_list_is_nil {
+                        return Ok(Sexp::Nil);
+                    }
+                    return Ok(Sexp::List(elements));
+                }
+                Some(b'.') if try!(self.at_pair_dot()) => {
+                    if elements.is_empty() {
+                        return Err(self.peek_error(ErrorCode::ExpectedSomeValue));
+                    }
+                    self.eat_char();
+                    let mut tail = try!(self.parse_sexp());
+                    try!(self.end_seq());
+                    while let Some(car) = elements.pop() {
+                        tail = Sexp::Pair(Some(Box::new(car)), Some(Box::new(tail)));
+                    }
+                    return Ok(tail);
+                }
+                Some(_) => {
+                    let elt = try!(self.parse_sexp());
+                    self.push_element(&mut elements, elt);
+                }
+                None => return Err(self.peek_error(ErrorCode::EofWhileParsingList)),
+            }
+        }
+    }
+
+    /// Appends `elt` to the elements of a list or vector, joining it onto the
+    /// string before it instead if both are strings and
+    /// `concat_adjacent_strings` is set.
+    fn push_element(&self, elements: &mut Vec<Sexp>, elt: Sexp) {
+        if self.config.concat_adjacent_strings {
+            let last = elements.last_mut();
+            if let (Some(&mut Sexp::Atom(ref mut last)), &Sexp::Atom(ref next)) = (last, &elt) {
+                if last.is_string() && next.is_string() {
+                    *last = Atom::into_string(format!("{}{}", last.as_str(), next.as_str()));
+                    return;
+                }
+            }
+        }
+        elements.push(elt);
+    }
+
+    /// Parses the rest of a `#n=` label definition or `#n#` reference, whose
+    /// first digit has been consumed, into the labelled datum.
+    fn parse_datum_label(&mut self, first: u8) -> Result<Sexp> {
+        let mut n = (first - b'0') as u64;
+        loop {
+            match try!(self.next_char()) {
+                Some(c @ b'0'...b'9') => {
+                    n = match n.checked_mul(10).and_then(|n| n.checked_add((c - b'0') as u64)) {
+                        Some(n) => n,
+                        None => return Err(self.error(ErrorCode::NumberOutOfRange)),
+                    };
+                }
+                Some(b'=') => {
+                    // `#0=#1=...` nests without opening a list, so it has to
+                    // count against the recursion limit itself.
+                    self.remaining_depth -= 1;
+                    if self.remaining_depth == 0 {
+                        return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+                    }
+                    let datum = self.parse_sexp();
+                    self.remaining_depth += 1;
+                    let datum = try!(datum);
+                    self.labels.insert(n, datum.clone());
+                    return Ok(datum);
+                }
+                Some(b'#') => {
+                    return match self.labels.get(&n).cloned() {
+                        Some(datum) => Ok(datum),
+                        None => Err(self.error(ErrorCode::UndefinedDatumLabel)),
+                    };
+                }
+                Some(_) => return Err(self.error(ErrorCode::ExpectedSomeIdent)),
+                None => return Err(self.error(ErrorCode::EofWhileParsingValue)),
+            }
+        }
+    }
+
+    /// Whether a `#(` vector literal begins at the current position.
+    fn at_hash_vector(&mut self) -> Result<bool> {
+        if !self.config.hash_vectors {
+            return Ok(false);
+        }
+        self.read.starts_with(b"#(").map_err(Error::io)
+    }
+
+    /// Parses a square bracketed list, or the part of a `#(` vector literal
+    /// after the `#`, into a `Sexp::Vector`. `close` is the closing bracket.
+    fn parse_vector(&mut self, close: u8) -> Result<Sexp> {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+        }
+
+        self.eat_char();
+        let ret = self.parse_vector_elements(close);
+
+        self.remaining_depth += 1;
+
+        ret
+    }
+
+    fn parse_vector_elements(&mut self, close: u8) -> Result<Sexp> {
+        let mut elements = Vec::new();
+        loop {
+            match try!(self.parse_whitespace()) {
+                Some(c) if c == close => {
+                    self.eat_char();
+                    return Ok(Sexp::Vector(elements));
+                }
+                Some(_) => {
+                    let elt = try!(self.parse_sexp());
+                    self.push_element(&mut elements, elt);
+                }
+                None => return Err(self.peek_error(ErrorCode::EofWhileParsingList)),
+            }
+        }
+    }
+
+    fn parse_sexp(&mut self) -> Result<Sexp> {
+        match try!(self.parse_whitespace()) {
+            Some(b'(') => self.parse_list(),
+            Some(b'[') if self.config.square_brackets => self.parse_vector(b']'),
+            Some(b'#') if try!(self.at_hash_vector()) => {
+                self.eat_char();
+                self.parse_vector(b')')
+            }
+            _ => de::Deserialize::deserialize(self),
+        }
+    }
+
+    /// Parses a single value into a `BorrowedSexp`, borrowing its atoms from
+    /// the input where the reader allows it.
+    fn parse_borrowed_sexp(&mut self) -> Result<BorrowedSexp<'de>> {
+        let peek = match try!(self.parse_whitespace()) {
+            Some(b) => b,
+            None => {
+                return Err(self.peek_error(ErrorCode::EofWhileParsingValue));
+            }
+        };
+
+        if try!(self.at_raw_string()) {
+            self.str_buf.clear();
+            let s = into_cow(try!(self.read.parse_raw_str_until(&mut self.str_buf, RAW_STRING)));
+            return Ok(BorrowedSexp::String(s));
+        }
+
+        if let Some((open, close)) = try!(self.parse_string_open()) {
+            self.str_buf.clear();
+            let s = if open == '"' && close == '"' {
+                into_cow(try!(self.read.parse_str(&mut self.str_buf)))
+            } else {
+                let mut buf = [0; 4];
+                let close = close.encode_utf8(&mut buf).as_bytes();
+                into_cow(try!(self.read.parse_str_until(&mut self.str_buf, close)))
+            };
+            return Ok(BorrowedSexp::String(s));
+        }
+
+        match peek {
+            b'(' => self.parse_borrowed_list(false),
+            b'[' if self.config.square_brackets => self.parse_borrowed_list(true),
+            b'#' if try!(self.read.starts_with(b"#:").map_err(Error::io)) => {
+                self.read.discard();
+                self.read.discard();
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                let name = into_cow(try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters)));
+                Ok(BorrowedSexp::Keyword(name))
+            }
+            b'a' ... b'z' | b'A' ... b'Z' => {
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                let name = into_cow(try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters)));
+                Ok(BorrowedSexp::Symbol(name))
+            }
+            b'|' => {
+                self.eat_char();
+                self.str_buf.clear();
+                let name = into_cow(try!(self.read.parse_str_until(&mut self.str_buf, b"|")));
+                Ok(BorrowedSexp::Symbol(name))
+            }
+            // Numbers, booleans, nil and verbatim strings own their contents
+            // anyway.
+            _ => de::Deserialize::deserialize(&mut *self).map(|sexp: Sexp| BorrowedSexp::from(sexp)),
+        }
+    }
+
+    /// Parses a list or, when `vector` is set, a square bracketed list into
+    /// a `BorrowedSexp`.
+    fn parse_borrowed_list(&mut self, vector: bool) -> Result<BorrowedSexp<'de>> {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+        }
+
+        self.eat_char();
+        let ret = self.parse_borrowed_elements(vector);
+
+        self.remaining_depth += 1;
+
+        ret
+    }
+
+    fn parse_borrowed_elements(&mut self, vector: bool) -> Result<BorrowedSexp<'de>> {
+        let mut elements = Vec::new();
+        loop {
+            match try!(self.parse_whitespace()) {
+                Some(b')') if !vector => {
+                    self.eat_char();
+                    if elements.is_empty() && self.config.empty_list_is_nil {
+                        return Ok(BorrowedSexp::Nil);
+                    }
+                    return Ok(BorrowedSexp::List(elements));
+                }
+                Some(b']') if vector => {
+                    self.eat_char();
+                    return Ok(BorrowedSexp::Vector(elements));
+                }
+                Some(b'.') if !vector && try!(self.at_pair_dot()) => {
+                    if elements.is_empty() {
+                        return Err(self.peek_error(ErrorCode::ExpectedSomeValue));
+                    }
+                    self.eat_char();
+                    let mut tail = try!(self.parse_borrowed_sexp());
+                    try!(self.end_seq());
+                    while let Some(car) = elements.pop() {
+                        tail = BorrowedSexp::Pair(Some(Box::new(car)), Some(Box::new(tail)));
+                    }
+                    return Ok(tail);
+                }
+                Some(_) => elements.push(try!(self.parse_borrowed_sexp())),
+                None => return Err(self.peek_error(ErrorCode::EofWhileParsingList)),
+            }
+        }
+    }
+
+    /// Whether the `.` at the current position separates the car and cdr of
+    /// a dotted pair, rather than beginning an atom.
+    fn at_pair_dot(&mut self) -> Result<bool> {
+        for dot in &[&b". "[..], b".\n", b".\t", b".\r", b".(", b".)"] {
+            if try!(self.read.starts_with(dot).map_err(Error::io)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Reads the `len` octets of a canonical verbatim string into `str_buf`.
+    fn parse_verbatim(&mut self, len: u64) -> Result<()> {
+        self.str_buf.clear();
+        for _ in 0..len {
+            match try!(self.next_char()) {
+                Some(b) => self.str_buf.push(b),
+                None => return Err(self.error(ErrorCode::EofWhileParsingString)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a token that begins like a number, for
+    /// `ParseConfig::aggressive_number_detection`. The token is visited as a
+    /// number if the whole of it is one, and as a symbol otherwise.
+    fn parse_numeric_token<V>(&mut self, exact: bool, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.str_buf.clear();
+        loop {
+            match try!(self.peek_or_null()) {
+                c @ b'0'...b'9' => {
+                    self.eat_char();
+                    self.str_buf.push(c);
+                }
+                _ => break,
+            }
+        }
+        if !self.str_buf.is_empty() && try!(self.peek()) == Some(b':') {
+            return self.visit_verbatim(visitor);
+        }
+
+        let delimiters = symbol_delimiters(&self.config);
+        loop {
+            match try!(self.peek()) {
+                Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') | Some(b')') | Some(b']') |
+                None => break,
+                Some(c) if delimiters.contains(&c) => break,
+                Some(c) => {
+                    self.eat_char();
+                    self.str_buf.push(c);
+                }
+            }
+        }
+
+        let token = match str::from_utf8(&self.str_buf) {
+            Ok(token) => token,
+            Err(_) => return Err(self.error(ErrorCode::InvalidUnicodeCodePoint)),
+        };
+        // Unlike the plain reader, a leading `+` is allowed here.
+        let unsigned = match token.as_bytes().first() {
+            Some(&b'+') if !token[1..].starts_with('-') => &token[1..],
+            _ => token,
+        };
+        match parse_number_bytes(unsigned.as_bytes(), self.config.numeric_underscores) {
+            Ok(n) => de::Deserializer::deserialize_any(n, visitor),
+            Err(_) if exact => visit_tagged_name(SYMBOL_TOKEN, String::from(token), visitor),
+            Err(_) => visitor.visit_str(token),
+        }
+    }
+
+    /// Reads and visits a verbatim string such as `3:abc`, whose length has
+    /// been collected in `str_buf` and whose `:` is next.
+    fn visit_verbatim<V>(&mut self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        let len = match str::from_utf8(&self.str_buf).ok().and_then(|len| len.parse().ok()) {
+            Some(len) => len,
+            None => return Err(self.error(ErrorCode::NumberOutOfRange)),
+        };
+        self.eat_char();
+        try!(self.parse_verbatim(len));
+        match str::from_utf8(&self.str_buf) {
+            Ok(s) => visitor.visit_str(s),
+            Err(_) => Err(self.error(ErrorCode::InvalidUnicodeCodePoint)),
+        }
+    }
+
+    /// Visits a number literal as the text it was written as, so it can be
+    /// read into a `String` or a decimal type without passing through `f64`.
+    /// A length prefix such as `3:abc` is still read as a verbatim string.
+    fn parse_number_text<V>(&mut self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.str_buf.clear();
+        let n = try!(NumberText { de: self }.parse_number_literal());
+
+        if let Number::U64(len) = n {
+            if try!(self.peek()) == Some(b':') {
+                self.eat_char();
+                try!(self.parse_verbatim(len));
+                return match str::from_utf8(&self.str_buf) {
+                    Ok(s) => visitor.visit_str(s),
+                    Err(_) => Err(self.error(ErrorCode::InvalidUnicodeCodePoint)),
+                };
+            }
+        }
+
+        // Only ASCII was pushed.
+        visitor.visit_string(String::from(str::from_utf8(&self.str_buf).unwrap()))
+    }
+
+    /// Visits a parsed number, first passing any suffix letters to the
+    /// configured `number_suffix` hook.
+    fn visit_number<V>(&mut self, n: Number, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        let hook = match self.config.number_suffix {
+            Some(hook) => hook,
+            None => return n.visit(visitor),
+        };
+
+        self.str_buf.clear();
+        loop {
+            match try!(self.peek_or_null()) {
+                c @ b'a'...b'z' | c @ b'A'...b'Z' => {
+                    self.eat_char();
+                    self.str_buf.push(c);
+                }
+                _ => break,
+            }
+        }
+        if self.str_buf.is_empty() {
+            return n.visit(visitor);
+        }
+
+        // Only ASCII letters were pushed.
+        let suffixed = n.into_sexp_number()
+            .and_then(|n| hook(str::from_utf8(&self.str_buf).unwrap(), n));
+        match suffixed {
+            Some(n) => de::Deserializer::deserialize_any(n, visitor),
+            None => Err(self.error(ErrorCode::InvalidNumber)),
+        }
+    }
+
     fn parse_ident(&mut self, ident: &[u8]) -> Result<()> {
         for c in ident {
             if Some(*c) != try!(self.next_char()) {
@@ -264,12 +1027,119 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         Ok(())
     }
 
+    /// Reads a list as a sequence, or as a tuple of exactly `len` elements.
+    fn deserialize_list<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        let vector = match try!(self.parse_whitespace()) {
+            Some(b'(') => {
+                self.remaining_depth -= 1;
+                if self.remaining_depth == 0 {
+                    return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+                }
+
+                self.eat_char();
+                let mut access = SeqAccess::new(self, len);
+                let ret = visitor.visit_seq(&mut access);
+                let ret = match (ret, len) {
+                    (Ok(ret), Some(len)) => access.check_no_more(len).map(|()| ret),
+                    (ret, _) => ret,
+                };
+
+                self.remaining_depth += 1;
+
+                return match (ret, self.end_seq()) {
+                    (Ok(ret), Ok(())) => Ok(ret),
+                    (Err(err), _) | (_, Err(err)) => Err(err.fix_position(|code| self.error(code))),
+                };
+            }
+            Some(b'#') if try!(self.at_hash_vector()) => {
+                self.eat_char();
+                try!(self.parse_vector(b')'))
+            }
+            Some(b'[') if self.config.square_brackets => try!(self.parse_vector(b']')),
+            _ => return de::Deserializer::deserialize_any(self, visitor),
+        };
+
+        let de = SexpDeserializer::new(vector, Some(&self.config));
+        let ret = match len {
+            Some(len) => de::Deserializer::deserialize_tuple(de, len, visitor),
+            None => de::Deserializer::deserialize_seq(de, visitor),
+        };
+        ret.map_err(|err| err.fix_position(|code| self.error(code)))
+    }
+
+    fn end_seq(&mut self) -> Result<()> {
+        match try!(self.parse_whitespace()) {
+            Some(b')') => {
+                self.eat_char();
+                Ok(())
+            }
+            Some(_) => Err(self.peek_error(ErrorCode::TrailingCharacters)),
+            None => Err(self.peek_error(ErrorCode::EofWhileParsingList)),
+        }
+    }
+}
+
+/// The grammar of a number literal, shared by the reader and by
+/// `Number::from_str` so that they agree on what a number is. Implementors
+/// supply the bytes and the positions reported in errors.
+trait ParseNumber {
+    fn peek_or_null(&mut self) -> Result<u8>;
+
+    fn peek_nth(&mut self, n: usize) -> Result<Option<u8>>;
+
+    fn eat_char(&mut self);
+
+    /// Error caused by a byte already consumed.
+    fn error(&mut self, reason: ErrorCode) -> Error;
+
+    /// Error caused by the byte from peek_or_null().
+    fn peek_error(&mut self, reason: ErrorCode) -> Error;
+
+    fn numeric_underscores(&self) -> bool;
+
+    fn next_char_or_null(&mut self) -> Result<u8> {
+        let ch = try!(self.peek_or_null());
+        self.eat_char();
+        Ok(ch)
+    }
+
+    /// Reads a number such as `42`, `-7`, `.5` or `1e9`. A `-` may lead it
+    /// but a `+` may not, and a `.` must be followed by a digit.
+    fn parse_number_literal(&mut self) -> Result<Number> {
+        let pos = if try!(self.peek_or_null()) == b'-' {
+            self.eat_char();
+            false
+        } else {
+            true
+        };
+        if try!(self.peek_or_null()) == b'.' {
+            Ok(Number::F64(try!(self.parse_decimal(pos, 0, 0))))
+        } else {
+            self.parse_integer(pos)
+        }
+    }
+
+    /// Consumes a `_` digit separator, which must be followed by a digit.
+    fn eat_digit_separator(&mut self) -> Result<()> {
+        self.eat_char();
+        match try!(self.peek_or_null()) {
+            b'0'...b'9' => Ok(()),
+            _ => Err(self.peek_error(ErrorCode::InvalidNumber)),
+        }
+    }
+
     fn parse_integer(&mut self, pos: bool) -> Result<Number> {
         match try!(self.next_char_or_null()) {
             b'0' => {
                 // There can be only one leading '0'.
                 match try!(self.peek_or_null()) {
                     b'0'...b'9' => Err(self.peek_error(ErrorCode::InvalidNumber)),
+                    b'_' if self.numeric_underscores() => {
+                        Err(self.peek_error(ErrorCode::InvalidNumber))
+                    }
                     _ => self.parse_number(pos, 0),
                 }
             }
@@ -295,6 +1165,9 @@ impl<'de, R: Read<'de>> Deserializer<R> {
 
                             res = res * 10 + digit;
                         }
+                        b'_' if self.numeric_underscores() => {
+                            try!(self.eat_digit_separator());
+                        }
                         _ => {
                             return self.parse_number(pos, res);
                         }
@@ -319,12 +1192,15 @@ impl<'de, R: Read<'de>> Deserializer<R> {
                     // Ignore that possibility.
                     exponent += 1;
                 }
+                b'_' if self.numeric_underscores() => {
+                    try!(self.eat_digit_separator());
+                }
                 b'.' => {
                     return self.parse_decimal(pos, significand, exponent);
                 }
-                // b'e' | b'E' => {
-                //     return self.parse_exponent(pos, significand, exponent);
-                // }
+                b'e' | b'E' if try!(self.at_exponent()) => {
+                    return self.parse_exponent(pos, significand, exponent);
+                }
                 _ => {
                     return self.f64_from_parts(pos, significand, exponent);
                 }
@@ -335,7 +1211,9 @@ impl<'de, R: Read<'de>> Deserializer<R> {
     fn parse_number(&mut self, pos: bool, significand: u64) -> Result<Number> {
         Ok(match try!(self.peek_or_null()) {
             b'.' => Number::F64(try!(self.parse_decimal(pos, significand, 0))),
-            // b'e' | b'E' => Number::F64(try!(self.parse_exponent(pos, significand, 0))),
+            b'e' | b'E' if try!(self.at_exponent()) => {
+                Number::F64(try!(self.parse_exponent(pos, significand, 0)))
+            }
             _ => {
                 if pos {
                     Number::U64(significand)
@@ -353,7 +1231,6 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         })
     }
 
-
     fn parse_decimal(
         &mut self,
         pos: bool,
@@ -363,7 +1240,15 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         self.eat_char();
 
         let mut at_least_one_digit = false;
-        while let c @ b'0'...b'9' = try!(self.peek_or_null()) {
+        loop {
+            let c = match try!(self.peek_or_null()) {
+                c @ b'0'...b'9' => c,
+                b'_' if at_least_one_digit && self.numeric_underscores() => {
+                    try!(self.eat_digit_separator());
+                    continue;
+                }
+                _ => break,
+            };
             self.eat_char();
             let digit = (c - b'0') as u64;
             at_least_one_digit = true;
@@ -371,8 +1256,14 @@ impl<'de, R: Read<'de>> Deserializer<R> {
             if overflow!(significand * 10 + digit, u64::MAX) {
                 // The next multiply/add would overflow, so just ignore all
                 // further digits.
-                while let b'0'...b'9' = try!(self.peek_or_null()) {
-                    self.eat_char();
+                loop {
+                    match try!(self.peek_or_null()) {
+                        b'0'...b'9' => self.eat_char(),
+                        b'_' if self.numeric_underscores() => {
+                            try!(self.eat_digit_separator())
+                        }
+                        _ => break,
+                    }
                 }
                 break;
             }
@@ -386,11 +1277,98 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         }
 
         match try!(self.peek_or_null()) {
-            // b'e' | b'E' => self.parse_exponent(pos, significand, exponent),
+            b'e' | b'E' if try!(self.at_exponent()) => {
+                self.parse_exponent(pos, significand, exponent)
+            }
             _ => self.f64_from_parts(pos, significand, exponent),
         }
     }
 
+    /// Whether the `e` or `E` about to be read begins an exponent, that is a
+    /// digit or a sign and a digit follow it. Otherwise it is left for the
+    /// `number_suffix` hook, so `2em` is the number 2 with the suffix `em`.
+    fn at_exponent(&mut self) -> Result<bool> {
+        let digit = |c: Option<u8>| c.map_or(false, |c| b'0' <= c && c <= b'9');
+        Ok(match try!(self.peek_nth(1)) {
+            Some(b'+') | Some(b'-') => digit(try!(self.peek_nth(2))),
+            c => digit(c),
+        })
+    }
+
+    fn parse_exponent(
+        &mut self,
+        pos: bool,
+        significand: u64,
+        starting_exp: i32,
+    ) -> Result<f64> {
+        self.eat_char();
+
+        let pos_exp = match try!(self.peek_or_null()) {
+            b'+' => {
+                self.eat_char();
+                true
+            }
+            b'-' => {
+                self.eat_char();
+                false
+            }
+            _ => true,
+        };
+
+        // Make sure a digit follows the exponent place.
+        let mut exp = match try!(self.next_char_or_null()) {
+            c @ b'0'...b'9' => (c - b'0') as i32,
+            _ => {
+                return Err(self.error(ErrorCode::InvalidNumber));
+            }
+        };
+
+        loop {
+            match try!(self.peek_or_null()) {
+                c @ b'0'...b'9' => {
+                    self.eat_char();
+                    let digit = (c - b'0') as i32;
+
+                    if overflow!(exp * 10 + digit, i32::MAX) {
+                        return self.parse_exponent_overflow(pos, significand, pos_exp);
+                    }
+
+                    exp = exp * 10 + digit;
+                }
+                _ => break,
+            }
+        }
+
+        let final_exp = if pos_exp {
+            starting_exp.saturating_add(exp)
+        } else {
+            starting_exp.saturating_sub(exp)
+        };
+
+        self.f64_from_parts(pos, significand, final_exp)
+    }
+
+    // This cold code should not be inlined into the middle of the hot
+    // exponent-parsing loop above.
+    #[cold]
+    #[inline(never)]
+    fn parse_exponent_overflow(
+        &mut self,
+        pos: bool,
+        significand: u64,
+        pos_exp: bool,
+    ) -> Result<f64> {
+        // Error instead of +/- infinity.
+        if significand != 0 && pos_exp {
+            return Err(self.error(ErrorCode::NumberOutOfRange));
+        }
+
+        while let b'0'...b'9' = try!(self.peek_or_null()) {
+            self.eat_char();
+        }
+        Ok(if pos { 0.0 } else { -0.0 })
+    }
+
     fn f64_from_parts(
         &mut self,
         pos: bool,
@@ -425,17 +1403,121 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         }
         Ok(if pos { f } else { -f })
     }
+}
+
+impl<'de, R: Read<'de>> ParseNumber for Deserializer<R> {
+    fn peek_or_null(&mut self) -> Result<u8> {
+        Deserializer::peek_or_null(self)
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Result<Option<u8>> {
+        self.read.peek_nth(n).map_err(Error::io)
+    }
+
+    fn eat_char(&mut self) {
+        Deserializer::eat_char(self)
+    }
+
+    fn next_char_or_null(&mut self) -> Result<u8> {
+        Deserializer::next_char_or_null(self)
+    }
+
+    fn error(&mut self, reason: ErrorCode) -> Error {
+        Deserializer::error(self, reason)
+    }
+
+    fn peek_error(&mut self, reason: ErrorCode) -> Error {
+        Deserializer::peek_error(self, reason)
+    }
+
+    fn numeric_underscores(&self) -> bool {
+        self.config.numeric_underscores
+    }
+}
+
+/// Reads a number literal while keeping its text in `str_buf`, for
+/// `Deserializer::parse_number_text`.
+struct NumberText<'a, R: 'a> {
+    de: &'a mut Deserializer<R>,
+}
+
+impl<'de, 'a, R: Read<'de>> ParseNumber for NumberText<'a, R> {
+    fn peek_or_null(&mut self) -> Result<u8> {
+        self.de.peek_or_null()
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Result<Option<u8>> {
+        ParseNumber::peek_nth(self.de, n)
+    }
+
+    fn eat_char(&mut self) {
+        if let Ok(Some(ch)) = self.de.peek() {
+            self.de.str_buf.push(ch);
+        }
+        self.de.eat_char();
+    }
+
+    fn error(&mut self, reason: ErrorCode) -> Error {
+        self.de.error(reason)
+    }
+
+    fn peek_error(&mut self, reason: ErrorCode) -> Error {
+        self.de.peek_error(reason)
+    }
+
+    fn numeric_underscores(&self) -> bool {
+        self.de.config.numeric_underscores
+    }
+}
+
+/// A number literal held in memory, as given to `Number::from_str`.
+struct NumberSlice<'a> {
+    slice: &'a [u8],
+    index: usize,
+    numeric_underscores: bool,
+}
+
+impl<'a> ParseNumber for NumberSlice<'a> {
+    fn peek_or_null(&mut self) -> Result<u8> {
+        Ok(self.slice.get(self.index).cloned().unwrap_or(b'\x00'))
+    }
 
-    fn end_seq(&mut self) -> Result<()> {
-        match try!(self.parse_whitespace()) {
-            Some(b')') => {
-                self.eat_char();
-                Ok(())
-            }
-            Some(_) => Err(self.peek_error(ErrorCode::TrailingCharacters)),
-            None => Err(self.peek_error(ErrorCode::EofWhileParsingList)),
+    fn peek_nth(&mut self, n: usize) -> Result<Option<u8>> {
+        Ok(self.slice.get(self.index + n).cloned())
+    }
+
+    fn eat_char(&mut self) {
+        if self.index < self.slice.len() {
+            self.index += 1;
         }
     }
+
+    fn error(&mut self, reason: ErrorCode) -> Error {
+        Error::syntax(reason, 0, 0)
+    }
+
+    fn peek_error(&mut self, reason: ErrorCode) -> Error {
+        Error::syntax(reason, 0, 0)
+    }
+
+    fn numeric_underscores(&self) -> bool {
+        self.numeric_underscores
+    }
+}
+
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub fn parse_number_bytes(bytes: &[u8], numeric_underscores: bool) -> Result<sexp::Number> {
+    let mut slice = NumberSlice {
+        slice: bytes,
+        index: 0,
+        numeric_underscores: numeric_underscores,
+    };
+    let n = try!(slice.parse_number_literal());
+    if slice.index < bytes.len() {
+        return Err(Error::syntax(ErrorCode::InvalidNumber, 0, 0));
+    }
+    n.into_sexp_number().ok_or_else(|| Error::syntax(ErrorCode::InvalidNumber, 0, 0))
 }
 
 #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -481,7 +1563,7 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
         where
         V: de::Visitor<'de>,
     {
-        self.parse_value(visitor)
+        self.parse_value(false, visitor)
     }
 
     /// Parses a `nil` as a None, and any other values as a `Some(...)`.
@@ -496,59 +1578,143 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
                     try!(self.parse_ident(b"il"));
                     visitor.visit_none()
                 }
+                // `#nil` is what the serializer writes for `None`, keeping it
+                // apart from `Some` of an empty list, `()`.
+                Some(b'#') if try!(self.read.starts_with(b"#nil").map_err(Error::io)) => {
+                    self.eat_char();
+                    try!(self.parse_ident(b"nil"));
+                    visitor.visit_none()
+                }
                 _ => visitor.visit_some(self),
             }
         }
 
-    /// Parses a newtype struct as the underlying value.
-    #[inline]
-        fn deserialize_newtype_struct<V>(self, _name: &str, visitor: V) -> Result<V::Value>
+    /// Reads a symbol listed in `ParseConfig::true_symbols` or
+    /// `false_symbols` as the corresponding boolean.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
         where
         V: de::Visitor<'de>,
     {
-            visitor.visit_newtype_struct(self)
+        match try!(self.parse_whitespace()) {
+            Some(b'a'...b'z') | Some(b'A'...b'Z') => {
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                let b = {
+                    let name = try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters));
+                    symbol_to_bool(&self.config, &name)
+                };
+                match b {
+                    Some(b) => visitor.visit_bool(b),
+                    None => {
+                        let err: Error = de::Error::invalid_type(Unexpected::Other("symbol"), &visitor);
+                        Err(err.fix_position(|code| self.error(code)))
+                    }
+                }
+            }
+            _ => self.deserialize_any(visitor),
         }
+    }
 
-    /// Parses an enum as an s-expression like `(($KEY1 $VALUE1) ($KEY2 $VALUE2))` where $VALUE
-    /// is either a direct Sexp or a sequence.
-    #[inline]
-        fn deserialize_enum<V>(
-            self,
-            _name: &str,
-            _variants: &'static [&'static str],
-            visitor: V,
-        ) -> Result<V::Value>
+    /// Reads a symbol or keyword as its name, and a number literal as its
+    /// text, rather than rejecting them.
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
         where
         V: de::Visitor<'de>,
     {
-            match try!(self.parse_whitespace()) {
-                Some(b'(') => {
-                    self.remaining_depth -= 1;
-                    if self.remaining_depth == 0 {
-                        return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
-                    }
+        let value = match try!(self.parse_whitespace()) {
+            Some(b'0'...b'9') | Some(b'-') => self.parse_number_text(visitor),
+            Some(b'a'...b'z') | Some(b'A'...b'Z') => {
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                match try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters)) {
+                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Reference::Copied(s) => visitor.visit_str(s),
+                }
+            }
+            Some(b'|') => {
+                self.eat_char();
+                self.str_buf.clear();
+                match try!(self.read.parse_str_until(&mut self.str_buf, b"|")) {
+                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Reference::Copied(s) => visitor.visit_str(s),
+                }
+            }
+            Some(b'#') if try!(self.read.starts_with(b"#:").map_err(Error::io)) => {
+                self.read.discard();
+                self.read.discard();
+                self.str_buf.clear();
+                let delimiters = symbol_delimiters(&self.config);
+                match try!(self.read.parse_symbol_until(&mut self.str_buf, delimiters)) {
+                    Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Reference::Copied(s) => visitor.visit_str(s),
+                }
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+        value.map_err(|err| err.fix_position(|code| self.error(code)))
+    }
 
-                    self.eat_char();
-                    let value = try!(visitor.visit_enum(VariantAccess::new(self)));
+    #[inline]
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
 
-                    self.remaining_depth += 1;
+    /// Parses a unit struct from nil, the empty list or its own name.
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        let sexp = try!(self.parse_sexp());
+        de::Deserializer::deserialize_unit_struct(
+            SexpDeserializer::new(sexp, Some(&self.config)),
+            name,
+            visitor,
+        )
+            .map_err(|err| err.fix_position(|code| self.error(code)))
+    }
 
-                    match try!(self.parse_whitespace()) {
-                        Some(b')') => {
-                            self.eat_char();
-                            Ok(value)
-                        }
-                        Some(_) => Err(self.error(ErrorCode::ExpectedSomeValue)),
-                        None => Err(self.error(ErrorCode::EofWhileParsingAlist)),
-                    }
-                }
-                Some(b'"') => visitor.visit_enum(UnitVariantAccess::new(self)),
-                // TODO: ATOMS BROKEN
-                Some(_) => Err(self.peek_error(ErrorCode::ExpectedSomeValue)),
-                None => Err(self.peek_error(ErrorCode::EofWhileParsingValue)),
+    /// Parses a newtype struct as the underlying value. `Sexp` asks for its
+    /// value through `SEXP_TOKEN`, see `parse_value`.
+    #[inline]
+        fn deserialize_newtype_struct<V>(self, name: &str, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+            if name == SEXP_TOKEN {
+                return self.parse_value(true, visitor);
             }
+            visitor.visit_newtype_struct(self)
         }
 
+    /// Parses an enum from a string or symbol naming a unit variant, or from
+    /// the tagged list form `((variant Name) args...)`.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        match try!(self.parse_whitespace()) {
+            Some(b'"') => visitor.visit_enum(UnitVariantAccess::new(self)),
+            Some(_) => {
+                let sexp = try!(self.parse_sexp());
+                de::Deserializer::deserialize_enum(
+                    SexpDeserializer::new(sexp, Some(&self.config)),
+                    name,
+                    variants,
+                    visitor,
+                )
+                    .map_err(|err| err.fix_position(|code| self.error(code)))
+            }
+            None => Err(self.peek_error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
         where
@@ -576,24 +1742,142 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
             self.deserialize_bytes(visitor)
         }
 
+    /// Parses a list, or a dotted pair, as a sequence without first building
+    /// a `Sexp`, so that borrowed strings can be deserialized from it. The
+    /// tail of a dotted list is the last element, so `(1 2 . 3)` reads as
+    /// `[1, 2, 3]`.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_list(None, visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_list(Some(len), visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_list(Some(len), visitor)
+    }
+
+    /// Parses a list as a map, accepting an alist or a plist.
+    ///
+    /// A list whose first element is itself a list is read as an alist, one
+    /// `(key . value)` or `(key value)` entry at a time, without first
+    /// building a `Sexp`. Only the keys are parsed into `Sexp`s, so that they
+    /// match struct fields the same way everywhere. Plists are still read
+    /// through a `Sexp`, since a list is only known to be one at its end.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        match try!(self.parse_whitespace()) {
+            Some(b'(') => {
+                self.remaining_depth -= 1;
+                if self.remaining_depth == 0 {
+                    return Err(self.peek_error(ErrorCode::RecursionLimitExceeded));
+                }
+                self.eat_char();
+
+                if try!(self.parse_whitespace()) != Some(b'(') {
+                    let list = self.parse_list_elements();
+                    self.remaining_depth += 1;
+                    return de::Deserializer::deserialize_map(
+                        SexpDeserializer::new(try!(list), Some(&self.config)),
+                        visitor,
+                    )
+                        .map_err(|err| err.fix_position(|code| self.error(code)));
+                }
+
+                let ret = visitor.visit_map(AlistAccess::new(self));
+
+                self.remaining_depth += 1;
+
+                match (ret, self.end_seq()) {
+                    (Ok(ret), Ok(())) => Ok(ret),
+                    (Err(err), _) | (_, Err(err)) => Err(err.fix_position(|code| self.error(code))),
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+        where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
     forward_to_deserialize_any! {
-            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string unit
-                unit_struct seq tuple tuple_struct map struct identifier ignored_any
+            i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char unit
+                identifier ignored_any
         }
 
 }
 
-// POSSIBLY BROKEN --------------------------------------------------------
 struct SeqAccess<'a, R: 'a> {
     de: &'a mut Deserializer<R>,
     first: bool,
+    // Set once the ` . ` of a dotted pair has been read; only the cdr may
+    // follow it.
+    cdr: bool,
+    // The number of elements a tuple must have, and the number read so far.
+    len: Option<usize>,
+    count: usize,
 }
 
 impl<'a, R: 'a> SeqAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>) -> Self {
+    fn new(de: &'a mut Deserializer<R>, len: Option<usize>) -> Self {
         SeqAccess {
             de: de,
             first: true,
+            cdr: false,
+            len: len,
+            count: 0,
+        }
+    }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> SeqAccess<'a, R> {
+    /// Fails if the list goes on after the `len` elements of a tuple,
+    /// counting the rest of its elements for the error message.
+    fn check_no_more(&mut self, len: usize) -> Result<()> {
+        let mut found = self.count;
+        loop {
+            match try!(self.de.parse_whitespace()) {
+                Some(b')') | None => break,
+                Some(b'.') if try!(self.de.at_pair_dot()) => self.de.eat_char(),
+                Some(_) => {
+                    try!(self.de.parse_sexp());
+                    found += 1;
+                }
+            }
+        }
+        if found == len {
+            Ok(())
+        } else {
+            Err(Error::tuple_length(len, found))
         }
     }
 }
@@ -605,84 +1889,74 @@ impl<'de, 'a, R: Read<'de> + 'a> de::SeqAccess<'de> for SeqAccess<'a, R> {
         where
         T: de::DeserializeSeed<'de>,
     {
-        match try!(self.de.peek()) {
+        match try!(self.de.parse_whitespace()) {
             Some(b')') => {
-                return Ok(None);
-            },
-            Some(b' ') => {
-                self.de.eat_char();
+                return match self.len {
+                    Some(len) if self.count < len => Err(Error::tuple_length(len, self.count)),
+                    _ => Ok(None),
+                };
             }
-            Some(_) => {
-                try!(self.de.parse_whitespace());
+            Some(_) if self.cdr => {
+                return Err(self.de.peek_error(ErrorCode::TrailingCharacters));
+            }
+            Some(b'.') if try!(self.de.at_pair_dot()) => {
                 if self.first {
-                    self.first = false;
-                } else {
-                    return Err(self.de.peek_error(ErrorCode::ExpectedListEltOrEnd));
+                    return Err(self.de.peek_error(ErrorCode::ExpectedSomeValue));
                 }
-            },
+                self.de.eat_char();
+                self.cdr = true;
+            }
+            Some(_) => {}
             None => {
                 return Err(self.de.peek_error(ErrorCode::EofWhileParsingList));
             }
         }
 
-        if try!(self.de.peek()).unwrap() == b')' {
-            Ok(None)
-        } else {
-            seed.deserialize(&mut *self.de).map(Some)
-        }
+        self.first = false;
+        self.count += 1;
+        seed.deserialize(&mut *self.de).map(Some)
     }
 }
 
-// END POSSIBLY BROKEN --------------------------------------------------------
-
-struct VariantAccess<'a, R: 'a> {
+struct AlistAccess<'a, R: 'a> {
     de: &'a mut Deserializer<R>,
 }
 
-impl<'a, R: 'a> VariantAccess<'a, R> {
+impl<'a, R: 'a> AlistAccess<'a, R> {
     fn new(de: &'a mut Deserializer<R>) -> Self {
-        VariantAccess { de: de }
-    }
-}
-
-impl<'de, 'a, R: Read<'de> + 'a> de::EnumAccess<'de> for VariantAccess<'a, R> {
-    type Error = Error;
-    type Variant = Self;
-
-    fn variant_seed<V>(self, _seed: V) -> Result<(V::Value, Self)>
-        where
-        V: de::DeserializeSeed<'de>,
-    {
-        unimplemented!()
+        AlistAccess { de: de }
     }
 }
 
-impl<'de, 'a, R: Read<'de> + 'a> de::VariantAccess<'de> for VariantAccess<'a, R> {
+impl<'de, 'a, R: Read<'de> + 'a> de::MapAccess<'de> for AlistAccess<'a, R> {
     type Error = Error;
 
-    fn unit_variant(self) -> Result<()> {
-        de::Deserialize::deserialize(self.de)
-    }
-
-    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
-        where
-        T: de::DeserializeSeed<'de>,
-    {
-        seed.deserialize(self.de)
-    }
-
-    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
         where
-        V: de::Visitor<'de>,
+        K: de::DeserializeSeed<'de>,
     {
-        de::Deserializer::deserialize_any(self.de, visitor)
+        match try!(self.de.parse_whitespace()) {
+            Some(b')') => Ok(None),
+            Some(b'(') => {
+                self.de.eat_char();
+                let key = try!(self.de.parse_sexp());
+                seed.deserialize(SexpDeserializer::new(key, Some(&self.de.config))).map(Some)
+            }
+            Some(_) => Err(self.de.peek_error(ErrorCode::ExpectedSomeValue)),
+            None => Err(self.de.peek_error(ErrorCode::EofWhileParsingList)),
+        }
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
         where
-        V: de::Visitor<'de>,
+        V: de::DeserializeSeed<'de>,
     {
-        de::Deserializer::deserialize_any(self.de, visitor)
+        if try!(self.de.parse_whitespace()) == Some(b'.') && try!(self.de.at_pair_dot()) {
+            self.de.eat_char();
+        }
+        let value = try!(seed.deserialize(&mut *self.de));
+        try!(self.de.end_seq());
+        Ok(value)
     }
 }
 
@@ -785,6 +2059,7 @@ macro_rules! deserialize_integer_key {
 pub struct StreamDeserializer<'de, R, T> {
     de: Deserializer<R>,
     offset: usize,
+    forms: usize,
     output: PhantomData<T>,
     lifetime: PhantomData<&'de ()>,
 }
@@ -803,10 +2078,27 @@ impl<'de, R, T> StreamDeserializer<'de, R, T>
     ///   - Deserializer::from_bytes(...).into_iter()
     ///   - Deserializer::from_reader(...).into_iter()
     pub fn new(read: R) -> Self {
+        StreamDeserializer::with_config(read, ParseConfig::default())
+    }
+
+    /// Create a sexp-stream deserializer that reads the dialect described by
+    /// `config`.
+    ///
+    /// ```rust,ignore
+    /// use sexpr::de::{StreamDeserializer, StrRead};
+    ///
+    /// let mut config = ParseConfig::default();
+    /// config.square_brackets = true;
+    ///
+    /// let stream = StreamDeserializer::<_, Sexp>::with_config(StrRead::new("[a] [b]"), config);
+    /// assert_eq!(stream.count(), 2);
+    /// ```
+    pub fn with_config(read: R, config: ParseConfig) -> Self {
         let offset = read.byte_offset();
         StreamDeserializer {
-            de: Deserializer::new(read),
+            de: Deserializer::with_config(read, config),
             offset: offset,
+            forms: 0,
             output: PhantomData,
             lifetime: PhantomData,
         }
@@ -819,6 +2111,24 @@ impl<'de, R, T> StreamDeserializer<'de, R, T>
     pub fn byte_offset(&self) -> usize {
         self.offset
     }
+
+    /// The comments skipped so far, as for `Deserializer::comments`.
+    pub fn comments(&self) -> &[Comment] {
+        self.de.comments()
+    }
+
+    fn next_value(&mut self) -> Result<T> {
+        self.offset = self.de.read.byte_offset();
+        if Some(self.forms) == self.de.config.max_forms {
+            return Err(self.de.peek_error(ErrorCode::TooManyForms));
+        }
+        self.forms += 1;
+        let result = de::Deserialize::deserialize(&mut self.de);
+        if result.is_ok() {
+            self.offset = self.de.read.byte_offset();
+        }
+        result
+    }
 }
 
 
@@ -838,13 +2148,14 @@ impl<'de, R, T> Iterator for StreamDeserializer<'de, R, T>
                 self.offset = self.de.read.byte_offset();
                 None
             }
-            Ok(Some(b'(')) => {
-                self.offset = self.de.read.byte_offset();
-                let result = de::Deserialize::deserialize(&mut self.de);
-                if result.is_ok() {
-                    self.offset = self.de.read.byte_offset();
+            Ok(Some(b'(')) => Some(self.next_value()),
+            Ok(Some(b'[')) if self.de.config.square_brackets => Some(self.next_value()),
+            Ok(Some(b'#')) => {
+                match self.de.at_hash_vector() {
+                    Ok(true) => Some(self.next_value()),
+                    Ok(false) => Some(Err(self.de.peek_error(ErrorCode::ExpectedList))),
+                    Err(e) => Some(Err(e)),
                 }
-                Some(result)
             }
             Ok(Some(_)) => Some(Err(self.de.peek_error(ErrorCode::ExpectedList))),
             Err(e) => Some(Err(e)),
@@ -922,6 +2233,106 @@ pub fn from_reader<R, T>(rdr: R) -> Result<T>
     from_trait(read::IoRead::new(rdr))
 }
 
+/// Deserialize an instance of type `T` from an IO stream holding an
+/// S-expression in any of its representations, telling them apart by their
+/// first bytes.
+///
+/// Input starting with a length prefix such as `3:`, after any number of
+/// `(`, is read as a canonical S-expression. Its octets need not be UTF-8:
+/// those that are not are visited as bytes. Input starting with `{` is
+/// taken to be the base64 transport encoding of a canonical S-expression,
+/// `{KDE6YSk=}` for `(1:a)`, and is decoded and read the same way. Anything
+/// else is read as text.
+///
+/// Leading whitespace is skipped before looking at the input. Since the
+/// canonical form only knows octet strings, a value comes back the same
+/// from all three representations only if its atoms are all strings.
+///
+/// ```rust,ignore
+/// let text: Sexp = sexpr::from_reader_auto(&b"(\"abc\" \"de\")"[..]).unwrap();
+/// let canonical: Sexp = sexpr::from_reader_auto(&b"(3:abc2:de)"[..]).unwrap();
+/// let transport: Sexp = sexpr::from_reader_auto(&b"{KDM6YWJjMjpkZSk=}"[..]).unwrap();
+/// assert_eq!(text, canonical);
+/// assert_eq!(text, transport);
+/// ```
+pub fn from_reader_auto<R, T>(mut rdr: R) -> Result<T>
+    where
+    R: io::Read,
+    T: de::DeserializeOwned,
+{
+    let mut bytes = Vec::new();
+    try!(rdr.read_to_end(&mut bytes).map_err(Error::io));
+
+    let start = bytes.iter()
+        .position(|&b| b != b' ' && b != b'\n' && b != b'\t' && b != b'\r')
+        .unwrap_or(bytes.len());
+    if bytes.get(start) == Some(&b'{') {
+        let decoded = try!(decode_transport(&bytes[start + 1..]));
+        return from_slice_auto(&decoded);
+    }
+    from_slice_auto(&bytes[start..])
+}
+
+/// Reads `bytes` as a canonical S-expression if it begins like one, and as
+/// text otherwise.
+fn from_slice_auto<'a, T>(bytes: &'a [u8]) -> Result<T>
+    where
+    T: de::Deserialize<'a>,
+{
+    let atom = bytes.iter().position(|&b| b != b'(').unwrap_or(bytes.len());
+    let digits = bytes[atom..].iter().take_while(|&&b| b'0' <= b && b <= b'9').count();
+    if digits > 0 && bytes.get(atom + digits) == Some(&b':') {
+        return canonical::decode(bytes);
+    }
+    from_slice(bytes)
+}
+
+/// Decodes the base64 of a transport encoding up to its closing `}`, which
+/// may only be followed by whitespace. Whitespace inside the braces is
+/// ignored.
+fn decode_transport(input: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || Error::syntax(ErrorCode::InvalidTransport, 0, 0);
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padding = false;
+    let mut rest = input.iter();
+    loop {
+        let b = match rest.next() {
+            Some(&b'}') => break,
+            Some(&b) => b,
+            None => return Err(invalid()),
+        };
+        let sextet = match b {
+            b' ' | b'\n' | b'\t' | b'\r' => continue,
+            b'=' => {
+                padding = true;
+                continue;
+            }
+            _ if padding => return Err(invalid()),
+            b'A'...b'Z' => b - b'A',
+            b'a'...b'z' => b - b'a' + 26,
+            b'0'...b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(invalid()),
+        };
+        acc = (acc << 6) | sextet as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    if rest.any(|&b| b != b' ' && b != b'\n' && b != b'\t' && b != b'\r') {
+        return Err(Error::syntax(ErrorCode::TrailingCharacters, 0, 0));
+    }
+    Ok(out)
+}
+
 /// Deserialize an instance of type `T` from bytes of an S-expression.
 ///
 /// # Errors
@@ -932,7 +2343,8 @@ pub fn from_reader<R, T>(rdr: R) -> Result<T>
 /// structure is correct but `T`'s implementation of `Deserialize` decides that
 /// something is wrong with the data, for example required struct fields are
 /// missing from the S-expression or some number is too big to fit in the expected
-/// primitive type.
+/// primitive type. Input that is not valid UTF-8 is rejected before parsing,
+/// with an error giving the byte offset of the first invalid sequence.
 ///
 /// ```rust,ignore
 /// #[macro_use]
@@ -962,7 +2374,9 @@ pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<T>
     where
     T: de::Deserialize<'a>,
 {
-    from_trait(read::SliceRead::new(v))
+    let read = read::SliceRead::new(v);
+    try!(read.validate_utf8());
+    from_trait(read)
 }
 
 /// Deserialize an instance of type `T` from a string of S-expressions.
@@ -1007,3 +2421,78 @@ pub fn from_str<'a, T>(s: &'a str) -> Result<T>
 {
     from_trait(read::StrRead::new(s))
 }
+
+/// Deserialize an instance of type `T` from a string of S-expressions written
+/// in the dialect described by `config`.
+///
+/// ```rust,ignore
+/// let mut config = sexpr::ParseConfig::default();
+/// config.string_delimiters.push(('«', '»'));
+///
+/// let s: String = sexpr::from_str_with_config("«quoted»", config).unwrap();
+/// assert_eq!(s, "quoted");
+/// ```
+pub fn from_str_with_config<'a, T>(s: &'a str, config: ParseConfig) -> Result<T>
+    where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::with_config(read::StrRead::new(s), config);
+    let value = try!(de::Deserialize::deserialize(&mut de));
+
+    // Make sure the whole stream has been consumed.
+    try!(de.end());
+    Ok(value)
+}
+
+/// Parses a byte slice holding a single S-expression into a `BorrowedSexp`
+/// whose symbols, keywords and strings point into `v` unless they contain
+/// escape sequences. Useful for large read-only inputs such as a memory
+/// mapped file.
+///
+/// ```rust,ignore
+/// let v = sexpr::from_slice_borrowed(b"(a b c)").unwrap();
+/// ```
+pub fn from_slice_borrowed<'a>(v: &'a [u8]) -> Result<BorrowedSexp<'a>> {
+    let mut de = Deserializer::new(read::SliceRead::new(v));
+    let value = try!(de.parse_borrowed_sexp());
+    try!(de.end());
+    Ok(value)
+}
+
+/// Parses a string holding a single S-expression into a `BorrowedSexp`
+/// whose atoms point into `s` where possible. See `from_slice_borrowed`.
+pub fn from_str_borrowed<'a>(s: &'a str) -> Result<BorrowedSexp<'a>> {
+    let mut de = Deserializer::new(read::StrRead::new(s));
+    let value = try!(de.parse_borrowed_sexp());
+    try!(de.end());
+    Ok(value)
+}
+
+/// Visits a symbol or keyword name, tagged with `token` when `exact` is set
+/// (see `Deserializer::parse_value`) and as a string, borrowed where the
+/// reader allows it, otherwise.
+fn visit_name<'de, 's, V>(
+    token: &'static str,
+    name: Reference<'de, 's, str>,
+    exact: bool,
+    visitor: V,
+) -> Result<V::Value>
+    where
+    V: de::Visitor<'de>,
+{
+    if exact {
+        return visit_tagged_name(token, String::from(&*name), visitor);
+    }
+    match name {
+        Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Reference::Copied(s) => visitor.visit_str(s),
+    }
+}
+
+/// Keeps text that the reader could borrow from the input borrowed.
+fn into_cow<'de, 's>(reference: Reference<'de, 's, str>) -> Cow<'de, str> {
+    match reference {
+        Reference::Borrowed(s) => Cow::Borrowed(s),
+        Reference::Copied(s) => Cow::Owned(String::from(s)),
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! (De)serialize a `std::time::Duration` as the list `(secs nanos)`.
+//!
+//! For use with serde's `with` attribute:
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Timeout {
+//!     #[serde(with = "sexpr::duration")]
+//!     after: Duration,
+//! }
+//! ```
+//!
+//! `Duration::new(90, 500)` is written as `(90 500)`, or as `(90 . 500)` when
+//! `SerializeConfig::two_tuples_as_pairs` is set. Either form is accepted when
+//! deserializing.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes `duration` as `(secs nanos)`.
+pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    (duration.as_secs(), duration.subsec_nanos()).serialize(serializer)
+}
+
+/// Deserializes a `Duration` from `(secs nanos)` or `(secs . nanos)`.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let (secs, nanos): (u64, u32) = try!(Deserialize::deserialize(deserializer));
+    Ok(Duration::new(secs, nanos))
+}
@@ -48,6 +48,12 @@ impl Error {
         self.err.column
     }
 
+    // Not public API. Should be pub(crate).
+    #[doc(hidden)]
+    pub fn code(&self) -> &ErrorCode {
+        &self.err.code
+    }
+
     /// Categorizes the cause of this error.
     ///
     /// - `Category::Io` - failure to read or write bytes on an IO stream
@@ -56,12 +62,14 @@ impl Error {
     /// - `Category::Eof` - unexpected end of the input data
     pub fn classify(&self) -> Category {
         match self.err.code {
-            ErrorCode::Message(_) => Category::Data,
+            ErrorCode::Message(_) |
+            ErrorCode::DuplicateKey(_) => Category::Data,
             ErrorCode::Io(_) => Category::Io,
             ErrorCode::EofWhileParsingList |
             ErrorCode::EofWhileParsingAlist |
             ErrorCode::EofWhileParsingString |
-            ErrorCode::EofWhileParsingValue => Category::Eof,
+            ErrorCode::EofWhileParsingValue |
+            ErrorCode::EofWhileParsingComment => Category::Eof,
             ErrorCode::ExpectedPairDot |
             ErrorCode::ExpectedListEltOrEnd |
             ErrorCode::ExpectedPairOrEnd |
@@ -73,10 +81,14 @@ impl Error {
             ErrorCode::InvalidNumber |
             ErrorCode::NumberOutOfRange |
             ErrorCode::InvalidUnicodeCodePoint |
+            ErrorCode::InvalidUtf8(_) |
             ErrorCode::KeyMustBeAString |
             ErrorCode::LoneLeadingSurrogateInHexEscape |
             ErrorCode::TrailingCharacters |
             ErrorCode::UnexpectedEndOfHexEscape |
+            ErrorCode::UndefinedDatumLabel |
+            ErrorCode::InvalidTransport |
+            ErrorCode::TooManyForms |
             ErrorCode::RecursionLimitExceeded => Category::Syntax,
         }
     }
@@ -205,6 +217,9 @@ pub enum ErrorCode {
     /// EOF while parsing a S-expression value.
     EofWhileParsingValue,
 
+    /// EOF while parsing a `#|...|#` block comment.
+    EofWhileParsingComment,
+
     /// Expected this character to be a `'.'`.
     ExpectedPairDot,
 
@@ -238,6 +253,9 @@ pub enum ErrorCode {
     /// Invalid unicode code point.
     InvalidUnicodeCodePoint,
 
+    /// Input bytes are not valid UTF-8, starting at this byte offset.
+    InvalidUtf8(usize),
+
     /// Object key is not a string.
     KeyMustBeAString,
 
@@ -250,6 +268,19 @@ pub enum ErrorCode {
     /// Unexpected end of hex excape.
     UnexpectedEndOfHexEscape,
 
+    /// A `#n#` datum reference to a label that has not been defined yet.
+    UndefinedDatumLabel,
+
+    /// A `{...}` transport encoding that is not well-formed base64.
+    InvalidTransport,
+
+    /// More top-level forms than `ParseConfig::max_forms` allows.
+    TooManyForms,
+
+    /// An alist has this key more than once, and `DedupPolicy::Error` was
+    /// asked for.
+    DuplicateKey(String),
+
     /// Encountered nesting of S-expression maps and arrays more than 128 layers deep.
     RecursionLimitExceeded,
 }
@@ -269,6 +300,18 @@ impl Error {
         }
     }
 
+    // Not public API. Should be pub(crate).
+    #[doc(hidden)]
+    pub fn tuple_length(expected: usize, found: usize) -> Self {
+        let relation = if found < expected { "few" } else { "many" };
+        de::Error::custom(format_args!(
+            "too {} elements for a tuple of {}: found {}",
+            relation,
+            expected,
+            found
+        ))
+    }
+
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn io(error: io::Error) -> Self {
@@ -306,6 +349,7 @@ impl Display for ErrorCode {
             ErrorCode::EofWhileParsingAlist => f.write_str("EOF while parsing an alist"),
             ErrorCode::EofWhileParsingString => f.write_str("EOF while parsing a string"),
             ErrorCode::EofWhileParsingValue => f.write_str("EOF while parsing a value"),
+            ErrorCode::EofWhileParsingComment => f.write_str("EOF while parsing a comment"),
             ErrorCode::ExpectedPairDot => f.write_str("expected `.`"),
             ErrorCode::ExpectedListEltOrEnd => f.write_str("expected ` ` or `)`"),
             ErrorCode::ExpectedPairOrEnd => f.write_str("expected `.` or `)`"),
@@ -317,12 +361,17 @@ impl Display for ErrorCode {
             ErrorCode::InvalidNumber => f.write_str("invalid number"),
             ErrorCode::NumberOutOfRange => f.write_str("number out of range"),
             ErrorCode::InvalidUnicodeCodePoint => f.write_str("invalid unicode code point"),
+            ErrorCode::InvalidUtf8(offset) => write!(f, "invalid UTF-8 at byte offset {}", offset),
             ErrorCode::KeyMustBeAString => f.write_str("key must be a string"),
             ErrorCode::LoneLeadingSurrogateInHexEscape => {
                 f.write_str("lone leading surrogate in hex escape")
             }
             ErrorCode::TrailingCharacters => f.write_str("trailing characters"),
             ErrorCode::UnexpectedEndOfHexEscape => f.write_str("unexpected end of hex escape"),
+            ErrorCode::UndefinedDatumLabel => f.write_str("undefined datum label"),
+            ErrorCode::InvalidTransport => f.write_str("invalid base64 transport encoding"),
+            ErrorCode::TooManyForms => f.write_str("too many top-level forms"),
+            ErrorCode::DuplicateKey(ref key) => write!(f, "duplicate key `{}`", key),
             ErrorCode::RecursionLimitExceeded => f.write_str("recursion limit exceeded"),
         }
     }
@@ -18,8 +18,13 @@ pub struct LineColIterator<I> {
     /// Index of the current column. The first character in the input and any
     /// characters immediately following a newline character are in column 1.
     /// The column is 0 immediately after a newline character has been read.
+    /// Columns count UTF-8 characters, so continuation bytes do not advance
+    /// it.
     col: usize,
 
+    /// Number of bytes read since the start of the current line.
+    line_bytes: usize,
+
     /// Byte offset of the start of the current line. This is the sum of lenghts
     /// of all previous lines. Keeping track of things this way allows efficient
     /// computation of the current line, column, and byte offset while only
@@ -36,6 +41,7 @@ where
             iter: iter,
             line: 1,
             col: 0,
+            line_bytes: 0,
             start_of_line: 0,
         }
     }
@@ -49,7 +55,7 @@ where
     }
 
     pub fn byte_offset(&self) -> usize {
-        self.start_of_line + self.col
+        self.start_of_line + self.line_bytes
     }
 }
 
@@ -63,16 +69,26 @@ where
         match self.iter.next() {
             None => None,
             Some(Ok(b'\n')) => {
-                self.start_of_line += self.col + 1;
+                self.start_of_line += self.line_bytes + 1;
                 self.line += 1;
                 self.col = 0;
+                self.line_bytes = 0;
                 Some(Ok(b'\n'))
             }
             Some(Ok(c)) => {
-                self.col += 1;
+                if !is_continuation_byte(c) {
+                    self.col += 1;
+                }
+                self.line_bytes += 1;
                 Some(Ok(c))
             }
             Some(Err(e)) => Some(Err(e)),
         }
     }
 }
+
+/// Whether `b` continues a multi-byte UTF-8 sequence rather than starting a
+/// character.
+pub fn is_continuation_byte(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
@@ -0,0 +1,196 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Write a `Sexp` with Common Lisp datum labels for repeated structure.
+//!
+//! A list, vector or dotted pair that occurs more than once is labelled
+//! where it first appears and referred to by its label afterwards:
+//!
+//! ```text
+//! ((a b) x (a b))  =>  (#1=(a b) x #1#)
+//! ```
+//!
+//! A `Sexp` owns its children, so "shared" means structurally equal: two
+//! subtrees are the same datum when they are equal as values, so a symbol
+//! is never confused with a string or `2` with `2.0`. Atoms and empty lists
+//! are never labelled. The output reads back into the original value with
+//! `ParseConfig::datum_labels`, together with `ParseConfig::square_brackets`
+//! if it holds any vectors, which are written `[...]`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sexp::Sexp;
+
+/// Serialize `value` as text, writing repeated lists, vectors and dotted
+/// pairs once and referring back to them with `#n#`.
+///
+/// ```rust,ignore
+/// let v: Sexp = sexpr::from_str("((a b) x (a b))").unwrap();
+/// assert_eq!(sexpr::to_string_with_labels(&v), "(#1=(a b) x #1#)");
+/// ```
+pub fn to_string_with_labels(value: &Sexp) -> String {
+    let mut counts = HashMap::new();
+    count(value, &mut counts);
+    let mut writer = LabelWriter {
+        counts: counts,
+        labels: HashMap::new(),
+        out: String::new(),
+    };
+    writer.write(value);
+    writer.out
+}
+
+/// Counts the occurrences of each compound subtree of `value`. The children
+/// of a subtree are only counted the first time it is seen, since every
+/// later occurrence is written as a reference.
+fn count(value: &Sexp, counts: &mut HashMap<String, usize>) {
+    if !is_compound(value) {
+        return;
+    }
+    let seen = counts.entry(key(value)).or_insert(0);
+    *seen += 1;
+    if *seen == 1 {
+        for child in children(value) {
+            count(child, counts);
+        }
+    }
+}
+
+struct LabelWriter {
+    counts: HashMap<String, usize>,
+    labels: HashMap<String, usize>,
+    out: String,
+}
+
+impl LabelWriter {
+    fn write(&mut self, value: &Sexp) {
+        if is_compound(value) {
+            let key = key(value);
+            if self.counts[&key] > 1 {
+                if let Some(&n) = self.labels.get(&key) {
+                    self.out.push_str(&format!("#{}#", n));
+                    return;
+                }
+                let n = self.labels.len() + 1;
+                self.labels.insert(key, n);
+                self.out.push_str(&format!("#{}=", n));
+            }
+        }
+
+        match *value {
+            Sexp::List(ref elts) => self.write_elements("(", elts.iter(), ")"),
+            Sexp::Vector(ref elts) => self.write_elements("[", elts.iter(), "]"),
+            Sexp::Pair(_, _) => {
+                let mut elts = children(value);
+                let tail = elts.pop();
+                self.write_elements("(", elts.into_iter(), "");
+                self.out.push_str(" . ");
+                if let Some(tail) = tail {
+                    self.write(tail);
+                }
+                self.out.push(')');
+            }
+            // `to_string` writes a keyword as a string.
+            Sexp::Atom(ref atom) if atom.is_keyword() => {
+                self.out.push_str("#:");
+                self.out.push_str(atom.as_str());
+            }
+            _ => self.out.push_str(&value.to_string()),
+        }
+    }
+
+    fn write_elements<'a, I>(&mut self, open: &str, elts: I, close: &str)
+    where
+        I: Iterator<Item = &'a Sexp>,
+    {
+        self.out.push_str(open);
+        for (i, elt) in elts.enumerate() {
+            if i > 0 {
+                self.out.push(' ');
+            }
+            self.write(elt);
+        }
+        self.out.push_str(close);
+    }
+}
+
+static NIL: Sexp = Sexp::Nil;
+
+/// An encoding of `value` that two subtrees share exactly when they are
+/// equal. Every atom is tagged with its type and length, since the text
+/// form writes a keyword and a string alike.
+fn key(value: &Sexp) -> String {
+    fn encode(value: &Sexp, out: &mut String) {
+        match *value {
+            Sexp::Nil => out.push('n'),
+            Sexp::Boolean(b) => out.push(if b { 't' } else { 'f' }),
+            Sexp::Number(ref n) => match n.as_f64() {
+                Some(f) if n.is_f64() => write!(out, "d{:x};", f.to_bits()).unwrap(),
+                _ => write!(out, "i{};", n).unwrap(),
+            },
+            Sexp::Atom(ref atom) => {
+                let tag = if atom.is_symbol() {
+                    'y'
+                } else if atom.is_keyword() {
+                    'k'
+                } else {
+                    's'
+                };
+                write!(out, "{}{}:{}", tag, atom.as_str().len(), atom.as_str()).unwrap();
+            }
+            Sexp::List(ref elts) | Sexp::Vector(ref elts) => {
+                out.push(if let Sexp::List(_) = *value { '(' } else { '[' });
+                for elt in elts {
+                    encode(elt, out);
+                }
+                out.push(')');
+            }
+            Sexp::Pair(ref car, ref cdr) => {
+                out.push('.');
+                for half in &[car, cdr] {
+                    match **half {
+                        Some(ref half) => encode(half, out),
+                        None => out.push('_'),
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    encode(value, &mut out);
+    out
+}
+
+fn is_compound(value: &Sexp) -> bool {
+    match *value {
+        Sexp::List(ref elts) | Sexp::Vector(ref elts) => !elts.is_empty(),
+        Sexp::Pair(_, _) => true,
+        _ => false,
+    }
+}
+
+/// The elements of a list or vector, or the cars of a chain of dotted pairs
+/// followed by its final cdr.
+fn children(value: &Sexp) -> Vec<&Sexp> {
+    match *value {
+        Sexp::List(ref elts) | Sexp::Vector(ref elts) => elts.iter().collect(),
+        Sexp::Pair(_, _) => {
+            let mut elts = Vec::new();
+            let mut spine = value;
+            while let Sexp::Pair(ref car, ref cdr) = *spine {
+                elts.push(car.as_ref().map_or(&NIL, |car| &**car));
+                spine = cdr.as_ref().map_or(&NIL, |cdr| &**cdr);
+            }
+            elts.push(spine);
+            elts
+        }
+        _ => Vec::new(),
+    }
+}
@@ -228,25 +228,47 @@ extern crate num_traits;
 extern crate serde;
 extern crate itoa;
 extern crate dtoa;
+#[cfg(feature = "sha2")]
+extern crate sha2;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 
 #[doc(inline)]
-pub use self::de::{Deserializer, StreamDeserializer, from_reader, from_slice, from_str};
+pub use self::de::{Deserializer, StreamDeserializer, from_reader, from_reader_auto, from_slice,
+                   from_slice_borrowed, from_str, from_str_borrowed, from_str_with_config};
+#[doc(inline)]
+pub use self::canonical::to_canonical;
+#[doc(inline)]
+pub use self::labels::to_string_with_labels;
+#[doc(inline)]
+pub use self::config::{BoolStyle, KeywordStyle, MapStyle, ParseConfig, PrettyConfig,
+                       SerializeConfig, UnitVariantStyle, UnknownHashPolicy};
 #[doc(inline)]
 pub use self::error::{Error, Result};
 #[doc(inline)]
-pub use ser::{to_string, Serializer};
+pub use self::reformat::reformat;
+#[doc(inline)]
+pub use ser::{to_string, to_string_with_config, to_string_with_header, Serializer};
 #[doc(inline)]
-pub use self::sexp::{Sexp, Number, from_value, to_value};
+pub use self::sexp::{BorrowedSexp, Sexp, Number, from_value, to_value, to_value_with_config};
 
 #[macro_use]
 mod macros;
 
 pub mod de;
+pub mod duration;
 pub mod error;
+#[cfg(feature = "chrono")]
+pub mod rfc3339;
 pub mod ser;
 pub mod sexp;
+pub mod system_time;
 
+mod canonical;
+mod config;
+mod labels;
 mod iter;
 mod number;
 mod atom;
 mod read;
+mod reformat;
@@ -27,3 +27,27 @@ macro_rules! sexp {
         $crate::from_str(stringify!($t)).unwrap();
     };
 }
+
+/// Construct an alist `sexpr::Sexp` from `key => value` entries. Each entry
+/// becomes a `(key . value)` pair as built by `Sexp::new_entry`, so keys may
+/// be anything convertible into an `Atom` and values anything convertible
+/// into a `Sexp`.
+///
+/// ```rust,ignore
+/// # #[macro_use]
+/// # extern crate sexpr;
+/// #
+/// # fn main() {
+/// let value = alist! {
+///     "name" => "John",
+///     "age" => 43,
+///     "phones" => vec!["+44 1"],
+/// };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! alist {
+    ($($key:expr => $value:expr),* $(,)*) => {
+        $crate::Sexp::List(vec![$($crate::Sexp::new_entry($key, $value)),*])
+    };
+}
@@ -1,10 +1,15 @@
 // Copyright 2017 Zephyr Pellerin
+use config::ParseConfig;
 use error::Error;
 use num_traits::NumCast;
 use serde::de::{self, Visitor, Unexpected};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use sexp::Sexp;
 use std::fmt::{self, Debug, Display};
 use std::i64;
+use std::str::FromStr;
+use std::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroIsize, NonZeroU8,
+               NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize};
 
 /// Represents a Sexp number, whether integer or floating point.
 #[derive(Clone, PartialEq)]
@@ -85,6 +90,40 @@ impl Number {
             None
         }
     }
+
+    /// Reads `s` as a single number, exactly as the deserializer would read
+    /// it inside a list under `config`, so settings such as
+    /// `numeric_underscores` and `number_suffix` apply. `FromStr` accepts
+    /// plain numbers only.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `s` is not one whole number.
+    ///
+    /// ```rust,ignore
+    /// let mut config = ParseConfig::default();
+    /// config.numeric_underscores = true;
+    /// assert_eq!(Number::parse("1_000", &config).unwrap(), Number::from(1000));
+    /// ```
+    pub fn parse(s: &str, config: &ParseConfig) -> Result<Number, Error> {
+        match try!(::de::from_str_with_config(s, config.clone())) {
+            Sexp::Number(n) => Ok(n),
+            other => Err(de::Error::invalid_type(other.unexpected(), &"a number")),
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = Error;
+
+    /// Parses a number exactly as `sexpr::from_str` reads one, so `"42"`
+    /// reads as a `u64`, `"-7"` as an `i64` and `"3.14"`, `".5"` or `"1e9"`
+    /// as an `f64`. Surrounding whitespace is ignored, while `"+5"`, `"5."`
+    /// and `"05"` are errors.
+    fn from_str(s: &str) -> Result<Number, Error> {
+        let s = s.trim_matches(|c| c == ' ' || c == '\n' || c == '\t' || c == '\r');
+        ::de::parse_number_bytes(s.as_bytes(), false)
+    }
 }
 
 impl fmt::Display for Number {
@@ -232,6 +271,22 @@ macro_rules! from_unsigned {
 from_signed!(i8 i16 i32 i64 isize);
 from_unsigned!(u8 u16 u32 u64 usize);
 
+macro_rules! from_nonzero {
+    ($($nonzero_ty:ident)*) => {
+        $(
+            impl From<$nonzero_ty> for Number {
+                #[inline]
+                fn from(n: $nonzero_ty) -> Self {
+                    Number::from(n.get())
+                }
+            }
+        )*
+    };
+}
+
+from_nonzero!(NonZeroI8 NonZeroI16 NonZeroI32 NonZeroI64 NonZeroIsize);
+from_nonzero!(NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64 NonZeroUsize);
+
 impl Number {
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
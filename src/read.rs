@@ -9,7 +9,7 @@
 use std::{char, cmp, io, str};
 use std::ops::Deref;
 
-use iter::LineColIterator;
+use iter::{self, LineColIterator};
 
 use super::error::{Error, ErrorCode, Result};
 
@@ -18,7 +18,7 @@ use super::error::{Error, ErrorCode, Result};
 /// stable we can use actual specialization.
 ///
 /// This trait is sealed and cannot be implemented for types outside of
-/// `serde_json`.
+/// `sexpr`.
 pub trait Read<'de>: private::Sealed {
     #[doc(hidden)]
     fn next(&mut self) -> io::Result<Option<u8>>;
@@ -54,6 +54,16 @@ pub trait Read<'de>: private::Sealed {
     #[doc(hidden)]
     fn byte_offset(&self) -> usize;
 
+    /// Returns true if the unread input begins with `prefix`. Nothing is
+    /// consumed.
+    #[doc(hidden)]
+    fn starts_with(&mut self, prefix: &[u8]) -> io::Result<bool>;
+
+    /// Returns the byte `n` places past the one peek() would return, or
+    /// `None` if the input ends first. Nothing is consumed.
+    #[doc(hidden)]
+    fn peek_nth(&mut self, n: usize) -> io::Result<Option<u8>>;
+
     /// Assumes the previous byte was a quotation mark. Parses a JSON-escaped
     /// string until the next quotation mark using the given scratch space if
     /// necessary. The scratch space is initially empty.
@@ -74,6 +84,93 @@ pub trait Read<'de>: private::Sealed {
         &'s mut self,
         scratch: &'s mut Vec<u8>,
     ) -> Result<Reference<'de, 's, [u8]>>;
+
+    /// Parses an unescaped string like `parse_symbol`, also stopping before
+    /// any byte in `delimiters`.
+    #[doc(hidden)]
+    fn parse_symbol_until<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        delimiters: &[u8],
+    ) -> Result<Reference<'de, 's, str>>
+        where
+        Self: Sized,
+    {
+        if delimiters.is_empty() {
+            return self.parse_symbol(scratch);
+        }
+        loop {
+            match try!(self.peek().map_err(Error::io)) {
+                Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') | Some(b')') | Some(b']') | None => break,
+                Some(ch) if delimiters.contains(&ch) => break,
+                Some(ch) => {
+                    self.discard();
+                    scratch.push(ch);
+                }
+            }
+        }
+        as_str(self, scratch).map(Reference::Copied)
+    }
+
+    /// Assumes the opening delimiter has been consumed. Parses an escaped
+    /// string until the next occurrence of `close` into the scratch space.
+    /// A backslash followed by `close` stands for the closing delimiter
+    /// itself.
+    #[doc(hidden)]
+    fn parse_str_until<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        close: &[u8],
+    ) -> Result<Reference<'de, 's, str>>
+        where
+        Self: Sized,
+    {
+        loop {
+            if try!(self.starts_with(close).map_err(Error::io)) {
+                for _ in close {
+                    self.discard();
+                }
+                return as_str(self, scratch).map(Reference::Copied);
+            }
+            match try!(next_or_eof(self)) {
+                b'\\' => {
+                    if try!(self.starts_with(close).map_err(Error::io)) {
+                        for _ in close {
+                            self.discard();
+                        }
+                        scratch.extend_from_slice(close);
+                    } else {
+                        try!(parse_escape(self, scratch));
+                    }
+                }
+                ch => scratch.push(ch),
+            }
+        }
+    }
+
+    /// Assumes the opening delimiter has been consumed. Copies the input up
+    /// to the next occurrence of `close` into the scratch space as it is,
+    /// without processing escapes.
+    #[doc(hidden)]
+    fn parse_raw_str_until<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        close: &[u8],
+    ) -> Result<Reference<'de, 's, str>>
+        where
+        Self: Sized,
+    {
+        loop {
+            if try!(self.starts_with(close).map_err(Error::io)) {
+                for _ in close {
+                    self.discard();
+                }
+                return as_str(self, scratch).map(Reference::Copied);
+            }
+            let ch = try!(next_or_eof(self));
+            scratch.push(ch);
+        }
+    }
 }
 
 pub struct Position {
@@ -97,17 +194,18 @@ impl<'b, 'c, T: ?Sized + 'static> Deref for Reference<'b, 'c, T> {
     }
 }
 
-/// JSON input source that reads from a std::io input stream.
+/// S-expression input source that reads from a std::io input stream.
 pub struct IoRead<R>
     where
     R: io::Read,
 {
     iter: LineColIterator<io::Bytes<R>>,
-    /// Temporary storage of peeked byte.
-    ch: Option<u8>,
+    /// Temporary storage of peeked bytes, oldest first. Usually holds at most
+    /// one byte; `starts_with` may buffer more.
+    ch: Vec<u8>,
 }
 
-/// JSON input source that reads from a slice of bytes.
+/// S-expression input source that reads from a slice of bytes.
 //
 // This is more efficient than other iterators because peek() can be read-only
 // and we can compute line/col position only if an error happens.
@@ -117,7 +215,7 @@ pub struct SliceRead<'a> {
     index: usize,
 }
 
-/// JSON input source that reads from a UTF-8 string.
+/// S-expression input source that reads from a UTF-8 string.
 //
 // Able to elide UTF-8 checks by assuming that the input is valid UTF-8.
 pub struct StrRead<'a> {
@@ -139,7 +237,7 @@ impl<R> IoRead<R>
     pub fn new(reader: R) -> Self {
         IoRead {
             iter: LineColIterator::new(reader.bytes()),
-            ch: None,
+            ch: Vec::new(),
         }
     }
 }
@@ -194,7 +292,9 @@ impl<R> IoRead<R>
     {
         loop {
             match try!(self.next().map_err(Error::io)) {
-                Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') | Some(b')') | None => return result(self, scratch),
+                Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') | Some(b')') | Some(b']') | None => {
+                    return result(self, scratch)
+                }
                 Some(ch) => scratch.push(ch),
             }
         }
@@ -209,38 +309,36 @@ impl<'de, R> Read<'de> for IoRead<R>
 {
     #[inline]
     fn next(&mut self) -> io::Result<Option<u8>> {
-        match self.ch.take() {
-            Some(ch) => Ok(Some(ch)),
-            None => {
-                match self.iter.next() {
-                    Some(Err(err)) => Err(err),
-                    Some(Ok(ch)) => Ok(Some(ch)),
-                    None => Ok(None),
-                }
-            }
+        if !self.ch.is_empty() {
+            return Ok(Some(self.ch.remove(0)));
+        }
+        match self.iter.next() {
+            Some(Err(err)) => Err(err),
+            Some(Ok(ch)) => Ok(Some(ch)),
+            None => Ok(None),
         }
     }
 
     #[inline]
     fn peek(&mut self) -> io::Result<Option<u8>> {
-        match self.ch {
-            Some(ch) => Ok(Some(ch)),
-            None => {
-                match self.iter.next() {
-                    Some(Err(err)) => Err(err),
-                    Some(Ok(ch)) => {
-                        self.ch = Some(ch);
-                        Ok(self.ch)
-                    }
-                    None => Ok(None),
-                }
+        if let Some(&ch) = self.ch.first() {
+            return Ok(Some(ch));
+        }
+        match self.iter.next() {
+            Some(Err(err)) => Err(err),
+            Some(Ok(ch)) => {
+                self.ch.push(ch);
+                Ok(Some(ch))
             }
+            None => Ok(None),
         }
     }
 
     #[inline]
     fn discard(&mut self) {
-        self.ch = None;
+        if !self.ch.is_empty() {
+            self.ch.remove(0);
+        }
     }
 
     fn position(&self) -> Position {
@@ -257,10 +355,29 @@ impl<'de, R> Read<'de> for IoRead<R>
     }
 
     fn byte_offset(&self) -> usize {
-        match self.ch {
-            Some(_) => self.iter.byte_offset() - 1,
-            None => self.iter.byte_offset(),
+        self.iter.byte_offset() - self.ch.len()
+    }
+
+    fn starts_with(&mut self, prefix: &[u8]) -> io::Result<bool> {
+        while self.ch.len() < prefix.len() {
+            match self.iter.next() {
+                Some(Err(err)) => return Err(err),
+                Some(Ok(ch)) => self.ch.push(ch),
+                None => return Ok(false),
+            }
+        }
+        Ok(self.ch.starts_with(prefix))
+    }
+
+    fn peek_nth(&mut self, n: usize) -> io::Result<Option<u8>> {
+        while self.ch.len() <= n {
+            match self.iter.next() {
+                Some(Err(err)) => return Err(err),
+                Some(Ok(ch)) => self.ch.push(ch),
+                None => return Ok(None),
+            }
         }
+        Ok(Some(self.ch[n]))
     }
 
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
@@ -293,6 +410,19 @@ impl<'a> SliceRead<'a> {
         }
     }
 
+    /// Checks that the whole slice is valid UTF-8, failing with the byte
+    /// offset of the first invalid sequence.
+    pub fn validate_utf8(&self) -> Result<()> {
+        match str::from_utf8(self.slice) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let offset = err.valid_up_to();
+                let pos = self.position_of_index(offset);
+                Err(Error::syntax(ErrorCode::InvalidUtf8(offset), pos.line, pos.column + 1))
+            }
+        }
+    }
+
     fn position_of_index(&self, i: usize) -> Position {
         let mut pos = Position { line: 1, column: 0 };
         for ch in &self.slice[..i] {
@@ -301,6 +431,7 @@ impl<'a> SliceRead<'a> {
                     pos.line += 1;
                     pos.column = 0;
                 }
+                b if iter::is_continuation_byte(b) => {}
                 _ => {
                     pos.column += 1;
                 }
@@ -322,8 +453,9 @@ impl<'a> SliceRead<'a> {
         let start = self.index;
 
         loop {
-            match self.slice[self.index] {
-                b' ' | b'\n' | b'\t' | b'\r' | b')' =>   {
+            match self.slice.get(self.index) {
+                Some(&b' ') | Some(&b'\n') | Some(&b'\t') | Some(&b'\r') | Some(&b')') | Some(&b']')
+                | None => {
                     if scratch.is_empty() {
                         // Fast path: return a slice of the raw JSON without any
                         // copying.
@@ -447,6 +579,14 @@ impl<'a> Read<'a> for SliceRead<'a> {
         self.index
     }
 
+    fn starts_with(&mut self, prefix: &[u8]) -> io::Result<bool> {
+        Ok(self.slice[self.index..].starts_with(prefix))
+    }
+
+    fn peek_nth(&mut self, n: usize) -> io::Result<Option<u8>> {
+        Ok(self.slice.get(self.index + n).cloned())
+    }
+
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
         self.parse_str_bytes(scratch, true, as_str)
     }
@@ -502,6 +642,14 @@ impl<'a> Read<'a> for StrRead<'a> {
         self.delegate.byte_offset()
     }
 
+    fn starts_with(&mut self, prefix: &[u8]) -> io::Result<bool> {
+        self.delegate.starts_with(prefix)
+    }
+
+    fn peek_nth(&mut self, n: usize) -> io::Result<Option<u8>> {
+        self.delegate.peek_nth(n)
+    }
+
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
         self.delegate
             .parse_str_bytes(
@@ -0,0 +1,396 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Normalize the layout of S-expression text, keeping its comments.
+//!
+//! A `Sexp` has nowhere to keep comments, so rather than parse into one and
+//! serialize it back out, the text is read into a light tree of tokens that
+//! remembers every comment and whether it ended a line of code or sat on a
+//! line of its own. The input is parsed with `PrettyConfig::dialect` first,
+//! so only text that dialect accepts is reformatted, and the comments are
+//! taken from where the parser found them rather than searched for again.
+//!
+//! A list that fits in the configured width, and contains no line comments,
+//! is written on one line. Otherwise its first element stays on the line of
+//! the opening bracket and each later element starts a new line, indented by
+//! `PrettyConfig::indent` spaces from the bracket.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use config::{symbol_delimiters, ParseConfig, PrettyConfig};
+use de::{Comment, CommentBody, StreamDeserializer};
+use error::Result;
+use read::StrRead;
+use sexp::Sexp;
+
+/// Parse `input` and write it back out in a canonical layout, preserving
+/// comments.
+///
+/// ```rust,ignore
+/// let messy = "(package   (name \"sexpr\") ; crate name\n (version\n \"0.7.0\"))";
+/// let tidy = sexpr::reformat(messy, &PrettyConfig::default()).unwrap();
+/// assert_eq!(tidy, "(package\n  (name \"sexpr\") ; crate name\n  (version \"0.7.0\"))\n");
+/// ```
+pub fn reformat(input: &str, config: &PrettyConfig) -> Result<String> {
+    let mut dialect = config.dialect.clone();
+    dialect.retain_comments = true;
+    let mut stream = StreamDeserializer::<_, Sexp>::with_config(StrRead::new(input), dialect);
+    for value in stream.by_ref() {
+        try!(value);
+    }
+    let comments = comment_spans(input, stream.comments());
+
+    let nodes = Lexer {
+        input: input,
+        index: 0,
+        dialect: &config.dialect,
+        comments: &comments,
+    }.nodes();
+    let mut writer = Writer {
+        config: config,
+        out: String::new(),
+    };
+    for (i, node) in nodes.iter().enumerate() {
+        match *node {
+            Node::Comment { trailing: true, .. } if i > 0 => writer.out.push(' '),
+            _ if i > 0 => writer.out.push('\n'),
+            _ => {}
+        }
+        writer.write(node, 0);
+    }
+    if !nodes.is_empty() {
+        writer.out.push('\n');
+    }
+    Ok(writer.out)
+}
+
+/// What the parser found at the start of a comment. The extent of a datum
+/// comment is found by lexing its datum.
+#[derive(Clone, Copy)]
+enum Span {
+    Line(usize),
+    Block(usize),
+    Datum,
+}
+
+/// Maps the byte offset of each comment to its span.
+fn comment_spans(input: &str, comments: &[Comment]) -> HashMap<usize, Span> {
+    let mut line_starts = vec![0];
+    line_starts.extend(input.bytes().enumerate().filter(|&(_, ch)| ch == b'\n').map(|(i, _)| i + 1));
+
+    let mut spans = HashMap::new();
+    for comment in comments {
+        let offset = line_starts[comment.line - 1] + comment.column - 1;
+        let span = match comment.body {
+            CommentBody::Line(ref text) => Span::Line(text.len()),
+            CommentBody::Block(ref text) => Span::Block(text.len()),
+            CommentBody::Datum(_) => Span::Datum,
+        };
+        spans.insert(offset, span);
+    }
+    spans
+}
+
+enum Node<'a> {
+    Atom(&'a str),
+    List {
+        open: &'a str,
+        close: &'a str,
+        items: Vec<Node<'a>>,
+    },
+    /// A comment. `trailing` is set when it followed code on the same line,
+    /// and `line` when it runs to the end of its line.
+    Comment {
+        text: &'a str,
+        trailing: bool,
+        line: bool,
+    },
+}
+
+impl<'a> Node<'a> {
+    fn is_comment(&self) -> bool {
+        match *self {
+            Node::Comment { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn is_line_comment(&self) -> bool {
+        match *self {
+            Node::Comment { line, .. } => line,
+            _ => false,
+        }
+    }
+
+    /// The node written on a single line, or `None` if it holds a line
+    /// comment or a comment spanning several lines.
+    fn flat(&self) -> Option<String> {
+        match *self {
+            Node::Atom(text) => Some(text.to_owned()),
+            Node::Comment { line: true, .. } => None,
+            Node::Comment { text, .. } => {
+                if text.contains('\n') {
+                    None
+                } else {
+                    Some(text.to_owned())
+                }
+            }
+            Node::List { open, close, ref items } => {
+                let mut out = String::from(open);
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    match item.flat() {
+                        Some(flat) => out.push_str(&flat),
+                        None => return None,
+                    }
+                }
+                out.push_str(close);
+                Some(out)
+            }
+        }
+    }
+}
+
+struct Lexer<'a, 'c> {
+    input: &'a str,
+    index: usize,
+    dialect: &'c ParseConfig,
+    comments: &'c HashMap<usize, Span>,
+}
+
+impl<'a, 'c> Lexer<'a, 'c> {
+    /// Reads nodes up to the end of input or an unmatched closing bracket.
+    fn nodes(&mut self) -> Vec<Node<'a>> {
+        let mut nodes = Vec::new();
+        // Whether a newline has been seen since the last token.
+        let mut fresh_line = true;
+        loop {
+            if let Some(&span) = self.comments.get(&self.index) {
+                let start = self.index;
+                self.skip_comment(span);
+                nodes.push(Node::Comment {
+                    text: self.input[start..self.index].trim_right(),
+                    trailing: !fresh_line,
+                    line: match span {
+                        Span::Line(_) => true,
+                        _ => false,
+                    },
+                });
+                fresh_line = false;
+                continue;
+            }
+            let ch = match self.input.as_bytes().get(self.index) {
+                Some(&ch) => ch,
+                None => return nodes,
+            };
+            match ch {
+                b'\n' => {
+                    fresh_line = true;
+                    self.index += 1;
+                    continue;
+                }
+                b' ' | b'\t' | b'\r' => {
+                    self.index += 1;
+                    continue;
+                }
+                b',' if self.dialect.commas_are_whitespace => {
+                    self.index += 1;
+                    continue;
+                }
+                b')' => return nodes,
+                b']' if self.dialect.square_brackets => return nodes,
+                _ => nodes.push(self.datum()),
+            }
+            fresh_line = false;
+        }
+    }
+
+    /// Reads the list or atom starting at the current index.
+    fn datum(&mut self) -> Node<'a> {
+        let label_len = if self.dialect.datum_labels { self.label_len() } else { 0 };
+        let bytes = &self.input.as_bytes()[self.index + label_len..];
+        match bytes[0] {
+            b'(' => self.list(label_len + 1),
+            b'[' if self.dialect.square_brackets => self.list(label_len + 1),
+            b'#' if self.dialect.hash_vectors && bytes.get(1) == Some(&b'(') => self.list(label_len + 2),
+            _ => {
+                let start = self.index;
+                self.skip_atom();
+                Node::Atom(&self.input[start..self.index])
+            }
+        }
+    }
+
+    /// The length of a datum label such as `#1=` at the current index, or 0.
+    fn label_len(&self) -> usize {
+        let bytes = &self.input.as_bytes()[self.index..];
+        if bytes.first() != Some(&b'#') {
+            return 0;
+        }
+        let digits = bytes[1..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+        if digits > 0 && bytes.get(1 + digits) == Some(&b'=') && bytes.len() > 2 + digits {
+            2 + digits
+        } else {
+            0
+        }
+    }
+
+    fn list(&mut self, open_len: usize) -> Node<'a> {
+        let open = &self.input[self.index..self.index + open_len];
+        self.index += open_len;
+        let items = self.nodes();
+        let start = self.index;
+        if self.index < self.input.len() {
+            self.index += 1;
+        }
+        Node::List {
+            open: open,
+            close: &self.input[start..self.index],
+            items: items,
+        }
+    }
+
+    /// Skips the comment at the current index. A datum comment `#;` is
+    /// followed by a datum, possibly after whitespace and other comments.
+    fn skip_comment(&mut self, span: Span) {
+        match span {
+            Span::Line(len) | Span::Block(len) => {
+                self.index += len;
+                return;
+            }
+            Span::Datum => self.index += 2,
+        }
+        loop {
+            if let Some(&span) = self.comments.get(&self.index) {
+                self.skip_comment(span);
+                continue;
+            }
+            match self.input.as_bytes().get(self.index) {
+                Some(&b' ') | Some(&b'\n') | Some(&b'\t') | Some(&b'\r') => self.index += 1,
+                Some(&b',') if self.dialect.commas_are_whitespace => self.index += 1,
+                Some(_) => {
+                    self.datum();
+                    return;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Skips a string, a `|quoted symbol|`, a verbatim string such as
+    /// `5:a b c` or a run of symbol characters.
+    fn skip_atom(&mut self) {
+        let rest = &self.input[self.index..];
+        if self.dialect.raw_strings && rest.starts_with("\"\"\"") {
+            self.index += match rest[3..].find("\"\"\"") {
+                Some(end) => end + 6,
+                None => rest.len(),
+            };
+            return;
+        }
+        let first = rest.chars().next().unwrap_or(' ');
+        let close = if first == '|' {
+            Some('|')
+        } else {
+            self.dialect.string_delimiters.iter().find(|&&(open, _)| open == first).map(|&(_, close)| close)
+        };
+        if let Some(close) = close {
+            let mut escaped = false;
+            for (i, ch) in rest.char_indices().skip(1) {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == close {
+                    self.index += i + ch.len_utf8();
+                    return;
+                }
+            }
+            self.index = self.input.len();
+            return;
+        }
+
+        let bytes = self.input.as_bytes();
+        let start = self.index;
+        while self.index < bytes.len() && bytes[self.index].is_ascii_digit() {
+            self.index += 1;
+        }
+        if self.index > start && bytes.get(self.index) == Some(&b':') {
+            // The length counts bytes, which may include whitespace and
+            // brackets.
+            if let Ok(len) = self.input[start..self.index].parse::<usize>() {
+                self.index = cmp::min(self.index.saturating_add(1 + len), bytes.len());
+                while !self.input.is_char_boundary(self.index) {
+                    self.index += 1;
+                }
+                return;
+            }
+        }
+        let delimiters = symbol_delimiters(self.dialect);
+        while self.index < bytes.len() {
+            match bytes[self.index] {
+                b' ' | b'\t' | b'\r' | b'\n' | b'(' | b')' | b'"' => break,
+                b'[' | b']' if self.dialect.square_brackets => break,
+                ch if delimiters.contains(&ch) => break,
+                _ => self.index += 1,
+            }
+        }
+    }
+}
+
+struct Writer<'c> {
+    config: &'c PrettyConfig,
+    out: String,
+}
+
+impl<'c> Writer<'c> {
+    /// Writes `node` starting at `column`.
+    fn write(&mut self, node: &Node, column: usize) {
+        let (open, close, items) = match *node {
+            Node::Atom(text) | Node::Comment { text, .. } => {
+                self.out.push_str(text);
+                return;
+            }
+            Node::List { open, close, ref items } => (open, close, items),
+        };
+
+        if let Some(flat) = node.flat() {
+            if column + flat.chars().count() <= self.config.width {
+                self.out.push_str(&flat);
+                return;
+            }
+        }
+
+        let inner = column + self.config.indent;
+        self.out.push_str(open);
+        for (i, item) in items.iter().enumerate() {
+            let head = i == 0 && !item.is_comment();
+            match *item {
+                Node::Comment { trailing: true, .. } => self.out.push(' '),
+                _ if head => {}
+                _ => self.newline(inner),
+            }
+            let start = if head { column + open.chars().count() } else { inner };
+            self.write(item, start);
+        }
+        if items.last().map_or(false, Node::is_line_comment) {
+            self.newline(column);
+        }
+        self.out.push_str(close);
+    }
+
+    fn newline(&mut self, column: usize) {
+        self.out.push('\n');
+        for _ in 0..column {
+            self.out.push(' ');
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! (De)serialize a `chrono::DateTime<Utc>` as an RFC 3339 string such as
+//! `"2017-06-01T12:30:00Z"`. Requires the `chrono` feature.
+//!
+//! ```rust,ignore
+//! use chrono::{DateTime, Utc};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Entry {
+//!     #[serde(with = "sexpr::rfc3339")]
+//!     logged_at: DateTime<Utc>,
+//! }
+//! ```
+//!
+//! Timestamps with any offset are accepted and converted to UTC.
+
+use std::fmt;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserializer, Serializer};
+use serde::de::{self, Visitor};
+
+/// Serializes `time` as an RFC 3339 string in UTC.
+pub fn serialize<S>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+/// Deserializes a `DateTime<Utc>` from an RFC 3339 string.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(Rfc3339Visitor)
+}
+
+struct Rfc3339Visitor;
+
+impl<'de> Visitor<'de> for Rfc3339Visitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an RFC 3339 timestamp")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<DateTime<Utc>, E>
+    where
+        E: de::Error,
+    {
+        DateTime::parse_from_rfc3339(value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|err| E::custom(format_args!("invalid RFC 3339 timestamp `{}`: {}", value, err)))
+    }
+}
@@ -10,11 +10,15 @@
 
 use std::fmt;
 use std::io;
+use std::iter;
+use std::mem;
 use std::num::FpCategory;
 use std::str;
 
 use serde::ser::{self, Impossible};
 use super::error::{Error, ErrorCode, Result};
+use super::config::{MapStyle, SerializeConfig, UnitVariantStyle};
+use sexp::{self, Sexp, PAIR_TOKEN, VECTOR_TOKEN};
 
 use itoa;
 use dtoa;
@@ -23,6 +27,13 @@ use dtoa;
 pub struct Serializer<W, F = CompactFormatter> {
     writer: W,
     formatter: F,
+    config: SerializeConfig,
+    // Set while serializing a `Sexp::Vector`, so that the sequence written
+    // next is bracketed.
+    open_vector: bool,
+    // Set while serializing an improper list, so that the tuple written next
+    // ends in a dotted cdr.
+    open_pair: bool,
 }
 
 impl<W> Serializer<W>
@@ -47,6 +58,78 @@ where
     }
 }
 
+impl<W> Serializer<W, ElispFormatter>
+where
+    W: io::Write,
+{
+    /// Creates a serializer whose output Emacs Lisp can `read`: booleans and
+    /// nil are written as `t` and `nil`, and structs and maps as plists with
+    /// `:keyword` keys.
+    ///
+    /// ```rust,ignore
+    /// let mut ser = sexpr::Serializer::elisp(Vec::new());
+    /// (true, None::<u8>).serialize(&mut ser).unwrap();
+    /// assert_eq!(ser.into_inner(), b"(t nil)");
+    /// ```
+    #[inline]
+    pub fn elisp(writer: W) -> Self {
+        let mut config = SerializeConfig::default();
+        config.map_style = MapStyle::Plist;
+        Serializer::with_config(writer, ElispFormatter, config)
+    }
+}
+
+impl Serializer<Vec<u8>> {
+    /// Creates a serializer that writes into the allocation of `buffer`,
+    /// whose contents are discarded. Serializing value after value with
+    /// `serialize_into` then reuses the one buffer instead of allocating a
+    /// new `String` for each as `to_string` does.
+    ///
+    /// ```rust,ignore
+    /// let mut ser = sexpr::Serializer::with_buffer(String::with_capacity(64));
+    /// for n in 0..3 {
+    ///     assert_eq!(ser.serialize_into(&(n, "x")).unwrap(), sexpr::to_string(&(n, "x")).unwrap());
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_buffer(buffer: String) -> Self {
+        let mut writer = buffer.into_bytes();
+        writer.clear();
+        Serializer::new(writer)
+    }
+}
+
+impl<F> Serializer<Vec<u8>, F>
+where
+    F: Formatter,
+{
+    /// Discards everything written so far, keeping the buffer's capacity.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.writer.clear();
+        self.open_vector = false;
+        self.open_pair = false;
+    }
+
+    /// Replaces the contents of the buffer with `value` and returns them.
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to fail, if `T` contains a map with non-string keys, or if the
+    /// formatter writes bytes that are not valid UTF-8.
+    pub fn serialize_into<T: ?Sized>(&mut self, value: &T) -> Result<&str>
+    where
+        T: ser::Serialize,
+    {
+        self.reset();
+        try!(value.serialize(&mut *self));
+        // A formatter other than the ones in this module may write anything.
+        str::from_utf8(&self.writer)
+            .map_err(|err| Error::syntax(ErrorCode::InvalidUtf8(err.valid_up_to()), 0, 0))
+    }
+}
+
 impl<W, F> Serializer<W, F>
 where
     W: io::Write,
@@ -56,9 +139,19 @@ where
     /// specified.
     #[inline]
     pub fn with_formatter(writer: W, formatter: F) -> Self {
+        Serializer::with_config(writer, formatter, SerializeConfig::default())
+    }
+
+    /// Creates a new S-expression visitor that lays out values according to
+    /// `config`.
+    #[inline]
+    pub fn with_config(writer: W, formatter: F, config: SerializeConfig) -> Self {
         Serializer {
             writer: writer,
             formatter: formatter,
+            config: config,
+            open_vector: false,
+            open_pair: false,
         }
     }
 
@@ -67,6 +160,25 @@ where
     pub fn into_inner(self) -> W {
         self.writer
     }
+
+    /// Writes `value` as a symbol, quoted between `|` if the default rules
+    /// or the `SerializeConfig` call for it.
+    fn serialize_symbol<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        let n = try!(to_string(value));
+        let bare = &n[1 .. n.len() - 1];
+        let quoted = if self.config.bare_symbols.iter().any(|s| s == bare) {
+            false
+        } else {
+            needs_pipe_quotes(bare) ||
+                bare.chars().any(|c| self.config.quote_symbol_chars.contains(&c))
+        };
+        self.formatter
+            .write_symbol(&mut self.writer, bare, quoted)
+            .map_err(Error::io)
+    }
 }
 
 impl<'a, W, F> ser::Serializer for &'a mut Serializer<W, F>
@@ -87,6 +199,11 @@ where
 
     #[inline]
     fn serialize_bool(self, value: bool) -> Result<()> {
+        if let Some(style) = self.config.bool_style {
+            return self.writer
+                .write_all(style.spelling(value).as_bytes())
+                .map_err(Error::io);
+        }
         try!(
             self.formatter
                 .write_bool(&mut self.writer, value)
@@ -219,14 +336,17 @@ where
 
     #[inline]
     fn serialize_char(self, value: char) -> Result<()> {
-        try!(format_escaped_char(&mut self.writer, &mut self.formatter, value).map_err(Error::io));
+        try!(
+            format_escaped_char(&mut self.writer, &mut self.formatter, value, self.config.ascii_only)
+                .map_err(Error::io)
+        );
         Ok(())
     }
 
     #[inline]
     fn serialize_str(self, value: &str) -> Result<()> {
         try!(format_escaped_str(&mut self.writer,
-                                &mut self.formatter, value).map_err(Error::io));
+                                &mut self.formatter, value, self.config.ascii_only).map_err(Error::io));
         Ok(())
     }
 
@@ -251,8 +371,22 @@ where
     }
 
     #[inline]
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        self.serialize_unit()
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        if self.config.unit_struct_as_name {
+            try!(self.serialize_symbol(name));
+        } else {
+            try!(
+                self.formatter
+                    .begin_array(&mut self.writer)
+                    .map_err(Error::io)
+            );
+            try!(
+                self.formatter
+                    .end_array(&mut self.writer)
+                    .map_err(Error::io)
+            );
+        }
+        Ok(())
     }
 
     #[inline]
@@ -262,21 +396,32 @@ where
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.serialize_str(variant)
+        match self.config.unit_variant_style {
+            UnitVariantStyle::String => self.serialize_str(variant),
+            UnitVariantStyle::Symbol => self.serialize_symbol(variant),
+            UnitVariantStyle::Keyword => {
+                self.formatter
+                    .write_keyword(&mut self.writer, variant)
+                    .map_err(Error::io)
+            }
+        }
     }
 
     /// Serialize newtypes without an object wrapper.
     #[inline]
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ser::Serialize,
     {
-        try!(
-            self.formatter
-                .write_bare_string(&mut self.writer, value)
-                .map_err(Error::io)
-        );
-        Ok(())
+        if name == VECTOR_TOKEN {
+            self.open_vector = true;
+            return value.serialize(self);
+        }
+        if name == PAIR_TOKEN {
+            self.open_pair = true;
+            return value.serialize(self);
+        }
+        self.serialize_symbol(value)
     }
 
     #[inline]
@@ -340,33 +485,44 @@ where
 
     #[inline]
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        if len == Some(0) {
+        let mut vector = mem::replace(&mut self.open_vector, false);
+        if vector && self.config.hash_vectors {
+            // `#(` ... `)`, a list behind a `#`.
+            try!(self.writer.write_all(b"#").map_err(Error::io));
+            vector = false;
+        }
+        if vector {
             try!(
                 self.formatter
-                    .begin_array(&mut self.writer)
+                    .begin_vector(&mut self.writer)
                     .map_err(Error::io)
             );
+        } else {
             try!(
                 self.formatter
-                    .end_array(&mut self.writer)
+                    .begin_array(&mut self.writer)
                     .map_err(Error::io)
             );
+        }
+        if len == Some(0) {
+            try!(end_seq(&mut self.formatter, &mut self.writer, vector).map_err(Error::io));
             Ok(
                 Compound {
                     ser: self,
                     state: State::Empty,
+                    pair: false,
+                    remaining: 0,
+                    vector: vector,
                 },
             )
         } else {
-            try!(
-                self.formatter
-                    .begin_array(&mut self.writer)
-                    .map_err(Error::io)
-            );
             Ok(
                 Compound {
                     ser: self,
                     state: State::First,
+                    pair: false,
+                    remaining: 0,
+                    vector: vector,
                 },
             )
         }
@@ -374,7 +530,12 @@ where
 
     #[inline]
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
-        self.serialize_seq(Some(len))
+        let dotted = mem::replace(&mut self.open_pair, false);
+        let pair = dotted || (len == 2 && self.config.two_tuples_as_pairs);
+        let mut compound = try!(self.serialize_seq(Some(len)));
+        compound.pair = pair;
+        compound.remaining = len;
+        Ok(compound)
     }
 
     #[inline]
@@ -383,7 +544,7 @@ where
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        self.serialize_seq(Some(len))
+        self.serialize_tuple(len)
     }
 
     #[inline]
@@ -394,28 +555,11 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        try!(
-            self.formatter
-                .begin_object(&mut self.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.formatter
-                .begin_object_key(&mut self.writer, true)
-                .map_err(Error::io)
-        );
-        try!(self.serialize_str(variant));
-        try!(
-            self.formatter
-                .end_object_key(&mut self.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.formatter
-                .begin_object_value(&mut self.writer)
-                .map_err(Error::io)
-        );
-        self.serialize_seq(Some(len))
+        // Written as `((variant Name) args...)`, the form read back by
+        // `deserialize_enum`.
+        let mut compound = try!(self.serialize_seq(Some(len + 1)));
+        try!(ser::SerializeSeq::serialize_element(&mut compound, &sexp::variant_tag(variant)));
+        Ok(compound)
     }
 
     #[inline]
@@ -435,6 +579,9 @@ where
                 Compound {
                     ser: self,
                     state: State::Empty,
+                    pair: false,
+                    remaining: 0,
+                    vector: false,
                 },
             )
         } else {
@@ -447,6 +594,9 @@ where
                 Compound {
                     ser: self,
                     state: State::First,
+                    pair: false,
+                    remaining: 0,
+                    vector: false,
                 },
             )
         }
@@ -498,6 +648,7 @@ where
         struct Adapter<'ser, W: 'ser, F: 'ser> {
             writer: &'ser mut W,
             formatter: &'ser mut F,
+            ascii_only: bool,
             error: Option<io::Error>,
         }
 
@@ -508,7 +659,7 @@ where
         {
             fn write_str(&mut self, s: &str) -> fmt::Result {
                 assert!(self.error.is_none());
-                match format_escaped_str_contents(self.writer, self.formatter, s) {
+                match format_escaped_str_contents(self.writer, self.formatter, s, self.ascii_only) {
                     Ok(()) => Ok(()),
                     Err(err) => {
                         self.error = Some(err);
@@ -527,6 +678,7 @@ where
             let mut adapter = Adapter {
                 writer: &mut self.writer,
                 formatter: &mut self.formatter,
+                ascii_only: self.config.ascii_only,
                 error: None,
             };
             match write!(adapter, "{}", value) {
@@ -557,6 +709,24 @@ pub enum State {
 pub struct Compound<'a, W: 'a, F: 'a> {
     ser: &'a mut Serializer<W, F>,
     state: State,
+    // Write the last element as the cdr of a dotted list.
+    pair: bool,
+    // Elements still to be written, when `pair` is set.
+    remaining: usize,
+    // Close with `]` rather than `)`.
+    vector: bool,
+}
+
+fn end_seq<W: ?Sized, F: ?Sized>(formatter: &mut F, writer: &mut W, vector: bool) -> io::Result<()>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    if vector {
+        formatter.end_vector(writer)
+    } else {
+        formatter.end_array(writer)
+    }
 }
 
 impl<'a, W, F> ser::SerializeSeq for Compound<'a, W, F>
@@ -594,12 +764,7 @@ where
         match self.state {
             State::Empty => {}
             _ => {
-                try!(
-                    self.ser
-                        .formatter
-                        .end_array(&mut self.ser.writer)
-                        .map_err(Error::io)
-                )
+                try!(end_seq(&mut self.ser.formatter, &mut self.ser.writer, self.vector).map_err(Error::io))
             }
         }
         Ok(())
@@ -619,6 +784,16 @@ where
     where
         T: ser::Serialize,
     {
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.pair && self.remaining == 0 && self.state == State::Rest {
+            try!(
+                self.ser
+                    .formatter
+                    .begin_pair_cdr(&mut self.ser.writer)
+                    .map_err(Error::io)
+            );
+            return value.serialize(&mut *self.ser);
+        }
         ser::SerializeSeq::serialize_element(self, value)
     }
 
@@ -641,12 +816,12 @@ where
     where
         T: ser::Serialize,
     {
-        ser::SerializeSeq::serialize_element(self, value)
+        ser::SerializeTuple::serialize_element(self, value)
     }
 
     #[inline]
     fn end(self) -> Result<()> {
-        ser::SerializeSeq::end(self)
+        ser::SerializeTuple::end(self)
     }
 }
 
@@ -668,30 +843,7 @@ where
 
     #[inline]
     fn end(self) -> Result<()> {
-        match self.state {
-            State::Empty => {}
-            _ => {
-                try!(
-                    self.ser
-                        .formatter
-                        .end_array(&mut self.ser.writer)
-                        .map_err(Error::io)
-                )
-            }
-        }
-        try!(
-            self.ser
-                .formatter
-                .end_object_value(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_object(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        ser::SerializeSeq::end(self)
     }
 }
 
@@ -708,6 +860,26 @@ where
     where
         T: ser::Serialize,
     {
+        if self.ser.config.map_style == MapStyle::Plist {
+            try!(
+                self.ser
+                    .formatter
+                    .begin_plist_key(&mut self.ser.writer, self.state == State::First)
+                    .map_err(Error::io)
+            );
+            self.state = State::Rest;
+
+            let name = match try!(sexp::to_value(key)) {
+                Sexp::Atom(a) => a.into_inner(),
+                Sexp::Number(ref n) if n.is_u64() || n.is_i64() => n.to_string(),
+                _ => return Err(key_must_be_a_string()),
+            };
+            return self.ser
+                .formatter
+                .write_keyword(&mut self.ser.writer, &name)
+                .map_err(Error::io);
+        }
+
         try!(
             self.ser
                 .formatter
@@ -732,12 +904,22 @@ where
     where
         T: ser::Serialize,
     {
-        try!(
-            self.ser
-                .formatter
-                .begin_object_value(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
+        let begin = match self.ser.config.map_style {
+            MapStyle::DottedPair => self.ser.formatter.begin_object_value(&mut self.ser.writer),
+            MapStyle::NestedList => {
+                self.ser.formatter.begin_nested_object_value(&mut self.ser.writer)
+            }
+            MapStyle::Plist => {
+                try!(
+                    self.ser
+                        .formatter
+                        .begin_plist_value(&mut self.ser.writer)
+                        .map_err(Error::io)
+                );
+                return value.serialize(&mut *self.ser);
+            }
+        };
+        try!(begin.map_err(Error::io));
         try!(value.serialize(&mut *self.ser));
         try!(
             self.ser
@@ -778,6 +960,9 @@ where
     where
         T: ser::Serialize,
     {
+        if self.ser.config.skip_none_fields && try!(serializes_to_nil(value)) {
+            return Ok(());
+        }
         try!(ser::SerializeMap::serialize_key(self, key));
         ser::SerializeMap::serialize_value(self, value)
     }
@@ -788,6 +973,296 @@ where
     }
 }
 
+/// Finds out whether `value` is written as nil, without writing it.
+fn serializes_to_nil<T: ?Sized>(value: &T) -> Result<bool>
+where
+    T: ser::Serialize,
+{
+    value.serialize(NilProbe)
+}
+
+/// Answers whether a value is written as `#nil`. Only the outermost layer of
+/// the value is looked at: anything that opens a list is not nil, so its
+/// elements are never visited.
+struct NilProbe;
+
+impl ser::Serializer for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    type SerializeSeq = NilProbe;
+    type SerializeTuple = NilProbe;
+    type SerializeTupleStruct = NilProbe;
+    type SerializeTupleVariant = NilProbe;
+    type SerializeMap = NilProbe;
+    type SerializeStruct = NilProbe;
+    type SerializeStructVariant = NilProbe;
+
+    fn serialize_bool(self, _value: bool) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_i8(self, _value: i8) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_i16(self, _value: i16) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_i32(self, _value: i32) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_i64(self, _value: i64) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_u8(self, _value: u8) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_u16(self, _value: u16) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_u32(self, _value: u32) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_u64(self, _value: u64) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_char(self, _value: char) -> Result<bool> {
+        Ok(false)
+    }
+
+    // NaN and the infinities are written as nil.
+    fn serialize_f32(self, value: f32) -> Result<bool> {
+        Ok(!value.is_finite())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<bool> {
+        Ok(!value.is_finite())
+    }
+
+    fn serialize_str(self, _value: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_none(self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<bool>
+    where
+        T: ser::Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<bool>
+    where
+        T: ser::Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<bool>
+    where
+        T: ser::Serialize,
+    {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<NilProbe> {
+        Ok(NilProbe)
+    }
+}
+
+impl ser::SerializeSeq for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeTuple for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeTupleStruct for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeTupleVariant for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeMap for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeStruct for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl ser::SerializeStructVariant for NilProbe {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, _value: &T) -> Result<()>
+    where
+        T: ser::Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
 impl<'a, W, F> ser::SerializeStructVariant for Compound<'a, W, F>
 where
     W: io::Write,
@@ -885,179 +1360,35 @@ where
     }
 
     fn serialize_i8(self, value: i8) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_i8(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_i8(value)
     }
 
     fn serialize_i16(self, value: i16) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_i16(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_i16(value)
     }
 
     fn serialize_i32(self, value: i32) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_i32(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
-    }
-
-    fn serialize_i64(self, value: i64) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_i64(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_i32(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<()> {
+        self.ser.serialize_i64(value)
     }
 
     fn serialize_u8(self, value: u8) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_u8(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_u8(value)
     }
 
     fn serialize_u16(self, value: u16) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_u16(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_u16(value)
     }
 
     fn serialize_u32(self, value: u32) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_u32(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_u32(value)
     }
 
     fn serialize_u64(self, value: u64) -> Result<()> {
-        try!(
-            self.ser
-                .formatter
-                .begin_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .write_u64(&mut self.ser.writer, value)
-                .map_err(Error::io)
-        );
-        try!(
-            self.ser
-                .formatter
-                .end_string(&mut self.ser.writer)
-                .map_err(Error::io)
-        );
-        Ok(())
+        self.ser.serialize_u64(value)
     }
 
     fn serialize_f32(self, _value: f32) -> Result<()> {
@@ -1174,6 +1505,9 @@ pub enum CharEscape {
     /// An escaped ASCII plane control character (usually escaped as
     /// `\u00XX` where `XX` are two hex characters)
     AsciiControl(u8),
+    /// A UTF-16 code unit of a non-ASCII character, escaped as `\uXXXX` when
+    /// `SerializeConfig::ascii_only` is set
+    Utf16(u16),
 }
 
 impl CharEscape {
@@ -1211,7 +1545,6 @@ pub trait Formatter {
     where
         W: io::Write,
     {
-        // XXX - This needs to be configurable
         let s = if value {
             b"#t" as &[u8]
         } else {
@@ -1310,7 +1643,8 @@ pub trait Formatter {
         dtoa::write(writer, value).map(|_| ())
     }
 
-    /// Write a string without any enclosing quotes
+    /// Write a string without any enclosing quotes, or between `|` when it
+    /// would not read back as a single symbol.
     #[inline]
     fn write_bare_string<W: ?Sized, T: ?Sized>(&mut self, writer: &mut W, value: &T) -> io::Result<()>
         where
@@ -1318,7 +1652,22 @@ pub trait Formatter {
         T: ser::Serialize,
     {
         let n = to_string(value).unwrap();
-        writer.write_all(&n[1 .. n.len() - 1].as_bytes())
+        let bare = &n[1 .. n.len() - 1];
+        self.write_symbol(writer, bare, needs_pipe_quotes(bare))
+    }
+
+    /// Writes the escaped symbol name `bare`, between `|` if `quoted` is set.
+    #[inline]
+    fn write_symbol<W: ?Sized>(&mut self, writer: &mut W, bare: &str, quoted: bool) -> io::Result<()>
+        where
+        W: io::Write,
+    {
+        if !quoted {
+            return writer.write_all(bare.as_bytes());
+        }
+        try!(writer.write_all(b"|"));
+        try!(writer.write_all(bare.replace('|', "\\|").as_bytes()));
+        writer.write_all(b"|")
     }
 
     /// Called before each series of `write_string_fragment` and
@@ -1384,6 +1733,7 @@ pub trait Formatter {
                 ];
                 return writer.write_all(bytes);
             }
+            Utf16(unit) => return write!(writer, "\\u{:04x}", unit),
         };
 
         writer.write_all(s)
@@ -1409,6 +1759,26 @@ pub trait Formatter {
         writer.write_all(b")")
     }
 
+    /// Called before a bracketed `Sexp::Vector`.  Writes a `[` to the
+    /// specified writer.
+    #[inline]
+    fn begin_vector<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b"[")
+    }
+
+    /// Called after a bracketed `Sexp::Vector`.  Writes a `]` to the
+    /// specified writer.
+    #[inline]
+    fn end_vector<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b"]")
+    }
+
     /// Called before every array value.  Writes a space if needed to
     /// the specified writer.
     #[inline]
@@ -1432,6 +1802,16 @@ pub trait Formatter {
         Ok(())
     }
 
+    /// Called before the cdr of a dotted pair.  Writes a ` . ` to the
+    /// specified writer.
+    #[inline]
+    fn begin_pair_cdr<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b" . ")
+    }
+
     /// Called before every object.  Writes a `(` to the specified
     /// writer.
     #[inline]
@@ -1452,20 +1832,21 @@ pub trait Formatter {
         writer.write_all(b")")
     }
 
-    /// Called before every object key.
+    /// Called before every object key.  Writes a space if needed and
+    /// the `(` opening the entry to the specified writer.
     #[inline]
     fn begin_object_key<W: ?Sized>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
     where
         W: io::Write,
     {
         if first {
-            Ok(())
+            writer.write_all(b"(")
         } else {
-            writer.write_all(b" ")
+            writer.write_all(b" (")
         }
     }
 
-    /// Called after every object key.  A `.` should be written to the
+    /// Called after every object key.  A ` . ` should be written to the
     /// specified writer by either this method or
     /// `begin_object_value`.
     #[inline]
@@ -1476,7 +1857,7 @@ pub trait Formatter {
         Ok(())
     }
 
-    /// Called before every object value.  A `.` should be written to
+    /// Called before every object value.  A ` . ` should be written to
     /// the specified writer by either this method or
     /// `end_object_key`.
     #[inline]
@@ -1484,16 +1865,61 @@ pub trait Formatter {
     where
         W: io::Write,
     {
-        writer.write_all(b".")
+        writer.write_all(b" . ")
     }
 
-    /// Called after every object value.
+    /// Called in place of `begin_object_value` when entries are written as
+    /// two-element lists.  Writes a ` ` to the specified writer.
     #[inline]
-    fn end_object_value<W: ?Sized>(&mut self, _writer: &mut W) -> io::Result<()>
+    fn begin_nested_object_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        Ok(())
+        writer.write_all(b" ")
+    }
+
+    /// Called after every object value.  Writes the `)` closing the
+    /// entry to the specified writer.
+    #[inline]
+    fn end_object_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b")")
+    }
+
+    /// Called before every key of an object written as a plist.  Writes a
+    /// space if needed to the specified writer.
+    #[inline]
+    fn begin_plist_key<W: ?Sized>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b" ")
+        }
+    }
+
+    /// Called before every value of an object written as a plist.  Writes a
+    /// space to the specified writer.
+    #[inline]
+    fn begin_plist_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b" ")
+    }
+
+    /// Writes the keyword `name` to the specified writer, as `#:name`.
+    #[inline]
+    fn write_keyword<W: ?Sized>(&mut self, writer: &mut W, name: &str) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        try!(writer.write_all(b"#:"));
+        writer.write_all(name.as_bytes())
     }
 }
 
@@ -1503,6 +1929,38 @@ pub struct CompactFormatter;
 
 impl Formatter for CompactFormatter {}
 
+/// This structure writes compact S-expressions in the spelling Emacs Lisp
+/// reads: `t` and `nil` for booleans and nil, and `:name` for keywords.
+#[derive(Clone, Debug)]
+pub struct ElispFormatter;
+
+impl Formatter for ElispFormatter {
+    #[inline]
+    fn write_null<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(b"nil")
+    }
+
+    #[inline]
+    fn write_bool<W: ?Sized>(&mut self, writer: &mut W, value: bool) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writer.write_all(if value { b"t" } else { b"nil" })
+    }
+
+    #[inline]
+    fn write_keyword<W: ?Sized>(&mut self, writer: &mut W, name: &str) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        try!(writer.write_all(b":"));
+        writer.write_all(name.as_bytes())
+    }
+}
+
 /// This structure pretty prints a S-expression value to make it human readable.
 #[derive(Clone, Debug)]
 pub struct PrettyFormatter<'a> {
@@ -1559,6 +2017,31 @@ impl<'a> Formatter for PrettyFormatter<'a> {
         writer.write_all(b")")
     }
 
+    #[inline]
+    fn begin_vector<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        self.current_indent += 1;
+        self.has_value = false;
+        writer.write_all(b"[")
+    }
+
+    #[inline]
+    fn end_vector<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        self.current_indent -= 1;
+
+        if self.has_value {
+            try!(writer.write_all(b"\n"));
+            try!(indent(writer, self.current_indent, self.indent));
+        }
+
+        writer.write_all(b"]")
+    }
+
     #[inline]
     fn begin_array_value<W: ?Sized>(&mut self, writer: &mut W, _first: bool) -> io::Result<()>
     where
@@ -1585,7 +2068,7 @@ impl<'a> Formatter for PrettyFormatter<'a> {
     {
         self.current_indent += 1;
         self.has_value = false;
-        writer.write_all(b"{")
+        writer.write_all(b"(")
     }
 
     #[inline]
@@ -1600,19 +2083,26 @@ impl<'a> Formatter for PrettyFormatter<'a> {
             try!(indent(writer, self.current_indent, self.indent));
         }
 
-        writer.write_all(b"}")
+        writer.write_all(b")")
     }
 
     #[inline]
-    fn begin_object_key<W: ?Sized>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    fn begin_object_key<W: ?Sized>(&mut self, writer: &mut W, _first: bool) -> io::Result<()>
     where
         W: io::Write,
     {
-        if first {
-            try!(writer.write_all(b"\n"));
-        } else {
-            try!(writer.write_all(b",\n"));
-        }
+        try!(writer.write_all(b"\n"));
+        try!(indent(writer, self.current_indent, self.indent));
+        writer.write_all(b"(")
+    }
+
+    #[inline]
+    fn begin_plist_key<W: ?Sized>(&mut self, writer: &mut W, _first: bool) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        self.has_value = true;
+        try!(writer.write_all(b"\n"));
         indent(writer, self.current_indent, self.indent)
     }
 
@@ -1621,16 +2111,16 @@ impl<'a> Formatter for PrettyFormatter<'a> {
     where
         W: io::Write,
     {
-        writer.write_all(b": ")
+        writer.write_all(b" . ")
     }
 
     #[inline]
-    fn end_object_value<W: ?Sized>(&mut self, _writer: &mut W) -> io::Result<()>
+    fn end_object_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
         self.has_value = true;
-        Ok(())
+        writer.write_all(b")")
     }
 }
 
@@ -1638,13 +2128,14 @@ fn format_escaped_str<W: ?Sized, F: ?Sized>(
     writer: &mut W,
     formatter: &mut F,
     value: &str,
+    ascii_only: bool,
 ) -> io::Result<()>
 where
     W: io::Write,
     F: Formatter,
 {
     try!(formatter.begin_string(writer));
-    try!(format_escaped_str_contents(writer, formatter, value));
+    try!(format_escaped_str_contents(writer, formatter, value, ascii_only));
     try!(formatter.end_string(writer));
     Ok(())
 }
@@ -1653,6 +2144,7 @@ fn format_escaped_str_contents<W: ?Sized, F: ?Sized>(
     writer: &mut W,
     formatter: &mut F,
     value: &str,
+    ascii_only: bool,
 ) -> io::Result<()>
 where
     W: io::Write,
@@ -1663,8 +2155,13 @@ where
     let mut start = 0;
 
     for (i, &byte) in bytes.iter().enumerate() {
+        // The rest of a non-ASCII character already escaped below.
+        if i < start {
+            continue;
+        }
+
         let escape = ESCAPE[byte as usize];
-        if escape == 0 {
+        if escape == 0 && !(ascii_only && byte >= 0x80) {
             continue;
         }
 
@@ -1672,6 +2169,16 @@ where
             try!(formatter.write_string_fragment(writer, &value[start..i]));
         }
 
+        if escape == 0 {
+            let c = value[i..].chars().next().unwrap();
+            let mut units = [0; 2];
+            for &unit in encode_utf16(c, &mut units) {
+                try!(formatter.write_char_escape(writer, CharEscape::Utf16(unit)));
+            }
+            start = i + c.len_utf8();
+            continue;
+        }
+
         let char_escape = CharEscape::from_escape_table(escape, byte);
         try!(formatter.write_char_escape(writer, char_escape));
 
@@ -1722,6 +2229,7 @@ fn format_escaped_char<W: ?Sized, F: ?Sized>(
     wr: &mut W,
     formatter: &mut F,
     value: char,
+    ascii_only: bool,
 ) -> io::Result<()>
 where
     W: io::Write,
@@ -1736,7 +2244,21 @@ where
     // the method `char::encode_utf8`.
     // See https://github.com/serde-rs/json/issues/270.
     let slice = unsafe { str::from_utf8_unchecked(&buf[0..value.len_utf8()]) };
-    format_escaped_str(wr, formatter, slice)
+    format_escaped_str(wr, formatter, slice, ascii_only)
+}
+
+/// The UTF-16 encoding of `c`, one code unit or a surrogate pair.
+fn encode_utf16(c: char, buf: &mut [u16; 2]) -> &[u16] {
+    let n = c as u32;
+    if n < 0x1_0000 {
+        buf[0] = n as u16;
+        &buf[..1]
+    } else {
+        let n = n - 0x1_0000;
+        buf[0] = 0xD800 | (n >> 10) as u16;
+        buf[1] = 0xDC00 | (n & 0x3FF) as u16;
+        &buf[..]
+    }
 }
 
 /// Serialize the given data structure as S-expression into the IO stream.
@@ -1825,6 +2347,141 @@ where
     Ok(string)
 }
 
+/// Serialize the given data structure as a String of S-expression, preceded
+/// by `header` written as `;` line comments, one per line of `header`.
+///
+/// The comments are skipped when the output is read back.
+///
+/// ```rust,ignore
+/// let s = sexpr::to_string_with_header(&vec![1, 2], "generated").unwrap();
+/// assert_eq!(s, "; generated\n(1 2)");
+/// ```
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn to_string_with_header<T: ?Sized>(value: &T, header: &str) -> Result<String>
+where
+    T: ser::Serialize,
+{
+    let body = try!(to_string(value));
+    let mut string = String::with_capacity(header.len() + body.len() + 8);
+    for line in header.lines() {
+        string.push(';');
+        if !line.is_empty() {
+            string.push(' ');
+            string.push_str(line);
+        }
+        string.push('\n');
+    }
+    string.push_str(&body);
+    Ok(string)
+}
+
+/// Serialize the given data structure as a String of S-expression, laid out
+/// according to `config`.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+#[inline]
+pub fn to_string_with_config<T: ?Sized>(value: &T, config: SerializeConfig) -> Result<String>
+where
+    T: ser::Serialize,
+{
+    let wrap_width = config.wrap_width;
+    let mut writer = Vec::with_capacity(128);
+    {
+        let mut ser = Serializer::with_config(&mut writer, CompactFormatter, config);
+        try!(value.serialize(&mut ser));
+    }
+    let string = unsafe {
+        // We do not emit invalid UTF-8.
+        String::from_utf8_unchecked(writer)
+    };
+    match wrap_width {
+        Some(width) => Ok(wrap(&string, width)),
+        None => Ok(string),
+    }
+}
+
+/// Reflows compact S-expression text so that lines stay within `width`
+/// columns where possible, breaking only at the spaces between elements.
+fn wrap(text: &str, width: usize) -> String {
+    let mut out = String::with_capacity(text.len());
+    // The column just inside each open bracket.
+    let mut indents = vec![0];
+    let mut column = 0;
+    for (n, element) in split_elements(text).into_iter().enumerate() {
+        let indent = *indents.last().unwrap();
+        if n > 0 {
+            if column + 1 + element.chars().count() > width && column > indent {
+                out.push('\n');
+                out.extend(iter::repeat(' ').take(indent));
+                column = indent;
+            } else {
+                out.push(' ');
+                column += 1;
+            }
+        }
+
+        let mut quote = None;
+        let mut escaped = false;
+        for c in element.chars() {
+            column += 1;
+            if escaped {
+                escaped = false;
+            } else if let Some(q) = quote {
+                if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            } else {
+                match c {
+                    '"' | '|' => quote = Some(c),
+                    '(' | '[' => indents.push(column),
+                    ')' | ']' if indents.len() > 1 => {
+                        indents.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        out.push_str(element);
+    }
+    out
+}
+
+/// Splits compact S-expression text at the spaces outside of strings and
+/// `|...|` symbols.
+fn split_elements(text: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if let Some(q) = quote {
+            if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else if c == '"' || c == '|' {
+            quote = Some(c);
+        } else if c == ' ' {
+            elements.push(&text[start..i]);
+            start = i + 1;
+        }
+    }
+    elements.push(&text[start..]);
+    elements
+}
+
 /// Serialize the given data structure as a pretty-printed String of S-expression.
 ///
 /// # Errors
@@ -1844,6 +2501,20 @@ where
     Ok(string)
 }
 
+/// Whether the escaped symbol name `bare` must be written as `|...|` to be
+/// read back as the same symbol: it is empty, does not begin with a letter,
+/// or contains a delimiter or an escape sequence.
+fn needs_pipe_quotes(bare: &str) -> bool {
+    match bare.as_bytes().first() {
+        Some(&b'a'...b'z') | Some(&b'A'...b'Z') => {}
+        _ => return true,
+    }
+    bare.bytes().any(|b| match b {
+        b' ' | b'\t' | b'\n' | b'\r' | b'(' | b')' | b'[' | b']' | b'"' | b'|' | b'\\' => true,
+        _ => false,
+    })
+}
+
 fn indent<W: ?Sized>(wr: &mut W, n: usize, s: &[u8]) -> io::Result<()>
 where
     W: io::Write,
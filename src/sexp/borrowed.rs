@@ -0,0 +1,95 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A `Sexp` whose atoms borrow from the input they were parsed from.
+
+use std::borrow::Cow;
+
+use atom::Atom;
+use number::Number;
+use super::Sexp;
+
+/// A S-expression value whose symbols, keywords and strings borrow directly
+/// from the parsed input where possible, avoiding an allocation per atom.
+///
+/// Atoms are only copied when the text cannot be used as is, such as a
+/// string containing escape sequences. Produced by `from_slice_borrowed` and
+/// `from_str_borrowed`; `into_owned` converts to an ordinary `Sexp`.
+///
+/// ```rust,ignore
+/// let input = br#"(define greeting "hello")"#;
+/// let v = sexpr::from_slice_borrowed(input).unwrap();
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub enum BorrowedSexp<'a> {
+    /// The nil value, `#nil`.
+    Nil,
+    /// A symbol, `name`.
+    Symbol(Cow<'a, str>),
+    /// A keyword, `#:name`, without its prefix.
+    Keyword(Cow<'a, str>),
+    /// A string, `"text"`, without its delimiters.
+    String(Cow<'a, str>),
+    /// An integer or floating point number.
+    Number(Number),
+    /// A boolean, `#t` or `#f`.
+    Boolean(bool),
+    /// A cons-pair, as in `Sexp::Pair`.
+    Pair(Option<Box<BorrowedSexp<'a>>>, Option<Box<BorrowedSexp<'a>>>),
+    /// A parenthesized list.
+    List(Vec<BorrowedSexp<'a>>),
+    /// A square bracketed list, as in `Sexp::Vector`.
+    Vector(Vec<BorrowedSexp<'a>>),
+}
+
+impl<'a> BorrowedSexp<'a> {
+    /// Converts into an owned `Sexp`, copying any borrowed atoms.
+    pub fn into_owned(self) -> Sexp {
+        match self {
+            BorrowedSexp::Nil => Sexp::Nil,
+            BorrowedSexp::Symbol(s) => Sexp::Atom(Atom::into_symbol(s.into_owned())),
+            BorrowedSexp::Keyword(s) => Sexp::Atom(Atom::into_keyword(s.into_owned())),
+            BorrowedSexp::String(s) => Sexp::Atom(Atom::into_string(s.into_owned())),
+            BorrowedSexp::Number(n) => Sexp::Number(n),
+            BorrowedSexp::Boolean(b) => Sexp::Boolean(b),
+            BorrowedSexp::Pair(car, cdr) => {
+                Sexp::Pair(car.map(|car| Box::new(car.into_owned())),
+                           cdr.map(|cdr| Box::new(cdr.into_owned())))
+            }
+            BorrowedSexp::List(elts) => Sexp::List(elts.into_iter().map(BorrowedSexp::into_owned).collect()),
+            BorrowedSexp::Vector(elts) => {
+                Sexp::Vector(elts.into_iter().map(BorrowedSexp::into_owned).collect())
+            }
+        }
+    }
+}
+
+impl<'a> From<Sexp> for BorrowedSexp<'a> {
+    fn from(sexp: Sexp) -> Self {
+        match sexp {
+            Sexp::Nil => BorrowedSexp::Nil,
+            Sexp::Atom(atom) => {
+                if atom.is_symbol() {
+                    BorrowedSexp::Symbol(Cow::Owned(atom.into_inner()))
+                } else if atom.is_keyword() {
+                    BorrowedSexp::Keyword(Cow::Owned(atom.into_inner()))
+                } else {
+                    BorrowedSexp::String(Cow::Owned(atom.into_inner()))
+                }
+            }
+            Sexp::Number(n) => BorrowedSexp::Number(n),
+            Sexp::Boolean(b) => BorrowedSexp::Boolean(b),
+            Sexp::Pair(car, cdr) => {
+                BorrowedSexp::Pair(car.map(|car| Box::new(BorrowedSexp::from(*car))),
+                                   cdr.map(|cdr| Box::new(BorrowedSexp::from(*cdr))))
+            }
+            Sexp::List(elts) => BorrowedSexp::List(elts.into_iter().map(BorrowedSexp::from).collect()),
+            Sexp::Vector(elts) => BorrowedSexp::Vector(elts.into_iter().map(BorrowedSexp::from).collect()),
+        }
+    }
+}
@@ -9,7 +9,7 @@
 use std::fmt;
 use std::i64;
 use std::io;
-use std::slice;
+use std::iter;
 use std::str;
 use std::vec;
 
@@ -17,15 +17,153 @@ use serde;
 use serde::de::{
     Deserialize,
     DeserializeSeed,
+    EnumAccess,
+    IntoDeserializer,
+    VariantAccess,
     Visitor,
     SeqAccess,
     MapAccess,
+    Unexpected,
 };
 
+use config::SerializeConfig;
 use error::Error;
 use number::Number;
 use atom::Atom;
-use sexp::Sexp;
+use config::{symbol_to_bool, ParseConfig};
+use sexp::{ConsCell, Sexp, KEYWORD_TOKEN, PAIR_TOKEN, SEXP_TOKEN, SYMBOL_TOKEN, VECTOR_TOKEN};
+
+fn uncons(cell: ConsCell) -> Sexp {
+    cell.map_or(Sexp::Nil, |sexp| *sexp)
+}
+
+/// The elements of the dotted list `(car . cdr)` read as a sequence: the car
+/// of each pair along the cdr chain, followed by the final tail, so that
+/// `(1 2 . 3)` reads as `[1, 2, 3]`.
+fn dotted_elements(car: ConsCell, cdr: ConsCell) -> Vec<Sexp> {
+    let mut elts = vec![uncons(car)];
+    let mut tail = uncons(cdr);
+    while let Sexp::Pair(car, cdr) = tail {
+        elts.push(uncons(car));
+        tail = uncons(cdr);
+    }
+    elts.push(tail);
+    elts
+}
+
+fn cons(car: Sexp, cdr: Sexp) -> Sexp {
+    Sexp::Pair(Some(Box::new(car)), Some(Box::new(cdr)))
+}
+
+/// Whether `elts` are the entries of an alist, all dotted pairs, which
+/// `deserialize_any` presents as a map so that other formats see an object.
+///
+/// An alist whose first key is `PAIR_TOKEN` or `VECTOR_TOKEN` is left a list,
+/// since as a map it would read back as the pair or vector those keys tag.
+fn is_alist_of_pairs(elts: &[Sexp]) -> bool {
+    let tagged = match elts.first() {
+        Some(&Sexp::Pair(Some(ref car), _)) => match **car {
+            Sexp::Atom(ref a) => {
+                a.as_string() == Some(PAIR_TOKEN) || a.as_string() == Some(VECTOR_TOKEN)
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    !elts.is_empty() && !tagged && elts.iter().all(|elt| match *elt {
+        Sexp::Pair(_, _) => true,
+        _ => false,
+    })
+}
+
+/// Whether `sexp` can be read as a single key/value entry of a map: either a
+/// dotted pair `(key . value)` or a two element list `(key value)`.
+fn is_entry(sexp: &Sexp) -> bool {
+    match *sexp {
+        Sexp::Pair(_, _) => true,
+        Sexp::List(ref elts) => elts.len() == 2,
+        _ => false,
+    }
+}
+
+/// Splits the elements of a list into the entries of a map.
+///
+/// A list whose elements are all entries (see `is_entry`) is read as an alist,
+/// so `((a . 1) (b . 2))` and `((a 1) (b 2))` are the same map, as is any mix
+/// of the two. Any other list is read as a plist of alternating keys and
+/// values, `(a 1 b 2)`, and must have an even number of elements. Keyword
+/// keys, as in `(#:a 1 #:b 2)`, deserialize as their name without the `#:`.
+///
+/// The heuristic is ambiguous for a plist whose keys and values are all two
+/// element lists, such as `((k 0) (v 1))`; that is always read as an alist.
+fn map_entries(elts: Vec<Sexp>) -> Result<Vec<(Sexp, Sexp)>, Error> {
+    if elts.iter().all(is_entry) {
+        Ok(
+            elts.into_iter()
+                .map(|entry| match entry {
+                    Sexp::Pair(car, cdr) => (uncons(car), uncons(cdr)),
+                    Sexp::List(mut kv) => {
+                        let value = kv.pop().unwrap();
+                        (kv.pop().unwrap(), value)
+                    }
+                    _ => unreachable!(),
+                })
+                .collect(),
+        )
+    } else if elts.len() % 2 == 0 {
+        let mut entries = Vec::with_capacity(elts.len() / 2);
+        let mut iter = elts.into_iter();
+        while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+            entries.push((key, value));
+        }
+        Ok(entries)
+    } else {
+        Err(serde::de::Error::invalid_length(elts.len(), &"an even number of elements in a plist"))
+    }
+}
+
+/// Visits a symbol or keyword name as the map `{token: name}`, which is how
+/// `Sexp::deserialize` asks to see it. See `SEXP_TOKEN`.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub fn visit_tagged_name<'de, V, E>(token: &'static str, name: String, visitor: V) -> Result<V::Value, E>
+where
+    V: Visitor<'de>,
+    E: serde::de::Error,
+{
+    visitor.visit_map(serde::de::value::MapDeserializer::new(iter::once((token, name))))
+}
+
+/// Visits an atom for `Sexp::deserialize`, keeping symbols and keywords apart
+/// from strings.
+fn visit_exact_atom<'de, V>(atom: Atom, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    if atom.is_symbol() {
+        visit_tagged_name(SYMBOL_TOKEN, atom.into_inner(), visitor)
+    } else if atom.is_keyword() {
+        visit_tagged_name(KEYWORD_TOKEN, atom.into_inner(), visitor)
+    } else {
+        visitor.visit_string(atom.into_inner())
+    }
+}
+
+/// Returns the variant name if `elts` is the tagged list form of an enum
+/// variant, `((variant Name) args...)`. The name may be a symbol or a string.
+fn tagged_variant(elts: &[Sexp]) -> Option<&str> {
+    match elts.first() {
+        Some(&Sexp::List(ref tag)) if tag.len() == 2 => match (&tag[0], &tag[1]) {
+            (&Sexp::Atom(ref head), &Sexp::Atom(ref name))
+                if head.as_symbol() == Some("variant") && !name.is_keyword() =>
+            {
+                Some(name.as_str())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
 impl<'de> Deserialize<'de> for Sexp {
     #[inline]
@@ -98,9 +236,9 @@ impl<'de> Deserialize<'de> for Sexp {
                 where
                 D: serde::Deserializer<'de>,
             {
-                /// XXX something about this feels wrong
-                let result: String = try!(Deserialize::deserialize(deserializer));
-                Ok(Sexp::Atom(Atom::into_symbol(String::from(result))))
+                // Another format answers `SEXP_TOKEN` with the value it
+                // wraps.
+                deserializer.deserialize_any(self)
             }
 
 
@@ -118,15 +256,38 @@ impl<'de> Deserialize<'de> for Sexp {
                 Ok(Sexp::List(vec))
             }
 
-            fn visit_map<V>(self, _visitor: V) -> Result<Sexp, V::Error>
+            fn visit_map<V>(self, mut visitor: V) -> Result<Sexp, V::Error>
             where
                 V: MapAccess<'de>,
             {
-                unimplemented!()
+                let key: Option<Sexp> = try!(visitor.next_key());
+                match key {
+                    Some(Sexp::Atom(ref a)) if a.as_string() == Some(PAIR_TOKEN) => {
+                        let (car, cdr): (Sexp, Sexp) = try!(visitor.next_value());
+                        Ok(cons(car, cdr))
+                    }
+                    Some(Sexp::Atom(ref a)) if a.as_string() == Some(VECTOR_TOKEN) => {
+                        Ok(Sexp::Vector(try!(visitor.next_value())))
+                    }
+                    Some(Sexp::Atom(ref a)) if a.as_string() == Some(SYMBOL_TOKEN) => {
+                        Ok(Sexp::Atom(Atom::into_symbol(try!(visitor.next_value()))))
+                    }
+                    Some(Sexp::Atom(ref a)) if a.as_string() == Some(KEYWORD_TOKEN) => {
+                        Ok(Sexp::Atom(Atom::into_keyword(try!(visitor.next_value()))))
+                    }
+                    Some(key) => {
+                        let mut alist = vec![cons(key, try!(visitor.next_value()))];
+                        while let Some((key, value)) = try!(visitor.next_entry()) {
+                            alist.push(cons(key, value));
+                        }
+                        Ok(Sexp::List(alist))
+                    }
+                    None => Ok(Sexp::List(Vec::new())),
+                }
             }
         }
 
-        deserializer.deserialize_any(ValueVisitor)
+        deserializer.deserialize_newtype_struct(SEXP_TOKEN, ValueVisitor)
     }
 }
 
@@ -166,6 +327,42 @@ impl fmt::Display for Sexp {
     }
 }
 
+/// Displays a `Sexp` laid out according to a `SerializeConfig`. Returned by
+/// `Sexp::display_with`; `{:#}` pretty-prints it, as it does a `Sexp`.
+pub struct DisplayWith<'a> {
+    value: &'a Sexp,
+    config: SerializeConfig,
+}
+
+impl<'a> DisplayWith<'a> {
+    // Not public API. Should be pub(crate).
+    #[doc(hidden)]
+    pub fn new(value: &'a Sexp, config: SerializeConfig) -> Self {
+        DisplayWith {
+            value: value,
+            config: config,
+        }
+    }
+}
+
+impl<'a> fmt::Display for DisplayWith<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use super::super::ser::{CompactFormatter, PrettyFormatter, Serializer};
+        use serde::Serialize;
+
+        let alternate = f.alternate();
+        let mut wr = WriterFormatter { inner: f };
+        let config = self.config.clone();
+        if alternate {
+            let mut ser = Serializer::with_config(&mut wr, PrettyFormatter::new(), config);
+            self.value.serialize(&mut ser).map_err(|_| fmt::Error)
+        } else {
+            let mut ser = Serializer::with_config(&mut wr, CompactFormatter, config);
+            self.value.serialize(&mut ser).map_err(|_| fmt::Error)
+        }
+    }
+}
+
 
 impl str::FromStr for Sexp {
     type Err = Error;
@@ -174,90 +371,500 @@ impl str::FromStr for Sexp {
     }
 }
 
+/// Deserializes an owned `Sexp`. The `ParseConfig` the value was read with,
+/// if any, decides the type-directed readings such as symbols standing in
+/// for booleans.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub struct SexpDeserializer<'c> {
+    value: Sexp,
+    config: Option<&'c ParseConfig>,
+}
+
+impl<'c> SexpDeserializer<'c> {
+    pub fn new(value: Sexp, config: Option<&'c ParseConfig>) -> Self {
+        SexpDeserializer {
+            value: value,
+            config: config,
+        }
+    }
+}
+
+macro_rules! forward_to_sexp_deserializer {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            #[inline]
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                serde::Deserializer::$method(SexpDeserializer::new(self, None), $($arg,)* visitor)
+            }
+        )*
+    };
+}
+
 impl<'de> serde::Deserializer<'de> for Sexp {
     type Error = Error;
 
+    forward_to_sexp_deserializer! {
+        deserialize_any() deserialize_bool() deserialize_i8() deserialize_i16()
+        deserialize_i32() deserialize_i64() deserialize_u8() deserialize_u16()
+        deserialize_u32() deserialize_u64() deserialize_f32() deserialize_f64()
+        deserialize_char() deserialize_str() deserialize_string()
+        deserialize_bytes() deserialize_byte_buf() deserialize_option()
+        deserialize_unit() deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str) deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier() deserialize_ignored_any()
+    }
+}
+
+impl<'de, 'c> serde::Deserializer<'de> for SexpDeserializer<'c> {
+    type Error = Error;
+
     #[inline]
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.value {
             Sexp::Nil => visitor.visit_unit(),
             Sexp::Boolean(v) => visitor.visit_bool(v),
             Sexp::Number(n) => n.deserialize_any(visitor),
-            Sexp::Atom(a) => visitor.visit_string(a.as_string()),
-            Sexp::Pair(_, _) => {
-                unimplemented!()
-            },
+            Sexp::Atom(a) => visitor.visit_string(a.into_inner()),
+            Sexp::Pair(car, cdr) => {
+                let pair = vec![uncons(car), uncons(cdr)];
+                visitor.visit_map(TaggedDeserializer::new(PAIR_TOKEN, pair, self.config))
+            }
+            Sexp::Vector(v) => {
+                visitor.visit_map(TaggedDeserializer::new(VECTOR_TOKEN, v, self.config))
+            }
             Sexp::List(v) => {
-                let len = v.len();
-                let mut deserializer = SeqDeserializer::new(v);
-                let seq = try!(visitor.visit_seq(&mut deserializer));
-                let remaining = deserializer.iter.len();
-                if remaining == 0 {
-                    Ok(seq)
-                } else {
-                    Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
+                if !is_alist_of_pairs(&v) {
+                    return visit_array(v, self.config, visitor);
                 }
+                let entries = v.into_iter().map(|entry| match entry {
+                    Sexp::Pair(car, cdr) => (uncons(car), uncons(cdr)),
+                    _ => unreachable!(),
+                });
+                visitor.visit_map(MapDeserializer::new(entries.collect(), self.config))
             }
         }
     }
 
+    /// Symbols configured as `ParseConfig::true_symbols` or `false_symbols`
+    /// read as the corresponding boolean.
+    #[inline]
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let config = self.config;
+        match self.value {
+            Sexp::Atom(ref a) => match (config, a.as_symbol()) {
+                (Some(config), Some(name)) => match symbol_to_bool(config, name) {
+                    Some(b) => return visitor.visit_bool(b),
+                    None => {}
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+        self.deserialize_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Sexp::Atom(a) => visitor.visit_string(a.into_inner()),
+            Sexp::Number(n) => visitor.visit_string(n.to_string()),
+            other => SexpDeserializer::new(other, self.config).deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    /// A dotted pair deserializes as a two element sequence.
+    #[inline]
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Sexp::Pair(car, cdr) => visit_array(dotted_elements(car, cdr), self.config, visitor),
+            Sexp::Vector(v) => visit_array(v, self.config, visitor),
+            other => SexpDeserializer::new(other, self.config).deserialize_any(visitor),
+        }
+    }
+
+    /// A list or vector must have exactly `len` elements.
+    #[inline]
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Sexp::List(ref v) | Sexp::Vector(ref v) if v.len() != len => {
+                Err(Error::tuple_length(len, v.len()))
+            }
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
     #[inline]
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.value {
             Sexp::Nil => visitor.visit_none(),
             _ => visitor.visit_some(self),
         }
     }
 
+    /// An enum is read from a bare symbol or string naming a unit variant, or
+    /// from the tagged list form `((variant Name) args...)`.
     #[inline]
     fn deserialize_enum<V>(
         self,
         _name: &str,
         _variants: &'static [&'static str],
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let (variant, args) = match self.value {
+            Sexp::Atom(ref a) => (a.as_str().to_owned(), Vec::new()),
+            Sexp::List(mut elts) => {
+                let variant = match tagged_variant(&elts) {
+                    Some(variant) => variant.to_owned(),
+                    None => {
+                        return Err(serde::de::Error::invalid_value(
+                            Unexpected::Seq,
+                            &"a list tagged with (variant Name)",
+                        ))
+                    }
+                };
+                elts.remove(0);
+                (variant, elts)
+            }
+            other => {
+                return Err(serde::de::Error::invalid_type(other.unexpected(), &"enum variant"))
+            }
+        };
+        visitor.visit_enum(EnumDeserializer {
+            variant: variant,
+            args: args,
+            config: self.config,
+        })
+    }
+
+    /// A unit struct is read from nil, the empty list or its own name.
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Sexp::Nil => visitor.visit_unit(),
+            Sexp::List(ref elts) if elts.is_empty() => visitor.visit_unit(),
+            Sexp::Atom(ref a) if a.as_symbol() == Some(name) => visitor.visit_unit(),
+            other => SexpDeserializer::new(other, self.config).deserialize_any(visitor),
+        }
     }
 
+    /// `Sexp::deserialize` asks for the value as it is through `SEXP_TOKEN`:
+    /// a symbol or keyword stays apart from a string, and an alist stays a
+    /// list.
     #[inline]
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name != SEXP_TOKEN {
+            return visitor.visit_newtype_struct(self);
+        }
+        match self.value {
+            Sexp::Atom(a) => visit_exact_atom(a, visitor),
+            Sexp::List(v) => visit_array(v, self.config, visitor),
+            other => SexpDeserializer::new(other, self.config).deserialize_any(visitor),
+        }
+    }
+
+    /// A list deserializes as a map from either an alist or a plist. See
+    /// `map_entries` for how the two are told apart.
+    #[inline]
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Sexp::List(elts) => {
+                let entries = try!(map_entries(elts));
+                visitor.visit_map(MapDeserializer::new(entries, self.config))
+            }
+            other => SexpDeserializer::new(other, self.config).deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char bytes byte_buf unit
         ignored_any
     }
 }
 
-struct SeqDeserializer {
+fn visit_array<'de, V>(
+    array: Vec<Sexp>,
+    config: Option<&ParseConfig>,
+    visitor: V,
+) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let len = array.len();
+    let mut deserializer = SeqDeserializer::new(array, config);
+    let seq = try!(visitor.visit_seq(&mut deserializer));
+    let remaining = deserializer.iter.len();
+    if remaining == 0 {
+        Ok(seq)
+    } else {
+        Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
+    }
+}
+
+struct EnumDeserializer<'c> {
+    variant: String,
+    args: Vec<Sexp>,
+    config: Option<&'c ParseConfig>,
+}
+
+impl<'de, 'c> EnumAccess<'de> for EnumDeserializer<'c> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'c>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer<'c>), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = self.variant.into_deserializer();
+        let visitor = VariantDeserializer {
+            args: self.args,
+            config: self.config,
+        };
+        seed.deserialize(variant).map(|v| (v, visitor))
+    }
+}
+
+struct VariantDeserializer<'c> {
+    args: Vec<Sexp>,
+    config: Option<&'c ParseConfig>,
+}
+
+impl<'de, 'c> VariantAccess<'de> for VariantDeserializer<'c> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.args.len() {
+            0 => Ok(()),
+            len => Err(serde::de::Error::invalid_length(len, &"no arguments")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.args.len() {
+            1 => seed.deserialize(SexpDeserializer::new(self.args.pop().unwrap(), self.config)),
+            len => Err(serde::de::Error::invalid_length(len, &"one argument")),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visit_array(self.args, self.config, visitor)
+    }
+
+    /// The fields of a struct variant follow the tag as alist entries or as
+    /// a plist, like the elements of a map.
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let entries = try!(map_entries(self.args));
+        visitor.visit_map(MapDeserializer::new(entries, self.config))
+    }
+}
+
+struct MapDeserializer<'c> {
+    iter: vec::IntoIter<(Sexp, Sexp)>,
+    value: Option<Sexp>,
+    config: Option<&'c ParseConfig>,
+}
+
+impl<'c> MapDeserializer<'c> {
+    fn new(entries: Vec<(Sexp, Sexp)>, config: Option<&'c ParseConfig>) -> Self {
+        MapDeserializer {
+            iter: entries.into_iter(),
+            value: None,
+            config: config,
+        }
+    }
+}
+
+impl<'de, 'c> MapAccess<'de> for MapDeserializer<'c> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(SexpDeserializer::new(key, self.config)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(SexpDeserializer::new(value, self.config)),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// Presents a pair or a vector as a single-entry map keyed by `token`, for
+/// the `Sexp` visitor to recognize.
+struct TaggedDeserializer<'c> {
+    token: &'static str,
+    elts: Option<Vec<Sexp>>,
+    config: Option<&'c ParseConfig>,
+}
+
+impl<'c> TaggedDeserializer<'c> {
+    fn new(token: &'static str, elts: Vec<Sexp>, config: Option<&'c ParseConfig>) -> Self {
+        TaggedDeserializer {
+            token: token,
+            elts: Some(elts),
+            config: config,
+        }
+    }
+}
+
+impl<'de, 'c> MapAccess<'de> for TaggedDeserializer<'c> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.elts {
+            Some(_) => seed.deserialize(self.token.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.elts.take() {
+            Some(elts) => seed.deserialize(SeqDeserializer::new(elts, self.config)),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.elts.is_some() { 1 } else { 0 })
+    }
+}
+
+struct SeqDeserializer<'c> {
     iter: vec::IntoIter<Sexp>,
+    config: Option<&'c ParseConfig>,
 }
 
-impl SeqDeserializer {
-    fn new(vec: Vec<Sexp>) -> Self {
-        SeqDeserializer { iter: vec.into_iter() }
+impl<'c> SeqDeserializer<'c> {
+    fn new(vec: Vec<Sexp>, config: Option<&'c ParseConfig>) -> Self {
+        SeqDeserializer {
+            iter: vec.into_iter(),
+            config: config,
+        }
     }
 }
 
-impl<'de> serde::Deserializer<'de> for SeqDeserializer {
+impl<'de, 'c> serde::Deserializer<'de> for SeqDeserializer<'c> {
     type Error = Error;
 
     #[inline]
@@ -266,16 +873,12 @@ impl<'de> serde::Deserializer<'de> for SeqDeserializer {
         V: Visitor<'de>,
     {
         let len = self.iter.len();
-        if len == 0 {
-            visitor.visit_unit()
+        let ret = try!(visitor.visit_seq(&mut self));
+        let remaining = self.iter.len();
+        if remaining == 0 {
+            Ok(ret)
         } else {
-            let ret = try!(visitor.visit_seq(&mut self));
-            let remaining = self.iter.len();
-            if remaining == 0 {
-                Ok(ret)
-            } else {
-                Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
-            }
+            Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
         }
     }
 
@@ -286,7 +889,7 @@ impl<'de> serde::Deserializer<'de> for SeqDeserializer {
     }
 }
 
-impl<'de> SeqAccess<'de> for SeqDeserializer {
+impl<'de, 'c> SeqAccess<'de> for SeqDeserializer<'c> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
@@ -294,7 +897,7 @@ impl<'de> SeqAccess<'de> for SeqDeserializer {
         T: DeserializeSeed<'de>,
     {
         match self.iter.next() {
-            Some(value) => seed.deserialize(value).map(Some),
+            Some(value) => seed.deserialize(SexpDeserializer::new(value, self.config)).map(Some),
             None => Ok(None),
         }
     }
@@ -320,23 +923,85 @@ impl<'de> serde::Deserializer<'de> for &'de Sexp {
             Sexp::Boolean(v) => visitor.visit_bool(v),
             Sexp::Number(ref n) => n.deserialize_any(visitor),
             Sexp::Atom(ref a) => visitor.visit_borrowed_str(a.as_str()),
-            Sexp::Pair(_, _) => {
-                unimplemented!()
-            },
-            Sexp::List(ref v) => {
-                let len = v.len();
-                let mut deserializer = SeqRefDeserializer::new(v);
-                let seq = try!(visitor.visit_seq(&mut deserializer));
-                let remaining = deserializer.iter.len();
-                if remaining == 0 {
-                    Ok(seq)
-                } else {
-                    Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
-                }
+            Sexp::Pair(ref car, ref cdr) => {
+                let pair = vec![uncons_ref(car), uncons_ref(cdr)];
+                visitor.visit_map(TaggedRefDeserializer::new(PAIR_TOKEN, pair))
+            }
+            Sexp::Vector(ref v) => {
+                visitor.visit_map(TaggedRefDeserializer::new(VECTOR_TOKEN, v.iter().collect()))
+            }
+            Sexp::List(ref v) if is_alist_of_pairs(v) => {
+                let entries = v.iter().map(|entry| match *entry {
+                    Sexp::Pair(ref car, ref cdr) => (uncons_ref(car), uncons_ref(cdr)),
+                    _ => unreachable!(),
+                });
+                visitor.visit_map(MapRefDeserializer::new(entries.collect()))
             }
+            Sexp::List(ref v) => visit_array_ref(v.iter().collect(), visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self {
+            Sexp::Atom(ref a) => visitor.visit_borrowed_str(a.as_str()),
+            Sexp::Number(ref n) => visitor.visit_string(n.to_string()),
+            _ => self.deserialize_any(visitor),
         }
     }
 
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    /// A dotted pair deserializes as a two element sequence.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self {
+            Sexp::Pair(ref car, ref cdr) => visit_array_ref(dotted_elements_ref(car, cdr), visitor),
+            Sexp::Vector(ref v) => visit_array_ref(v.iter().collect(), visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self {
+            Sexp::List(ref v) | Sexp::Vector(ref v) if v.len() != len => {
+                Err(Error::tuple_length(len, v.len()))
+            }
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
@@ -347,45 +1012,318 @@ impl<'de> serde::Deserializer<'de> for &'de Sexp {
         }
     }
 
+    /// An enum is read from a bare symbol or string naming a unit variant, or
+    /// from the tagged list form `((variant Name) args...)`.
     fn deserialize_enum<V>(
         self,
         _name: &str,
         _variants: &'static [&'static str],
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let (variant, args) = match *self {
+            Sexp::Atom(ref a) => (a.as_str(), &[][..]),
+            Sexp::List(ref elts) => match tagged_variant(elts) {
+                Some(variant) => (variant, &elts[1..]),
+                None => {
+                    return Err(serde::de::Error::invalid_value(
+                        Unexpected::Seq,
+                        &"a list tagged with (variant Name)",
+                    ))
+                }
+            },
+            _ => return Err(serde::de::Error::invalid_type(self.unexpected(), &"enum variant")),
+        };
+        visitor.visit_enum(EnumRefDeserializer {
+            variant: variant,
+            args: args,
+        })
+    }
+
+    /// A unit struct is read from nil, the empty list or its own name.
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self {
+            Sexp::Nil => visitor.visit_unit(),
+            Sexp::List(ref elts) if elts.is_empty() => visitor.visit_unit(),
+            Sexp::Atom(ref a) if a.as_symbol() == Some(name) => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
     }
 
+    /// See `SexpDeserializer::deserialize_newtype_struct`.
     #[inline]
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name != SEXP_TOKEN {
+            return visitor.visit_newtype_struct(self);
+        }
+        match *self {
+            Sexp::Atom(ref a) if a.is_string() => visitor.visit_borrowed_str(a.as_str()),
+            Sexp::Atom(ref a) => visit_exact_atom(a.clone(), visitor),
+            Sexp::List(ref v) => visit_array_ref(v.iter().collect(), visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// A list deserializes as a map from either an alist or a plist. See
+    /// `map_entries` for how the two are told apart.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self {
+            Sexp::List(ref elts) => {
+                let entries = try!(map_entries_ref(elts));
+                visitor.visit_map(MapRefDeserializer::new(entries))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
-        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char bytes byte_buf unit
         ignored_any
     }
 }
 
+static NIL: Sexp = Sexp::Nil;
+
+fn uncons_ref(cell: &ConsCell) -> &Sexp {
+    match *cell {
+        Some(ref sexp) => sexp,
+        None => &NIL,
+    }
+}
+
+/// Borrowing counterpart of `dotted_elements`.
+fn dotted_elements_ref<'a>(car: &'a ConsCell, cdr: &'a ConsCell) -> Vec<&'a Sexp> {
+    let mut elts = vec![uncons_ref(car)];
+    let mut tail = uncons_ref(cdr);
+    while let Sexp::Pair(ref car, ref cdr) = *tail {
+        elts.push(uncons_ref(car));
+        tail = uncons_ref(cdr);
+    }
+    elts.push(tail);
+    elts
+}
+
+/// Borrowing counterpart of `map_entries`.
+fn map_entries_ref(elts: &[Sexp]) -> Result<Vec<(&Sexp, &Sexp)>, Error> {
+    if elts.iter().all(is_entry) {
+        Ok(
+            elts.iter()
+                .map(|entry| match *entry {
+                    Sexp::Pair(ref car, ref cdr) => (uncons_ref(car), uncons_ref(cdr)),
+                    Sexp::List(ref kv) => (&kv[0], &kv[1]),
+                    _ => unreachable!(),
+                })
+                .collect(),
+        )
+    } else if elts.len() % 2 == 0 {
+        Ok(elts.chunks(2).map(|kv| (&kv[0], &kv[1])).collect())
+    } else {
+        Err(serde::de::Error::invalid_length(elts.len(), &"an even number of elements in a plist"))
+    }
+}
+
+fn visit_array_ref<'de, V>(array: Vec<&'de Sexp>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let len = array.len();
+    let mut deserializer = SeqRefDeserializer::new(array);
+    let seq = try!(visitor.visit_seq(&mut deserializer));
+    let remaining = deserializer.iter.len();
+    if remaining == 0 {
+        Ok(seq)
+    } else {
+        Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
+    }
+}
+
+struct EnumRefDeserializer<'de> {
+    variant: &'de str,
+    args: &'de [Sexp],
+}
+
+impl<'de> EnumAccess<'de> for EnumRefDeserializer<'de> {
+    type Error = Error;
+    type Variant = VariantRefDeserializer<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantRefDeserializer<'de>), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = self.variant.into_deserializer();
+        let visitor = VariantRefDeserializer { args: self.args };
+        seed.deserialize(variant).map(|v| (v, visitor))
+    }
+}
+
+struct VariantRefDeserializer<'de> {
+    args: &'de [Sexp],
+}
+
+impl<'de> VariantAccess<'de> for VariantRefDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.args.len() {
+            0 => Ok(()),
+            len => Err(serde::de::Error::invalid_length(len, &"no arguments")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.args.len() {
+            1 => seed.deserialize(&self.args[0]),
+            len => Err(serde::de::Error::invalid_length(len, &"one argument")),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visit_array_ref(self.args.iter().collect(), visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let entries = try!(map_entries_ref(self.args));
+        visitor.visit_map(MapRefDeserializer::new(entries))
+    }
+}
+
+struct MapRefDeserializer<'de> {
+    iter: vec::IntoIter<(&'de Sexp, &'de Sexp)>,
+    value: Option<&'de Sexp>,
+}
+
+impl<'de> MapRefDeserializer<'de> {
+    fn new(entries: Vec<(&'de Sexp, &'de Sexp)>) -> Self {
+        MapRefDeserializer {
+            iter: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for MapRefDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+struct TaggedRefDeserializer<'de> {
+    token: &'static str,
+    elts: Option<Vec<&'de Sexp>>,
+}
+
+impl<'de> TaggedRefDeserializer<'de> {
+    fn new(token: &'static str, elts: Vec<&'de Sexp>) -> Self {
+        TaggedRefDeserializer {
+            token: token,
+            elts: Some(elts),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for TaggedRefDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.elts {
+            Some(_) => seed.deserialize(self.token.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.elts.take() {
+            Some(elts) => seed.deserialize(SeqRefDeserializer::new(elts)),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.elts.is_some() { 1 } else { 0 })
+    }
+}
 
 struct SeqRefDeserializer<'de> {
-    iter: slice::Iter<'de, Sexp>,
+    iter: vec::IntoIter<&'de Sexp>,
 }
 
 impl<'de> SeqRefDeserializer<'de> {
-    fn new(slice: &'de [Sexp]) -> Self {
-        SeqRefDeserializer { iter: slice.iter() }
+    fn new(vec: Vec<&'de Sexp>) -> Self {
+        SeqRefDeserializer { iter: vec.into_iter() }
     }
 }
 
@@ -398,16 +1336,12 @@ impl<'de> serde::Deserializer<'de> for SeqRefDeserializer<'de> {
         V: Visitor<'de>,
     {
         let len = self.iter.len();
-        if len == 0 {
-            visitor.visit_unit()
+        let ret = try!(visitor.visit_seq(&mut self));
+        let remaining = self.iter.len();
+        if remaining == 0 {
+            Ok(ret)
         } else {
-            let ret = try!(visitor.visit_seq(&mut self));
-            let remaining = self.iter.len();
-            if remaining == 0 {
-                Ok(ret)
-            } else {
-                Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
-            }
+            Err(serde::de::Error::invalid_length(len, &"fewer elements in array"))
         }
     }
 
@@ -439,3 +1373,22 @@ impl<'de> SeqAccess<'de> for SeqRefDeserializer<'de> {
     }
 }
 
+
+impl Sexp {
+    // Not public API. Should be pub(crate).
+    #[doc(hidden)]
+    pub fn unexpected(&self) -> Unexpected {
+        match *self {
+            Sexp::Nil => Unexpected::Unit,
+            Sexp::Boolean(b) => Unexpected::Bool(b),
+            Sexp::Number(ref n) => n.unexpected(),
+            Sexp::Atom(ref a) => match a.as_string() {
+                Some(s) => Unexpected::Str(s),
+                None if a.is_keyword() => Unexpected::Other("keyword"),
+                None => Unexpected::Other("symbol"),
+            },
+            Sexp::Pair(_, _) => Unexpected::Other("pair"),
+            Sexp::List(_) | Sexp::Vector(_) => Unexpected::Seq,
+        }
+    }
+}
@@ -7,9 +7,14 @@
 // except according to those terms.
 
 use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+
+use serde::de::Error as DeError;
 
 use super::Sexp;
-use map::Map;
+use atom::Atom;
+use error::Error;
 use number::Number;
 
 macro_rules! from_integer {
@@ -65,7 +70,7 @@ impl From<f64> for Sexp {
     /// # }
     /// ```
     fn from(f: f64) -> Self {
-        Number::from_f64(f).map_or(Sexp::Null, Sexp::Number)
+        Number::from_f64(f).map_or(Sexp::Nil, Sexp::Number)
     }
 }
 
@@ -158,12 +163,13 @@ impl<'a> From<Cow<'a, str>> for Sexp {
     /// # }
     /// ```
     fn from(f: Cow<'a, str>) -> Self {
-        Sexp::Atom(Atom::from_string(f))
+        Sexp::Atom(Atom::from_string(f.into_owned()))
     }
 }
 
-impl From<Map<String, Sexp>> for Sexp {
-    /// Convert map (with string keys) to `Sexp`
+impl From<char> for Sexp {
+    /// Convert `char` to a single character string `Sexp`, as `serialize_char`
+    /// does.
     ///
     /// # Examples
     ///
@@ -171,15 +177,55 @@ impl From<Map<String, Sexp>> for Sexp {
     /// # extern crate sexpr;
     /// #
     /// # fn main() {
-    /// use sexpr::{Map, Sexp};
+    /// use sexpr::Sexp;
+    ///
+    /// let c = 'a';
+    /// let x: Sexp = c.into();
+    /// # }
+    /// ```
+    fn from(c: char) -> Self {
+        Sexp::Atom(Atom::into_string(c.to_string()))
+    }
+}
+
+impl<'a> From<&'a OsStr> for Sexp {
+    /// Convert `OsStr` to a string `Sexp`, replacing any invalid unicode
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// use sexpr::Sexp;
+    /// use std::path::Path;
     ///
-    /// let mut m = Map::new();
-    /// m.insert("Lorem".to_string(), "ipsum".into());
-    /// let x: Sexp = m.into();
+    /// let x: Sexp = Path::new("/etc/hosts").as_os_str().into();
     /// # }
     /// ```
-    fn from(f: Map<String, Sexp>) -> Self {
-        unimplemented!()
+    fn from(s: &'a OsStr) -> Self {
+        Sexp::Atom(Atom::into_string(s.to_string_lossy().into_owned()))
+    }
+}
+
+impl<T: Into<Sexp>> From<Option<T>> for Sexp {
+    /// Convert `Option` to `Sexp`, with `None` becoming `Sexp::Nil`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// use sexpr::Sexp;
+    ///
+    /// let o: Option<char> = None;
+    /// let x: Sexp = o.into();
+    /// # }
+    /// ```
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Sexp::Nil, Into::into)
     }
 }
 
@@ -266,3 +312,96 @@ impl<T: Into<Sexp>> ::std::iter::FromIterator<T> for Sexp {
         Sexp::List(vec)
     }
 }
+
+macro_rules! try_from_number {
+    ($($ty:ident $as_ty:ident $expected:expr,)*) => {
+        $(
+            impl TryFrom<Sexp> for $ty {
+                type Error = Error;
+
+                fn try_from(sexp: Sexp) -> Result<Self, Error> {
+                    match sexp {
+                        Sexp::Number(n) => n.$as_ty().ok_or_else(|| {
+                            Error::invalid_value(n.unexpected(), &$expected)
+                        }),
+                        other => Err(Error::invalid_type(other.unexpected(), &$expected)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_number! {
+    i64 as_i64 "an i64",
+    u64 as_u64 "a u64",
+    f64 as_f64 "a number",
+}
+
+impl TryFrom<Sexp> for bool {
+    type Error = Error;
+
+    /// Convert `#t` or `#f` to a `bool`.
+    ///
+    /// ```rust,ignore
+    /// use std::convert::TryFrom;
+    ///
+    /// assert_eq!(bool::try_from(Sexp::Boolean(true)).unwrap(), true);
+    /// assert!(bool::try_from(Sexp::from(1)).is_err());
+    /// ```
+    fn try_from(sexp: Sexp) -> Result<Self, Error> {
+        match sexp {
+            Sexp::Boolean(b) => Ok(b),
+            other => Err(Error::invalid_type(other.unexpected(), &"a boolean")),
+        }
+    }
+}
+
+impl TryFrom<Sexp> for String {
+    type Error = Error;
+
+    /// Convert an atom to its text: the contents of a string, or the name of
+    /// a symbol or keyword.
+    fn try_from(sexp: Sexp) -> Result<Self, Error> {
+        match sexp {
+            Sexp::Atom(atom) => Ok(atom.into_inner()),
+            other => Err(Error::invalid_type(other.unexpected(), &"an atom")),
+        }
+    }
+}
+
+impl TryFrom<Sexp> for Atom {
+    type Error = Error;
+
+    fn try_from(sexp: Sexp) -> Result<Self, Error> {
+        match sexp {
+            Sexp::Atom(atom) => Ok(atom),
+            other => Err(Error::invalid_type(other.unexpected(), &"an atom")),
+        }
+    }
+}
+
+impl TryFrom<Sexp> for Number {
+    type Error = Error;
+
+    fn try_from(sexp: Sexp) -> Result<Self, Error> {
+        match sexp {
+            Sexp::Number(n) => Ok(n),
+            other => Err(Error::invalid_type(other.unexpected(), &"a number")),
+        }
+    }
+}
+
+impl TryFrom<Sexp> for Vec<Sexp> {
+    type Error = Error;
+
+    /// Convert a list or vector to its elements. `Sexp::Nil` converts to no
+    /// elements, as the empty list.
+    fn try_from(sexp: Sexp) -> Result<Self, Error> {
+        match sexp {
+            Sexp::List(elts) | Sexp::Vector(elts) => Ok(elts),
+            Sexp::Nil => Ok(Vec::new()),
+            other => Err(Error::invalid_type(other.unexpected(), &"a list")),
+        }
+    }
+}
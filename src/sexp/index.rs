@@ -26,8 +26,8 @@ pub trait Index: private::Sealed {
     fn index_into_mut<'v>(&self, v: &'v mut Sexp) -> Option<&'v mut Sexp>;
 
     /// Panic if array index out of bounds. If key is not already in the object,
-    /// insert it with a value of null. Panic if Sexp is a type that cannot be
-    /// indexed into, except if Sexp is null then it can be treated as an empty
+    /// insert it with a value of nil. Panic if Sexp is a type that cannot be
+    /// indexed into, except if Sexp is nil then it can be treated as an empty
     /// object.
     #[doc(hidden)]
     fn index_or_insert<'v>(&self, v: &'v mut Sexp) -> &'v mut Sexp;
@@ -132,6 +132,7 @@ impl<'a> fmt::Display for Type<'a> {
             Sexp::Number(_) => formatter.write_str("number"),
             Sexp::Atom(_) => formatter.write_str("atom"),
             Sexp::List(_) => formatter.write_str("list"),
+            Sexp::Vector(_) => formatter.write_str("vector"),
             Sexp::Pair(_, _) => formatter.write_str("pair"),
         }
     }
@@ -185,7 +186,7 @@ where
     /// assert_eq!(data["x"]["y"], sexpr::from_str("(z zz)"));
     /// assert_eq!(data["x"]["y"][0], sexpr::from_str("z"));
     ///
-    /// assert_eq!(data["a"], Sexp::Nil); // returns null for undefined values
+    /// assert_eq!(data["a"], Sexp::Nil); // returns nil for undefined values
     /// assert_eq!(data["a"]["b"], Sexp::Nil); // does not panic
     /// # }
     /// ```
@@ -206,10 +207,10 @@ where
     /// than the index. Indexing into a value that is not an array or an array
     /// that is too small will panic.
     ///
-    /// If the index is a string, the value must be an object or null which is
+    /// If the index is a string, the value must be an object or nil which is
     /// treated like an empty object. If the key is not already present in the
-    /// object, it will be inserted with a value of null. Indexing into a value
-    /// that is neither an object nor null will panic.
+    /// object, it will be inserted with a value of nil. Indexing into a value
+    /// that is neither an object nor nil will panic.
     ///
     /// # Examples
     ///
@@ -72,19 +72,36 @@
 //! # }
 //! ```
 //!
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+use std::io;
+use std::iter;
+use std::mem;
+use std::ops::Range;
+use std::option;
+use std::slice;
 use std::str;
 use std::string::String;
 
 use serde::ser::Serialize;
+use serde;
 use serde::de::DeserializeOwned;
 
-use error::Error;
+use canonical;
+use config::SerializeConfig;
+use error::{Error, ErrorCode};
+#[cfg(feature = "sha2")]
+use sha2::digest::{Digest, Output};
 pub use number::Number;
 pub use atom::Atom;
 
 mod index;
 pub use self::index::Index;
 
+mod borrowed;
+pub use self::borrowed::BorrowedSexp;
+
 use self::ser::Serializer;
 
 // Rather than having a specialized 'nil' atom, we save space by letting `None`
@@ -173,31 +190,70 @@ pub enum Sexp {
     /// # }
     /// ```
     List(Vec<Sexp>),
+
+    /// Represents a vector, `[a b c]` or `#(a b c)`, for dialects where it
+    /// means something other than a list. Only produced by the parser when
+    /// `ParseConfig::square_brackets` or `ParseConfig::hash_vectors` is set.
+    ///
+    /// Either way it is written back as `[a b c]`, unless
+    /// `SerializeConfig::hash_vectors` asks for `#(a b c)`.
+    Vector(Vec<Sexp>),
+}
+
+/// A `Sexp::Vector` is serialized as a newtype struct with this name, and
+/// presented to `deserialize_any` visitors as a map with this single key, so
+/// that it can be told apart from a list.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub const VECTOR_TOKEN: &'static str = "$sexpr::private::Vector";
+
+/// An improper list such as `(a b . c)` is serialized as a newtype struct with
+/// this name around a tuple of its elements, the last of which is the cdr. A
+/// `Sexp::Pair` is presented to `deserialize_any` visitors as a map with this
+/// single key, whose value is the sequence `(car cdr)`, so that the `Sexp`
+/// visitor can tell a dotted pair apart from a list.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub const PAIR_TOKEN: &'static str = "$sexpr::private::Pair";
+
+/// `Sexp` asks a deserializer for exactly the value it holds by calling
+/// `deserialize_newtype_struct` with this name. The deserializers in this
+/// crate then present a symbol or keyword as a map with the single key
+/// `SYMBOL_TOKEN` or `KEYWORD_TOKEN` whose value is its name. To any other
+/// visitor a symbol or keyword is a plain string.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub const SEXP_TOKEN: &'static str = "$sexpr::private::Sexp";
+
+/// See `SEXP_TOKEN`.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub const SYMBOL_TOKEN: &'static str = "$sexpr::private::Symbol";
+
+/// See `SEXP_TOKEN`.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub const KEYWORD_TOKEN: &'static str = "$sexpr::private::Keyword";
+
+/// The `(variant Name)` tag that heads the list form of an enum variant with
+/// arguments, `((variant Name) args...)`.
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub fn variant_tag(variant: &str) -> Sexp {
+    Sexp::List(vec![Sexp::Atom(Atom::into_symbol(String::from("variant"))),
+                    Sexp::Atom(Atom::into_symbol(String::from(variant)))])
 }
 
 mod ser;
 mod de;
 
+// Not public API. Should be pub(crate).
+#[doc(hidden)]
+pub use self::de::{visit_tagged_name, DisplayWith, SexpDeserializer};
+mod from;
 
-impl From<String> for Sexp {
-    /// Convert `String` to `Sexp`
-    ///
-    /// # Examples
-    ///
-    /// ```rust,ignore
-    /// # extern crate sexpr;
-    /// #
-    /// # fn main() {
-    /// use sexpr::Sexp;
-    ///
-    /// let s: String = "lorem".to_string();
-    /// let x: Sexp = s.into();
-    /// # }
-    /// ```
-    fn from(f: String) -> Self {
-        Sexp::Atom(Atom::from_string(f))
-    }
-}
+
+static NIL: Sexp = Sexp::Nil;
 
 impl Sexp {
     /// Return a new Sexp::Pair with a symbol key
@@ -240,7 +296,7 @@ impl Sexp {
     /// ```
     ///
     /// Square brackets can also be used to index into a value in a more concise
-    /// way. This returns `Value::Null` in cases where `get` would have returned
+    /// way. This returns `Sexp::Nil` in cases where `get` would have returned
     /// `None`.
     ///
     /// ```rust,ignore
@@ -255,14 +311,1067 @@ impl Sexp {
     /// ));
     /// assert_eq!(object["B"][0], sexp!("b"));
     ///
-    /// assert_eq!(object["D"], sexp!(null));
-    /// assert_eq!(object[0]["x"]["y"]["z"], sexp!(null));
+    /// assert_eq!(object["D"], sexp!(#nil));
+    /// assert_eq!(object[0]["x"]["y"]["z"], sexp!(#nil));
     /// # }
     /// ```
     pub fn get<I: Index>(&self, _index: I) -> Option<&Sexp> {
         unimplemented!()
     }
 
+    /// Looks up a deeply nested value by following `segments` in turn. A
+    /// `Key` selects the value of the first alist entry with that key, and an
+    /// `Index` selects an element of a list or vector.
+    ///
+    /// Returns `None` as soon as a segment does not match.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # use sexpr::sexp::PathSegment::{Index, Key};
+    /// #
+    /// # fn main() {
+    /// let v = sexp!(((name . "John Doe") (phones . ("+44 1234567" "+44 2345678"))));
+    /// assert_eq!(v.get_path(&[Key("phones"), Index(0)]), Some(&sexp!("+44 1234567")));
+    /// # }
+    /// ```
+    pub fn get_path(&self, segments: &[PathSegment]) -> Option<&Sexp> {
+        let mut value = self;
+        for segment in segments {
+            value = match (*segment, value) {
+                (PathSegment::Key(key), &Sexp::List(ref elts)) => {
+                    let entry = elts.iter().filter_map(Sexp::as_pair).find(|&(car, _)| match *car {
+                        Sexp::Atom(ref atom) => atom.as_str() == key,
+                        _ => false,
+                    });
+                    match entry {
+                        Some((_, cdr)) => cdr,
+                        None => return None,
+                    }
+                }
+                (PathSegment::Index(i), &Sexp::List(ref elts)) |
+                (PathSegment::Index(i), &Sexp::Vector(ref elts)) => {
+                    match elts.get(i) {
+                        Some(elt) => elt,
+                        None => return None,
+                    }
+                }
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Whether `self` is an alist with an entry for `key`, matched the same
+    /// way as a `Key` segment of `get_path`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!(((name . "John Doe") (age . 43)));
+    /// assert!(v.contains_key("age"));
+    /// assert!(!v.contains_key("phones"));
+    /// # }
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get_path(&[PathSegment::Key(key)]).is_some()
+    }
+
+    /// Whether `self` is a list or vector with an element equal to `value`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!((sexp s-exp sexpr));
+    /// assert!(v.contains(&sexp!(sexpr)));
+    /// assert!(!v.contains(&sexp!(json)));
+    /// # }
+    /// ```
+    pub fn contains(&self, value: &Sexp) -> bool {
+        match *self {
+            Sexp::List(ref elts) | Sexp::Vector(ref elts) => elts.contains(value),
+            _ => false,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements of a list
+    /// or vector, or to the car and cdr of a pair. Empty cons cells are
+    /// skipped, and any other value yields nothing.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!((1 2 3));
+    /// for elt in v.iter_mut() {
+    ///     if let Sexp::Number(ref mut n) = *elt {
+    ///         *n = Number::from(n.as_i64().unwrap() * 2);
+    ///     }
+    /// }
+    /// assert_eq!(v, sexp!((2 4 6)));
+    /// # }
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut {
+        match *self {
+            Sexp::List(ref mut elts) | Sexp::Vector(ref mut elts) => {
+                IterMut {
+                    cells: None.into_iter().chain(None),
+                    elts: elts.iter_mut(),
+                }
+            }
+            Sexp::Pair(ref mut car, ref mut cdr) => {
+                IterMut {
+                    cells: car.as_mut().map(|car| &mut **car).into_iter()
+                        .chain(cdr.as_mut().map(|cdr| &mut **cdr)),
+                    elts: [].iter_mut(),
+                }
+            }
+            _ => {
+                IterMut {
+                    cells: None.into_iter().chain(None),
+                    elts: [].iter_mut(),
+                }
+            }
+        }
+    }
+
+    /// Returns the first half of a pair, or the first element of a list.
+    ///
+    /// An empty cons cell reads as `Sexp::Nil`. Returns `None` for an empty
+    /// list and for anything else that is not a pair or a list.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(sexp!((a . b)).car(), Some(&sexp!(a)));
+    /// assert_eq!(sexp!((a b c)).car(), Some(&sexp!(a)));
+    /// # }
+    /// ```
+    pub fn car(&self) -> Option<&Sexp> {
+        match *self {
+            Sexp::Pair(ref car, _) => Some(car.as_ref().map_or(&NIL, |car| &**car)),
+            Sexp::List(ref elts) => elts.first(),
+            _ => None,
+        }
+    }
+
+    /// Returns the second half of a pair, or all but the first element of a
+    /// list.
+    ///
+    /// The rest of a list is not stored as a `Sexp` of its own, so it is built
+    /// as a new `Sexp::List`; this is why a `Cow` is returned. An empty cons
+    /// cell reads as `Sexp::Nil`. Returns `None` for an empty list and for
+    /// anything else that is not a pair or a list.
+    ///
+    /// Building that list clones every element after the first, so walking a
+    /// list of `n` elements by repeated `cdr` takes O(n²) time and copies.
+    /// To walk a list, match `Sexp::List(ref elts)` and iterate over `elts`,
+    /// or slice it as `&elts[1..]`, which borrows instead.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(*sexp!((a . b)).cdr().unwrap(), sexp!(b));
+    /// assert_eq!(*sexp!((a b c)).cdr().unwrap(), sexp!((b c)));
+    /// # }
+    /// ```
+    pub fn cdr(&self) -> Option<Cow<Sexp>> {
+        match *self {
+            Sexp::Pair(_, ref cdr) => {
+                Some(Cow::Borrowed(cdr.as_ref().map_or(&NIL, |cdr| &**cdr)))
+            }
+            Sexp::List(ref elts) if !elts.is_empty() => {
+                Some(Cow::Owned(Sexp::List(elts[1..].to_vec())))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns both halves of a pair, reading an empty cons cell as
+    /// `Sexp::Nil`. Returns `None` for anything that is not a pair, including
+    /// lists.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!((a . 1));
+    /// assert_eq!(v.as_pair(), Some((&sexp!(a), &sexp!(1))));
+    /// # }
+    /// ```
+    pub fn as_pair(&self) -> Option<(&Sexp, &Sexp)> {
+        match *self {
+            Sexp::Pair(ref car, ref cdr) => {
+                Some((car.as_ref().map_or(&NIL, |car| &**car),
+                      cdr.as_ref().map_or(&NIL, |cdr| &**cdr)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `self` counts as true in a conditional such as `if` or
+    /// `cond`. Only `#nil`, `#f` and the empty list `()` are false;
+    /// everything else, including `0`, `""` and an empty vector, is true.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert!(!sexp!(#f).is_truthy());
+    /// assert!(!sexp!(()).is_truthy());
+    /// assert!(sexp!(0).is_truthy());
+    /// # }
+    /// ```
+    pub fn is_truthy(&self) -> bool {
+        match *self {
+            Sexp::Nil | Sexp::Boolean(false) => false,
+            Sexp::List(ref elts) => !elts.is_empty(),
+            _ => true,
+        }
+    }
+
+    /// Whether `self` is an association list: a non-empty list whose elements
+    /// are all pairs, such as `((a . 1) (b . 2))`. An empty list is not
+    /// considered an alist.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert!(sexp!(((a . 1) (b . 2))).is_alist());
+    /// assert!(!sexp!((a b c)).is_alist());
+    /// # }
+    /// ```
+    pub fn is_alist(&self) -> bool {
+        match *self {
+            Sexp::List(ref elts) => {
+                !elts.is_empty() && elts.iter().all(|elt| match *elt {
+                    Sexp::Pair(_, _) => true,
+                    _ => false,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the entries of an alist as `(key, value)` pairs, in order,
+    /// without copying any of them. Returns `None` unless `is_alist` holds
+    /// and every key is a symbol, string or keyword.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!(((a . 1) (b . 2)));
+    /// assert_eq!(v.as_alist(), Some(vec![("a", &sexp!(1)), ("b", &sexp!(2))]));
+    /// assert_eq!(sexp!(((a . 1) b)).as_alist(), None);
+    /// # }
+    /// ```
+    pub fn as_alist(&self) -> Option<Vec<(&str, &Sexp)>> {
+        let elts = match *self {
+            Sexp::List(ref elts) if !elts.is_empty() => elts,
+            _ => return None,
+        };
+        elts.iter()
+            .map(|elt| match elt.as_pair() {
+                Some((&Sexp::Atom(ref key), value)) => Some((key.as_str(), value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Splits the entries of an alist in two by `pred`, which is given each
+    /// entry's key and value. Returns an alist of the entries for which
+    /// `pred` holds and one of the rest, each keeping the original order.
+    /// Elements that are not pairs keyed by a symbol, string or keyword go to
+    /// the second alist; a value that is not a list gives two empty lists.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!(((meta-id . 1) (name . "x")));
+    /// let (meta, data) = v.partition_alist(|key, _| key.starts_with("meta-"));
+    /// assert_eq!(meta, sexp!(((meta-id . 1))));
+    /// assert_eq!(data, sexp!(((name . "x"))));
+    /// # }
+    /// ```
+    pub fn partition_alist<F>(&self, pred: F) -> (Sexp, Sexp)
+    where
+        F: Fn(&str, &Sexp) -> bool,
+    {
+        let (mut matching, mut rest) = (Vec::new(), Vec::new());
+        if let Sexp::List(ref elts) = *self {
+            for elt in elts {
+                let matches = match elt.as_pair() {
+                    Some((&Sexp::Atom(ref key), value)) => pred(key.as_str(), value),
+                    _ => false,
+                };
+                if matches {
+                    matching.push(elt.clone());
+                } else {
+                    rest.push(elt.clone());
+                }
+            }
+        }
+        (Sexp::List(matching), Sexp::List(rest))
+    }
+
+    /// Returns the names of the elements if `self` is a list made up only of
+    /// symbols, such as `(sexp s-exp sexpr)`. Returns `None` if any element
+    /// is not a symbol or `self` is not a list.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(sexp!((a b c)).as_symbol_list(), Some(vec!["a", "b", "c"]));
+    /// assert_eq!(sexp!((a 1 b)).as_symbol_list(), None);
+    /// # }
+    /// ```
+    pub fn as_symbol_list(&self) -> Option<Vec<&str>> {
+        match *self {
+            Sexp::List(ref elts) => {
+                elts.iter()
+                    .map(|elt| match *elt {
+                        Sexp::Atom(ref atom) => atom.as_symbol(),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the number `self` holds, reading it from a string or symbol
+    /// if need be, so `8080` and `"8080"` both give `Some(8080)`. Returns
+    /// `None` for anything that doesn't look numeric.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v: Sexp = sexpr::from_str(r#"(8080 "8080")"#).unwrap();
+    /// assert_eq!(v[0].coerce_number(), Some(Number::from(8080)));
+    /// assert_eq!(v[1].coerce_number(), Some(Number::from(8080)));
+    /// # }
+    /// ```
+    pub fn coerce_number(&self) -> Option<Number> {
+        let text = match *self {
+            Sexp::Number(ref n) => return Some(n.clone()),
+            Sexp::Atom(ref atom) if !atom.is_keyword() => atom.as_str().trim(),
+            _ => return None,
+        };
+        text.parse().ok()
+    }
+
+    /// Returns the number `self` holds as an `f64`, reading it from a string
+    /// or symbol if need be, as `coerce_number` does. Integers are converted
+    /// too, losing precision beyond 2^53. Returns `None` for anything that
+    /// doesn't look numeric.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v: Sexp = sexpr::from_str(r#"(2 0.5 "1.5" x)"#).unwrap();
+    /// assert_eq!(v[0].as_f64_lossy(), Some(2.0));
+    /// assert_eq!(v[2].as_f64_lossy(), Some(1.5));
+    /// assert_eq!(v[3].as_f64_lossy(), None);
+    /// # }
+    /// ```
+    pub fn as_f64_lossy(&self) -> Option<f64> {
+        self.coerce_number().and_then(|n| n.as_f64())
+    }
+
+    /// Encodes `self` in canonical form, as `sexpr::to_canonical` does, but
+    /// with the entries of every alist sorted by key first. Two alists with
+    /// the same entries in a different order get the same encoding, which
+    /// makes this suitable for signing data whose key order is not
+    /// meaningful. Only lists for which `is_alist` holds are reordered.
+    ///
+    /// ```rust,ignore
+    /// let a: Sexp = sexpr::from_str("((b . 2) (a . 1))").unwrap();
+    /// let b: Sexp = sexpr::from_str("((a . 1) (b . 2))").unwrap();
+    /// assert_eq!(a.to_string_canonical_sorted(), b.to_string_canonical_sorted());
+    /// ```
+    pub fn to_string_canonical_sorted(&self) -> String {
+        canonical::to_canonical_sorted(self)
+    }
+
+    /// Writes `self` to `writer` as `sexpr::to_string` would render it.
+    ///
+    /// The text goes straight to the writer as the tree is walked: strings
+    /// are escaped a run of unescaped bytes at a time and numbers are
+    /// formatted on the stack, so unlike `to_string` no buffer is allocated
+    /// for the output or for any node within it. Wrap an unbuffered sink
+    /// such as a `File` in a `BufWriter`.
+    ///
+    /// ```rust,ignore
+    /// let v: Sexp = sexpr::from_str(r#"(a "b\n" 3)"#).unwrap();
+    /// let mut out = Vec::new();
+    /// v.write_to(&mut out).unwrap();
+    /// assert_eq!(out, br#"(a "b\n" 3)"#);
+    /// ```
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        ::ser::to_writer(writer, self).map_err(io::Error::from)
+    }
+
+    /// Returns a value that displays `self` the way `to_string_with_config`
+    /// would write it, for rendering the same `Sexp` for different dialects.
+    /// `{:#}` pretty-prints it.
+    ///
+    /// ```rust,ignore
+    /// let mut config = SerializeConfig::default();
+    /// config.bool_style = Some(BoolStyle::TNil);
+    ///
+    /// let v: Sexp = sexpr::from_str("(#t #f)").unwrap();
+    /// assert_eq!(v.display_with(config).to_string(), "(t nil)");
+    /// ```
+    pub fn display_with(&self, config: SerializeConfig) -> DisplayWith {
+        DisplayWith::new(self, config)
+    }
+
+    /// Hashes the canonical encoding of `self`, as produced by
+    /// `sexpr::to_canonical`, without building the encoded string. The
+    /// encoding is fed to the hasher piece by piece as the tree is walked.
+    ///
+    /// ```rust,ignore
+    /// extern crate sha2;
+    /// use sha2::Sha256;
+    ///
+    /// let v: Sexp = sexpr::from_str("(a b c)").unwrap();
+    /// let digest = v.canonical_hash::<Sha256>();
+    /// ```
+    #[cfg(feature = "sha2")]
+    pub fn canonical_hash<H: Digest>(&self) -> Output<H> {
+        let mut hasher = H::new();
+        canonical::encode(self, &mut |bytes: &[u8]| hasher.update(bytes));
+        hasher.finalize()
+    }
+
+    /// Returns the maximum nesting depth of `self`. Scalars have depth 0, `(a)`
+    /// has depth 1 and `((a))` has depth 2.
+    ///
+    /// A chain of pairs counts as a single list, so `(a b . c)`, which is the
+    /// pair `(a . (b . c))`, has depth 1 just like `(a b c)`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(sexp!(1).depth(), 0);
+    /// assert_eq!(sexp!((1 (2 3))).depth(), 2);
+    /// # }
+    /// ```
+    pub fn depth(&self) -> usize {
+        match *self {
+            Sexp::List(ref elts) | Sexp::Vector(ref elts) => {
+                1 + elts.iter().map(Sexp::depth).max().unwrap_or(0)
+            }
+            Sexp::Pair(_, _) => {
+                // Walk the cdr chain iteratively; only cars nest deeper.
+                let mut depth = 0;
+                let mut spine = self;
+                loop {
+                    match *spine {
+                        Sexp::Pair(ref car, ref cdr) => {
+                            if let Some(ref car) = *car {
+                                depth = cmp::max(depth, car.depth());
+                            }
+                            match *cdr {
+                                Some(ref cdr) => spine = cdr,
+                                None => break,
+                            }
+                        }
+                        ref tail => {
+                            depth = cmp::max(depth, tail.depth());
+                            break;
+                        }
+                    }
+                }
+                1 + depth
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns the total number of nodes in `self`: every atom, number,
+    /// boolean and nil, plus one for each list, vector and pair containing
+    /// them. An empty car or cdr of a pair counts as a nil.
+    ///
+    /// Useful for rejecting pathologically large input after parsing.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(sexp!(1).count_nodes(), 1);
+    /// assert_eq!(sexp!((a b)).count_nodes(), 3);
+    /// # }
+    /// ```
+    pub fn count_nodes(&self) -> usize {
+        match *self {
+            Sexp::List(ref elts) | Sexp::Vector(ref elts) => {
+                1 + elts.iter().map(Sexp::count_nodes).sum::<usize>()
+            }
+            Sexp::Pair(_, _) => {
+                // Walk the cdr chain iteratively, as in `depth`.
+                let mut count = 0;
+                let mut spine = self;
+                loop {
+                    match *spine {
+                        Sexp::Pair(ref car, ref cdr) => {
+                            count += 1 + car.as_ref().map_or(1, |car| car.count_nodes());
+                            match *cdr {
+                                Some(ref cdr) => spine = cdr,
+                                None => {
+                                    count += 1;
+                                    break;
+                                }
+                            }
+                        }
+                        ref tail => {
+                            count += tail.count_nodes();
+                            break;
+                        }
+                    }
+                }
+                count
+            }
+            _ => 1,
+        }
+    }
+
+    /// Splices the elements of a list that are themselves lists into it, one
+    /// level deep, like `(apply append ...)`. Other elements stay in place and
+    /// anything other than a list is returned unchanged.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// assert_eq!(sexp!(((1 2) (3 (4)) 5)).flatten(), sexp!((1 2 3 (4) 5)));
+    /// # }
+    /// ```
+    pub fn flatten(&self) -> Sexp {
+        match *self {
+            Sexp::List(ref elts) => {
+                let mut flat = Vec::with_capacity(elts.len());
+                for elt in elts {
+                    match *elt {
+                        Sexp::List(ref inner) => flat.extend(inner.iter().cloned()),
+                        ref other => flat.push(other.clone()),
+                    }
+                }
+                Sexp::List(flat)
+            }
+            ref other => other.clone(),
+        }
+    }
+
+    /// Rewrites cons cells built up programmatically into the lists they
+    /// stand for, at any depth. The rules, applied from the leaves up, are:
+    ///
+    /// - an empty car is `#nil`, so `Pair(None, cdr)` is `(#nil . cdr)`;
+    /// - a cell with no cdr, or a cdr of `#nil`, is the one-element list of
+    ///   its car, so `(x . #nil)` becomes `(x)`;
+    /// - a cell whose cdr is a list is that list with the car in front, so
+    ///   `(x . (y z))` becomes `(x y z)`;
+    /// - an empty cell, `Pair(None, None)`, is `#nil`.
+    ///
+    /// Any other pair, such as `(a . b)`, is kept. Simplifying a second time
+    /// changes nothing.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = Sexp::Pair(Some(Box::new(sexp!(a))), None);
+    /// assert_eq!(v.simplify(), sexp!((a)));
+    /// assert_eq!(sexp!((a . (b c))).simplify(), sexp!((a b c)));
+    /// # }
+    /// ```
+    pub fn simplify(self) -> Sexp {
+        match self {
+            Sexp::List(elts) => Sexp::List(elts.into_iter().map(Sexp::simplify).collect()),
+            Sexp::Vector(elts) => Sexp::Vector(elts.into_iter().map(Sexp::simplify).collect()),
+            Sexp::Pair(None, None) => Sexp::Nil,
+            Sexp::Pair(car, cdr) => {
+                let car = car.map_or(Sexp::Nil, |car| car.simplify());
+                match cdr.map(|cdr| cdr.simplify()) {
+                    None | Some(Sexp::Nil) => Sexp::List(vec![car]),
+                    Some(Sexp::List(mut elts)) => {
+                        elts.insert(0, car);
+                        Sexp::List(elts)
+                    }
+                    Some(cdr) => Sexp::Pair(Some(Box::new(car)), Some(Box::new(cdr))),
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Renames every symbol in `self`, at any depth, whose name is a key of
+    /// `mapping`. Strings and keywords are left untouched even when their
+    /// text matches.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # use std::collections::HashMap;
+    /// #
+    /// # fn main() {
+    /// let mut mapping = HashMap::new();
+    /// mapping.insert("x".to_string(), "y".to_string());
+    ///
+    /// let mut v = sexp!((f x "x"));
+    /// v.replace_symbols(&mapping);
+    /// assert_eq!(v, sexp!((f y "x")));
+    /// # }
+    /// ```
+    pub fn replace_symbols(&mut self, mapping: &HashMap<String, String>) {
+        match *self {
+            Sexp::Atom(ref mut a) => {
+                let renamed = match a.as_symbol().and_then(|name| mapping.get(name)) {
+                    Some(renamed) => renamed.clone(),
+                    None => return,
+                };
+                *a = Atom::into_symbol(renamed);
+            }
+            Sexp::Pair(ref mut car, ref mut cdr) => {
+                for cell in vec![car, cdr] {
+                    if let Some(ref mut v) = *cell {
+                        v.replace_symbols(mapping);
+                    }
+                }
+            }
+            Sexp::List(ref mut elts) | Sexp::Vector(ref mut elts) => {
+                for elt in elts {
+                    elt.replace_symbols(mapping);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Retains only the elements of a list for which `f` returns `true`,
+    /// analogous to `Vec::retain`. Each entry of an alist is passed to `f` as
+    /// a whole pair.
+    ///
+    /// Values other than `Sexp::List` are left unchanged.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!((1 2 3 4));
+    /// v.retain(|x| match *x {
+    ///     Sexp::Number(ref n) => n.as_u64().map_or(true, |n| n % 2 != 0),
+    ///     _ => true,
+    /// });
+    /// assert_eq!(v, sexp!((1 3)));
+    /// # }
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+        where
+        F: FnMut(&Sexp) -> bool,
+    {
+        if let Sexp::List(ref mut elts) = *self {
+            elts.retain(f);
+        }
+    }
+
+    /// Calls `f` with the key and a mutable reference to the value of each
+    /// `(key . value)` entry of an alist, so values can be rewritten in place.
+    /// An entry with an empty cdr is passed `Sexp::Nil`.
+    ///
+    /// Elements that are not pairs with a symbol, keyword or string key are
+    /// skipped, and values other than `Sexp::List` are left unchanged.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!(((port . 80) (timeout . 5)));
+    /// v.map_values(|key, value| if key == "port" {
+    ///     *value = sexp!(8080);
+    /// });
+    /// assert_eq!(v, sexp!(((port . 8080) (timeout . 5))));
+    /// # }
+    /// ```
+    pub fn map_values<F>(&mut self, mut f: F)
+        where
+        F: FnMut(&str, &mut Sexp),
+    {
+        let elts = match *self {
+            Sexp::List(ref mut elts) => elts,
+            _ => return,
+        };
+        for elt in elts {
+            if let Sexp::Pair(Some(ref car), ref mut cdr) = *elt {
+                let key = match **car {
+                    Sexp::Atom(ref atom) => atom.as_str(),
+                    _ => continue,
+                };
+                match *cdr {
+                    Some(ref mut value) => f(key, value),
+                    None => {
+                        let mut value = Sexp::Nil;
+                        f(key, &mut value);
+                        if value != Sexp::Nil {
+                            *cdr = Some(Box::new(value));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the value of the alist entry for `key`, first appending a
+    /// `(key . value)` entry with the value returned by `f` if there is none.
+    /// Keys are matched the same way as a `Key` segment of `get_path`, and
+    /// `Sexp::Nil` is treated as an empty alist. A new key is always a
+    /// symbol, so that `"#:x"` is found again as `"#:x"` rather than read as
+    /// the keyword `x`.
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving `self` unchanged, if `self` is neither a list nor
+    /// `Sexp::Nil`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!(((port . 80)));
+    /// *v.get_or_insert_with("timeout", || sexp!(5)).unwrap() = sexp!(30);
+    /// assert_eq!(*v.get_or_insert_with("port", || sexp!(8080)).unwrap(), sexp!(80));
+    /// assert_eq!(v, sexp!(((port . 80) (timeout . 30))));
+    /// # }
+    /// ```
+    pub fn get_or_insert_with<F>(&mut self, key: &str, f: F) -> Result<&mut Sexp, Error>
+        where
+        F: FnOnce() -> Sexp,
+    {
+        if let Sexp::Nil = *self {
+            *self = Sexp::List(Vec::new());
+        }
+        let elts = match *self {
+            Sexp::List(ref mut elts) => elts,
+            ref this => return Err(serde::de::Error::invalid_type(this.unexpected(), &"an alist")),
+        };
+
+        let found = elts.iter().position(|elt| match *elt {
+            Sexp::Pair(Some(ref car), _) => match **car {
+                Sexp::Atom(ref atom) => atom.as_str() == key,
+                _ => false,
+            },
+            _ => false,
+        });
+        let i = match found {
+            Some(i) => i,
+            None => {
+                let key = Sexp::Atom(Atom::into_symbol(String::from(key)));
+                elts.push(Sexp::Pair(Some(Box::new(key)), Some(Box::new(f()))));
+                elts.len() - 1
+            }
+        };
+
+        match elts[i] {
+            Sexp::Pair(_, ref mut cdr) => Ok(&mut **cdr.get_or_insert_with(|| Box::new(Sexp::Nil))),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Moves all the elements of `other` onto the end of `self`, as with
+    /// `Vec::append`.
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving `self` unchanged, if either `self` or `other` is not a
+    /// `Sexp::List`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!((1 2));
+    /// v.append(sexp!((3 4))).unwrap();
+    /// assert_eq!(v, sexp!((1 2 3 4)));
+    /// # }
+    /// ```
+    pub fn append(&mut self, other: Sexp) -> Result<(), Error> {
+        match (self, other) {
+            (&mut Sexp::List(ref mut elts), Sexp::List(mut others)) => {
+                elts.append(&mut others);
+                Ok(())
+            }
+            (&mut Sexp::List(_), other) => {
+                Err(serde::de::Error::invalid_type(other.unexpected(), &"a list"))
+            }
+            (this, _) => Err(serde::de::Error::invalid_type(this.unexpected(), &"a list")),
+        }
+    }
+
+    /// Replaces the elements of a list in `range` with `replacement`, as with
+    /// `Vec::splice`, and returns the elements that were removed. An empty
+    /// range inserts `replacement` without removing anything.
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving `self` unchanged, if `self` is not a `Sexp::List` or
+    /// `range` does not lie within it.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!((a b c d));
+    /// let removed = v.splice(1..3, vec![sexp!(x)]).unwrap();
+    /// assert_eq!(v, sexp!((a x d)));
+    /// assert_eq!(removed, vec![sexp!(b), sexp!(c)]);
+    /// # }
+    /// ```
+    pub fn splice(&mut self, range: Range<usize>, replacement: Vec<Sexp>)
+                  -> Result<Vec<Sexp>, Error> {
+        let elts = match *self {
+            Sexp::List(ref mut elts) => elts,
+            ref this => return Err(serde::de::Error::invalid_type(this.unexpected(), &"a list")),
+        };
+        if range.start > range.end || range.end > elts.len() {
+            return Err(serde::de::Error::custom(format_args!(
+                "range {}..{} out of bounds for a list of length {}",
+                range.start,
+                range.end,
+                elts.len()
+            )));
+        }
+        Ok(elts.splice(range, replacement).collect())
+    }
+
+    /// Replaces the value at `path` with `new` and returns the value that was
+    /// there. `path` is a pointer such as `/server/ports/0`: each segment
+    /// indexes a vector or a plain list if it is a number, and otherwise
+    /// selects the value of an alist entry, as a `Key` segment of
+    /// `get_path` does. Missing alist entries along the way are created, as
+    /// with `get_or_insert_with`, and nil is treated as an empty alist. As in
+    /// a JSON pointer, `~1` and `~0` stand for `/` and `~` within a segment,
+    /// and the empty path refers to `self`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` does not start with `/`, an index is out of bounds,
+    /// or a segment runs into an atom. The segments already followed may
+    /// have been created by then.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!(((server . ((port . 80)))));
+    /// let old = v.replace_at("/server/port", sexp!(8080)).unwrap();
+    /// assert_eq!(old, sexp!(80));
+    ///
+    /// v.replace_at("/client/timeout", sexp!(30)).unwrap();
+    /// assert_eq!(v, sexp!(((server . ((port . 8080))) (client . ((timeout . 30))))));
+    /// # }
+    /// ```
+    pub fn replace_at(&mut self, path: &str, new: Sexp) -> Result<Sexp, Error> {
+        if path.is_empty() {
+            return Ok(mem::replace(self, new));
+        }
+        if !path.starts_with('/') {
+            return Err(serde::de::Error::custom(
+                format_args!("invalid path {:?}: must start with '/'", path),
+            ));
+        }
+
+        let mut target = self;
+        for segment in path[1..].split('/') {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            target = try!(step_or_insert(target, &segment));
+        }
+        Ok(mem::replace(target, new))
+    }
+
+    /// Shrinks the capacity of every list and vector in `self`, at any depth,
+    /// to fit its length, as with `Vec::shrink_to_fit`. Useful for trees kept
+    /// around after heavy editing with `retain` or `splice`.
+    ///
+    /// ```rust,ignore
+    /// let mut v = Sexp::List(Vec::with_capacity(64));
+    /// v.append(sexp!((1 2 3))).unwrap();
+    /// v.shrink_to_fit();
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        match *self {
+            Sexp::List(ref mut elts) | Sexp::Vector(ref mut elts) => {
+                elts.shrink_to_fit();
+                for elt in elts {
+                    elt.shrink_to_fit();
+                }
+            }
+            Sexp::Pair(ref mut car, ref mut cdr) => {
+                for cell in car.iter_mut().chain(cdr.iter_mut()) {
+                    cell.shrink_to_fit();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Lays the elements of the list `other` over those of the list `self`,
+    /// position by position: each element of `other` replaces the element of
+    /// `self` at the same index, unless it is nil, in which case the element
+    /// of `self` is kept. Elements of `other` past the end of `self` are
+    /// appended. Both `#nil` and the symbol `nil` count as nil.
+    ///
+    /// Does nothing unless both `self` and `other` are lists.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!((a b c d));
+    /// v.overlay(&sexp!((1 nil 3)));
+    /// assert_eq!(v, sexp!((1 b 3 d)));
+    /// # }
+    /// ```
+    pub fn overlay(&mut self, other: &Sexp) {
+        let (elts, others) = match (self, other) {
+            (&mut Sexp::List(ref mut elts), &Sexp::List(ref others)) => (elts, others),
+            _ => return,
+        };
+        for (i, other) in others.iter().enumerate() {
+            let is_nil = match *other {
+                Sexp::Nil => true,
+                Sexp::Atom(ref atom) => atom.as_symbol() == Some("nil"),
+                _ => false,
+            };
+            if i >= elts.len() {
+                elts.push(other.clone());
+            } else if !is_nil {
+                elts[i] = other.clone();
+            }
+        }
+    }
+
+    /// Removes entries of an alist whose key repeats an earlier or later
+    /// entry, as chosen by `policy`. Only dotted pair elements, `(key .
+    /// value)`, are treated as entries; other elements are kept as they are.
+    /// Does nothing if `self` is not a list.
+    ///
+    /// # Errors
+    ///
+    /// With `DedupPolicy::Error`, fails on the first repeated key, leaving
+    /// `self` unchanged.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # use sexpr::sexp::DedupPolicy;
+    /// #
+    /// # fn main() {
+    /// let mut v = sexp!(((a . 1) (b . 2) (a . 3)));
+    /// v.dedup_keys(DedupPolicy::LastWins).unwrap();
+    /// assert_eq!(v, sexp!(((b . 2) (a . 3))));
+    /// # }
+    /// ```
+    pub fn dedup_keys(&mut self, policy: DedupPolicy) -> Result<(), Error> {
+        let elts = match *self {
+            Sexp::List(ref mut elts) => elts,
+            _ => return Ok(()),
+        };
+
+        fn key(elt: &Sexp) -> Option<&Sexp> {
+            match *elt {
+                Sexp::Pair(Some(ref car), _) => Some(car),
+                _ => None,
+            }
+        }
+
+        let keep: Vec<bool> = {
+            let keys: Vec<Option<&Sexp>> = elts.iter().map(key).collect();
+            let mut keep = Vec::with_capacity(keys.len());
+            for (i, k) in keys.iter().enumerate() {
+                let k = match *k {
+                    Some(k) => k,
+                    None => {
+                        keep.push(true);
+                        continue;
+                    }
+                };
+                let earlier = keys[..i].iter().any(|other| *other == Some(k));
+                keep.push(match policy {
+                    DedupPolicy::FirstWins => !earlier,
+                    DedupPolicy::LastWins => !keys[i + 1..].iter().any(|other| *other == Some(k)),
+                    DedupPolicy::Error if earlier => {
+                        return Err(Error::syntax(ErrorCode::DuplicateKey(k.to_string()), 0, 0));
+                    }
+                    DedupPolicy::Error => true,
+                });
+            }
+            keep
+        };
+
+        let mut keep = keep.into_iter();
+        elts.retain(|_| keep.next().unwrap());
+        Ok(())
+    }
+
+    /// Renders an alist with each `(key . value)` entry on its own line and
+    /// the keys padded so the dots line up, for showing a configuration back
+    /// to a user. Values are pretty-printed, and values that are themselves
+    /// alists are laid out the same way. Anything that is not an alist is
+    /// rendered as by `{:#}`.
+    ///
+    /// ```rust,ignore
+    /// # #[macro_use]
+    /// # extern crate sexpr;
+    /// #
+    /// # fn main() {
+    /// let v = sexp!(((name . "sexpr") (port . 8080)));
+    /// assert_eq!(v.to_pretty_alist(), "((name . \"sexpr\")\n (port . 8080))");
+    /// # }
+    /// ```
+    pub fn to_pretty_alist(&self) -> String {
+        let mut out = String::new();
+        write_pretty_alist(&mut out, self, 0);
+        out
+    }
+
     // fn search_alist<S: ToString>(&self, key: S) -> Option<Sexp>
     // {
     //     let key = key.to_string();
@@ -283,6 +1392,106 @@ impl Sexp {
 
 }
 
+/// Writes `value` for `Sexp::to_pretty_alist`, starting at `column`, which
+/// continuation lines are indented to.
+fn write_pretty_alist(out: &mut String, value: &Sexp, column: usize) {
+    let entries: Option<Vec<(String, &Sexp)>> = match *value {
+        Sexp::List(ref elts) if !elts.is_empty() => {
+            let entries = elts.iter().map(|elt| match *elt {
+                Sexp::Pair(Some(ref key), ref value) => {
+                    Some((key.to_string(), value.as_ref().map_or(&NIL, |v| &**v)))
+                }
+                _ => None,
+            });
+            entries.collect()
+        }
+        _ => None,
+    };
+
+    let entries = match entries {
+        Some(entries) => entries,
+        None => {
+            let pretty = format!("{:#}", value);
+            let indent = format!("\n{:1$}", "", column);
+            out.push_str(&pretty.replace('\n', &indent));
+            return;
+        }
+    };
+
+    let width = entries.iter().map(|&(ref key, _)| key.chars().count()).max().unwrap_or(0);
+    out.push('(');
+    for (i, &(ref key, value)) in entries.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(&format!("{:1$}", "", column + 1));
+        }
+        out.push_str(&format!("({:1$} . ", key, width));
+        write_pretty_alist(out, value, column + width + 5);
+        out.push(')');
+    }
+    out.push(')');
+}
+
+/// Chooses which entry `Sexp::dedup_keys` keeps when an alist has more than
+/// one entry with the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupPolicy {
+    /// Keep the first entry for each key.
+    FirstWins,
+    /// Keep the last entry for each key.
+    LastWins,
+    /// Fail on a repeated key.
+    Error,
+}
+
+/// Iterator over mutable references to the children of a `Sexp`, returned
+/// by `Sexp::iter_mut`.
+pub struct IterMut<'a> {
+    cells: iter::Chain<option::IntoIter<&'a mut Sexp>, option::IntoIter<&'a mut Sexp>>,
+    elts: slice::IterMut<'a, Sexp>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut Sexp;
+
+    fn next(&mut self) -> Option<&'a mut Sexp> {
+        self.cells.next().or_else(|| self.elts.next())
+    }
+}
+
+/// Follows one segment of a `replace_at` path, creating an alist entry for
+/// it if it names a key that is not there yet.
+fn step_or_insert<'a>(target: &'a mut Sexp, segment: &str) -> Result<&'a mut Sexp, Error> {
+    let index = match (segment.parse::<usize>(), &*target) {
+        (Ok(i), &Sexp::Vector(_)) => Some(i),
+        (Ok(i), &Sexp::List(ref elts)) if !elts.is_empty() && !target.is_alist() => Some(i),
+        _ => None,
+    };
+    match (index, target) {
+        (Some(i), &mut Sexp::List(ref mut elts)) |
+        (Some(i), &mut Sexp::Vector(ref mut elts)) => {
+            let len = elts.len();
+            elts.get_mut(i).ok_or_else(|| {
+                serde::de::Error::custom(
+                    format_args!("index {} out of bounds for a list of length {}", i, len),
+                )
+            })
+        }
+        (_, target @ &mut Sexp::Nil) |
+        (_, target @ &mut Sexp::List(_)) => target.get_or_insert_with(segment, || Sexp::Nil),
+        (_, other) => Err(serde::de::Error::invalid_type(other.unexpected(), &"a list or vector")),
+    }
+}
+
+/// One step of a path for `Sexp::get_path`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    /// The value of an alist entry.
+    Key(&'a str),
+    /// An element of a list or vector.
+    Index(usize),
+}
+
 /// Convert a `T` into `sexpr::Sexp` which is an enum that can represent
 /// any valid S-expression data.
 ///
@@ -351,7 +1560,20 @@ pub fn to_value<T>(value: T) -> Result<Sexp, Error>
 where
     T: Serialize,
 {
-    value.serialize(Serializer)
+    to_value_with_config(value, SerializeConfig::default())
+}
+
+/// Convert a `T` into `sexpr::Sexp`, laid out according to `config`.
+///
+/// # Errors
+///
+/// This conversion can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn to_value_with_config<T>(value: T, config: SerializeConfig) -> Result<Sexp, Error>
+where
+    T: Serialize,
+{
+    value.serialize(Serializer::new(config))
 }
 
 /// Interpret a `sexpr::Sexp` as an instance of type `T`.
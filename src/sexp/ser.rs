@@ -10,7 +10,8 @@ use serde::{self, Serialize};
 use error::{Error, ErrorCode};
 use number::Number;
 use atom::{Atom};
-use sexp::{Sexp, to_value};
+use config::{MapStyle, SerializeConfig, UnitVariantStyle};
+use sexp::{self, Sexp, PAIR_TOKEN, VECTOR_TOKEN, to_value_with_config};
 
 
 impl Serialize for Sexp {
@@ -24,17 +25,63 @@ impl Serialize for Sexp {
             Sexp::Number(ref n) => n.serialize(serializer),
             Sexp::Atom(ref atom) => atom.serialize(serializer),
             Sexp::List(ref v) => v.serialize(serializer),
-            Sexp::Pair(_, _) => {
-                unimplemented!()
-            },
-            // Sexp::Pair(Some(_), None) => unimplemented!(),
-            // Sexp::Pair(None, Some(_)) => unimplemented!(),
-            // Sexp::Pair(None, None)  => unimplemented!(),
+            Sexp::Vector(ref v) => serializer.serialize_newtype_struct(VECTOR_TOKEN, v),
+            Sexp::Pair(ref car, ref cdr) => {
+                let mut elts = vec![uncons(car)];
+                let mut tail = uncons(cdr);
+                while let Sexp::Pair(ref car, ref cdr) = *tail {
+                    elts.push(uncons(car));
+                    tail = uncons(cdr);
+                }
+                match *tail {
+                    // A chain of cells ending in nil is a proper list. A
+                    // single cell keeps its dot, since that is the shape of
+                    // an alist entry, `(key . value)`, whatever the value.
+                    Sexp::Nil if elts.len() > 1 => elts.serialize(serializer),
+                    _ => {
+                        elts.push(tail);
+                        serializer.serialize_newtype_struct(PAIR_TOKEN, &DottedList(elts))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn uncons(cell: &Option<Box<Sexp>>) -> &Sexp {
+    static NIL: Sexp = Sexp::Nil;
+    match *cell {
+        Some(ref sexp) => sexp,
+        None => &NIL,
+    }
+}
+
+/// The elements of an improper list, the last of which is written as the
+/// cdr: `(a b . c)`.
+struct DottedList<'a>(Vec<&'a Sexp>);
+
+impl<'a> Serialize for DottedList<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ::serde::Serializer,
+    {
+        use serde::ser::SerializeTuple;
+        let mut tuple = try!(serializer.serialize_tuple(self.0.len()));
+        for elt in &self.0 {
+            try!(tuple.serialize_element(elt));
         }
+        tuple.end()
     }
 }
 
-pub struct Serializer;
+pub struct Serializer {
+    config: SerializeConfig,
+}
+
+impl Serializer {
+    pub fn new(config: SerializeConfig) -> Self {
+        Serializer { config: config }
+    }
+}
 
 impl serde::Serializer for Serializer {
     type Ok = Sexp;
@@ -126,8 +173,12 @@ impl serde::Serializer for Serializer {
     }
 
     #[inline]
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<Sexp, Error> {
-        self.serialize_unit()
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Sexp, Error> {
+        if self.config.unit_struct_as_name {
+            Ok(Sexp::Atom(Atom::into_symbol(String::from(name))))
+        } else {
+            Ok(Sexp::List(Vec::new()))
+        }
     }
 
     #[inline]
@@ -137,19 +188,32 @@ impl serde::Serializer for Serializer {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Sexp, Error> {
-        self.serialize_str(variant)
+        match self.config.unit_variant_style {
+            UnitVariantStyle::Keyword => Ok(Sexp::Atom(Atom::into_keyword(variant.to_owned()))),
+            UnitVariantStyle::String | UnitVariantStyle::Symbol => self.serialize_str(variant),
+        }
     }
 
     #[inline]
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Sexp, Error>
         where
         T: Serialize,
     {
-        value.serialize(self)
+        match try!(value.serialize(self)) {
+            Sexp::List(v) if name == VECTOR_TOKEN => Ok(Sexp::Vector(v)),
+            Sexp::List(mut v) if name == PAIR_TOKEN && v.len() >= 2 => {
+                let mut tail = v.pop().unwrap();
+                while let Some(car) = v.pop() {
+                    tail = Sexp::Pair(Some(Box::new(car)), Some(Box::new(tail)));
+                }
+                Ok(tail)
+            }
+            other => Ok(other),
+        }
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -179,11 +243,20 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
-        Ok(SerializeVec { vec: Vec::with_capacity(len.unwrap_or(0)) })
+        Ok(
+            SerializeVec {
+                vec: Vec::with_capacity(len.unwrap_or(0)),
+                pair: false,
+                config: self.config,
+            },
+        )
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
-        self.serialize_seq(Some(len))
+        let pair = len == 2 && self.config.two_tuples_as_pairs;
+        let mut vec = try!(self.serialize_seq(Some(len)));
+        vec.pair = pair;
+        Ok(vec)
     }
 
     fn serialize_tuple_struct(
@@ -191,7 +264,7 @@ impl serde::Serializer for Serializer {
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Error> {
-        self.serialize_seq(Some(len))
+        self.serialize_tuple(len)
     }
 
     fn serialize_tuple_variant(
@@ -205,12 +278,19 @@ impl serde::Serializer for Serializer {
             SerializeTupleVariant {
                 name: String::from(variant),
                 vec: Vec::with_capacity(len),
+                config: self.config,
             },
         )
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
-        unimplemented!()
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(
+            SerializeMap {
+                entries: Vec::with_capacity(len.unwrap_or(0)),
+                next_key: None,
+                config: self.config,
+            },
+        )
     }
 
     fn serialize_struct(
@@ -235,12 +315,16 @@ impl serde::Serializer for Serializer {
 #[doc(hidden)]
 pub struct SerializeVec {
     vec: Vec<Sexp>,
+    // Build a dotted pair from the two elements rather than a list.
+    pair: bool,
+    config: SerializeConfig,
 }
 
-#[doc(hidden)]#[allow(dead_code)]
+#[doc(hidden)]
 pub struct SerializeTupleVariant {
     name: String,
     vec: Vec<Sexp>,
+    config: SerializeConfig,
 }
 
 impl serde::ser::SerializeSeq for SerializeVec {
@@ -251,7 +335,7 @@ impl serde::ser::SerializeSeq for SerializeVec {
         where
         T: Serialize,
     {
-        self.vec.push(try!(to_value(&value)));
+        self.vec.push(try!(to_value_with_config(&value, self.config.clone())));
         Ok(())
     }
 
@@ -271,7 +355,12 @@ impl serde::ser::SerializeTuple for SerializeVec {
         serde::ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(self) -> Result<Sexp, Error> {
+    fn end(mut self) -> Result<Sexp, Error> {
+        if self.pair && self.vec.len() == 2 {
+            let cdr = self.vec.pop().map(Box::new);
+            let car = self.vec.pop().map(Box::new);
+            return Ok(Sexp::Pair(car, cdr));
+        }
         serde::ser::SerializeSeq::end(self)
     }
 }
@@ -284,11 +373,11 @@ impl serde::ser::SerializeTupleStruct for SerializeVec {
         where
         T: Serialize,
     {
-        serde::ser::SerializeSeq::serialize_element(self, value)
+        serde::ser::SerializeTuple::serialize_element(self, value)
     }
 
     fn end(self) -> Result<Sexp, Error> {
-        serde::ser::SerializeSeq::end(self)
+        serde::ser::SerializeTuple::end(self)
     }
 }
 
@@ -300,18 +389,22 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
         where
         T: Serialize,
     {
-        self.vec.push(try!(to_value(&value)));
+        self.vec.push(try!(to_value_with_config(&value, self.config.clone())));
         Ok(())
     }
 
     fn end(self) -> Result<Sexp, Error> {
-        unimplemented!()
+        let mut vec = self.vec;
+        vec.insert(0, sexp::variant_tag(&self.name));
+        Ok(Sexp::List(vec))
     }
 }
 
 #[doc(hidden)]
 pub struct SerializeMap {
-    next_key: Option<String>,
+    entries: Vec<Sexp>,
+    next_key: Option<Sexp>,
+    config: SerializeConfig,
 }
 
 impl serde::ser::SerializeMap for SerializeMap {
@@ -322,11 +415,12 @@ impl serde::ser::SerializeMap for SerializeMap {
     where
         T: Serialize,
     {
-        match try!(to_value(&key)) {
-            Sexp::Atom(a) => self.next_key = Some(a.as_string()),
+        match try!(to_value_with_config(&key, self.config.clone())) {
+            Sexp::Atom(a) => self.next_key = Some(Sexp::Atom(Atom::from(a.into_inner()))),
+            // Integer keys stay numbers so they read back as integers.
             Sexp::Number(n) => {
                 if n.is_u64() || n.is_i64() {
-                    self.next_key = Some(n.to_string())
+                    self.next_key = Some(Sexp::Number(n))
                 } else {
                     return Err(Error::syntax(ErrorCode::KeyMustBeAString, 0, 0));
                 }
@@ -336,15 +430,35 @@ impl serde::ser::SerializeMap for SerializeMap {
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Error>
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
     where
         T: Serialize,
     {
-        unimplemented!()
+        let key = self.next_key.take();
+        // Panic because this indicates a bug in the program rather than an
+        // expected failure.
+        let key = key.expect("serialize_value called before serialize_key");
+        let value = try!(to_value_with_config(&value, self.config.clone()));
+        match self.config.map_style {
+            MapStyle::DottedPair => {
+                self.entries.push(Sexp::Pair(Some(Box::new(key)), Some(Box::new(value))))
+            }
+            MapStyle::NestedList => self.entries.push(Sexp::List(vec![key, value])),
+            MapStyle::Plist => {
+                let name = match key {
+                    Sexp::Number(n) => n.to_string(),
+                    Sexp::Atom(a) => a.into_inner(),
+                    other => other.to_string(),
+                };
+                self.entries.push(Sexp::Atom(Atom::into_keyword(name)));
+                self.entries.push(value);
+            }
+        }
+        Ok(())
     }
 
     fn end(self) -> Result<Sexp, Error> {
-        unimplemented!()
+        Ok(Sexp::List(self.entries))
     }
 }
 
@@ -369,6 +483,7 @@ impl serde::ser::SerializeStruct for SerializeMap {
 pub struct SerializeStructVariant {
     name: String,
     values: Vec<Sexp>,
+    config: SerializeConfig,
 }
 
 impl serde::ser::SerializeStructVariant for SerializeStructVariant {
@@ -380,7 +495,7 @@ impl serde::ser::SerializeStructVariant for SerializeStructVariant {
         T: Serialize,
     {
         self.values.push(
-            Sexp::new_entry(key, to_value(&value).ok().unwrap_or(Sexp::Nil))
+            Sexp::new_entry(key, to_value_with_config(&value, self.config.clone()).ok().unwrap_or(Sexp::Nil))
         );
         Ok(())
     }
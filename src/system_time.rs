@@ -0,0 +1,50 @@
+// Copyright 2017 Zephyr Pellerin
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! (De)serialize a `std::time::SystemTime` as the time elapsed since the Unix
+//! epoch, written like a `Duration` by the `sexpr::duration` module.
+//!
+//! ```rust,ignore
+//! use std::time::SystemTime;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Entry {
+//!     #[serde(with = "sexpr::system_time")]
+//!     modified: SystemTime,
+//! }
+//! ```
+//!
+//! Times before the epoch cannot be represented and fail to serialize.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserializer, Serializer};
+use serde::ser::Error;
+
+use duration;
+
+/// Serializes `time` as `(secs nanos)` since the Unix epoch.
+pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => duration::serialize(&elapsed, serializer),
+        Err(_) => Err(S::Error::custom("SystemTime must be later than UNIX_EPOCH")),
+    }
+}
+
+/// Deserializes a `SystemTime` from `(secs nanos)` or `(secs . nanos)` since
+/// the Unix epoch.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let elapsed = try!(duration::deserialize(deserializer));
+    Ok(UNIX_EPOCH + elapsed)
+}
@@ -11,9 +11,17 @@ extern crate serde_derive;
 
 extern crate serde;
 extern crate serde_bytes;
+extern crate serde_json;
+extern crate serde_transcode;
+#[macro_use]
 extern crate sexpr;
+#[cfg(feature = "sha2")]
+extern crate sha2;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 
 use std::fmt::{Debug};
+use std::io;
 use std::{f32, f64};
 use std::{u32, u64};
 use std::{i8, i16, i32, i64};
@@ -21,7 +29,10 @@ use std::{i8, i16, i32, i64};
 //use serde::de::{self, Deserialize};
 use serde::ser::{self};
 
-use sexpr::{to_string, to_value};
+use sexpr::{from_str, from_str_with_config, to_canonical, to_string, to_string_with_config,
+            to_string_with_labels, to_value, ParseConfig, SerializeConfig};
+use sexpr::{BoolStyle, KeywordStyle, Number, Sexp, UnitVariantStyle, UnknownHashPolicy};
+use sexpr::sexp::Atom;
 
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -111,6 +122,2338 @@ fn test_write_sym() {
 }
 
 
+#[test]
+fn test_atom_extractors() {
+    let sym = Atom::from_str("symbol");
+    assert_eq!(sym.as_symbol(), Some("symbol"));
+    assert_eq!(sym.as_keyword(), None);
+    assert_eq!(sym.as_string(), None);
+
+    let kw = Atom::from_str("#:keyword");
+    assert_eq!(kw.as_symbol(), None);
+    assert_eq!(kw.as_keyword(), Some("keyword"));
+    assert_eq!(kw.as_string(), None);
+
+    let s = Atom::into_string(String::from("string"));
+    assert_eq!(s.as_symbol(), None);
+    assert_eq!(s.as_keyword(), None);
+    assert_eq!(s.as_string(), Some("string"));
+
+    assert_eq!(sym.into_inner(), "symbol");
+    assert_eq!(kw.into_inner(), "keyword");
+    assert_eq!(s.into_inner(), "string");
+}
+
+#[test]
+fn test_atom_discriminate_quotes() {
+    let quoted = Atom::discriminate(String::from("'foo"));
+    assert!(!quoted.is_string());
+    assert_eq!(quoted.as_symbol(), Some("'foo"));
+
+    let single = Atom::discriminate(String::from("'foo'"));
+    assert!(!single.is_string());
+
+    let double = Atom::discriminate(String::from("\"foo\""));
+    assert_eq!(double.as_string(), Some("foo"));
+}
+
+#[test]
+fn test_parse_string_delimiters() {
+    let s: String = from_str("\"plain\"").unwrap();
+    assert_eq!(s, "plain");
+
+    let mut config = ParseConfig::default();
+    config.string_delimiters.push(('«', '»'));
+
+    let s: String = from_str_with_config("«quoted»", config.clone()).unwrap();
+    assert_eq!(s, "quoted");
+
+    let s: String = from_str_with_config("«a \\» b»", config.clone()).unwrap();
+    assert_eq!(s, "a » b");
+
+    let s: String = from_str_with_config("\"still works\"", config).unwrap();
+    assert_eq!(s, "still works");
+}
+
+fn num(n: u64) -> Sexp {
+    Sexp::Number(Number::from(n))
+}
+
+#[test]
+fn test_sexp_retain() {
+    let mut v = Sexp::List((1..7).map(num).collect());
+    v.retain(|x| match *x {
+        Sexp::Number(ref n) => n.as_u64().map_or(true, |n| n % 2 != 0),
+        _ => true,
+    });
+    assert_eq!(v, Sexp::List(vec![num(1), num(3), num(5)]));
+
+    let mut alist = Sexp::List(vec![
+        Sexp::new_entry("name", String::from("\"sexpr\"")),
+        Sexp::new_entry(":internal", num(1)),
+        Sexp::new_entry("version", num(7)),
+    ]);
+    alist.retain(|entry| match *entry {
+        Sexp::Pair(Some(ref key), _) => **key != Sexp::Atom(Atom::from_str(":internal")),
+        _ => true,
+    });
+    assert_eq!(alist, Sexp::List(vec![
+        Sexp::new_entry("name", String::from("\"sexpr\"")),
+        Sexp::new_entry("version", num(7)),
+    ]));
+
+    let mut atom = num(2);
+    atom.retain(|_| false);
+    assert_eq!(atom, num(2));
+}
+
+#[test]
+fn test_sexp_append() {
+    let mut v = Sexp::List(vec![num(1), num(2)]);
+    v.append(Sexp::List(vec![num(3), num(4)])).unwrap();
+    assert_eq!(v, Sexp::List(vec![num(1), num(2), num(3), num(4)]));
+
+    v.append(Sexp::List(vec![])).unwrap();
+    assert_eq!(v, Sexp::List(vec![num(1), num(2), num(3), num(4)]));
+
+    let err = v.append(num(5)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: integer `5`, expected a list");
+    assert_eq!(v, Sexp::List(vec![num(1), num(2), num(3), num(4)]));
+
+    let mut atom = num(1);
+    assert!(atom.append(Sexp::List(vec![num(2)])).is_err());
+    assert_eq!(atom, num(1));
+}
+
+#[test]
+fn test_parse_empty_atom() {
+    let empty = Sexp::Atom(Atom::into_string(String::new()));
+
+    let v: Sexp = from_str("\"\"").unwrap();
+    assert_eq!(v, empty);
+
+    let v: Sexp = from_str("0:").unwrap();
+    assert_eq!(v, empty);
+
+    let v: Sexp = from_str("3:abc").unwrap();
+    assert_eq!(v, Sexp::Atom(Atom::into_string(String::from("abc"))));
+
+    assert!(from_str::<Sexp>("4:abc").is_err());
+
+    let atom = Atom::discriminate(String::from("\"\""));
+    assert_eq!(atom.as_string(), Some(""));
+}
+
+#[test]
+fn test_two_tuples_as_pairs() {
+    let tuple = (1i32, String::from("a"));
+
+    let s = to_string(&tuple).unwrap();
+    assert_eq!(s, "(1 \"a\")");
+    assert_eq!(from_str::<(i32, String)>(&s).unwrap(), tuple);
+
+    let mut config = SerializeConfig::default();
+    config.two_tuples_as_pairs = true;
+    let s = to_string_with_config(&tuple, config).unwrap();
+    assert_eq!(s, "(1 . \"a\")");
+    assert_eq!(from_str::<(i32, String)>(&s).unwrap(), tuple);
+
+    let v: Sexp = from_str(&s).unwrap();
+    let a = Sexp::Atom(Atom::into_string(String::from("a")));
+    assert_eq!(v, Sexp::Pair(Some(Box::new(num(1))), Some(Box::new(a))));
+
+    assert!(from_str::<(i32, String)>("(1 . \"a\" 2)").is_err());
+    assert!(from_str::<Sexp>("( . 1)").is_err());
+}
+
+#[test]
+fn test_sexp_depth() {
+    let a = || Sexp::Atom(Atom::into_symbol(String::from("a")));
+    let cons = |car: Sexp, cdr: Sexp| Sexp::Pair(Some(Box::new(car)), Some(Box::new(cdr)));
+
+    assert_eq!(num(1).depth(), 0);
+    assert_eq!(Sexp::List(vec![]).depth(), 1);
+    assert_eq!(Sexp::List(vec![a(), num(1), num(2)]).depth(), 1);
+    assert_eq!(Sexp::List(vec![Sexp::List(vec![a()])]).depth(), 2);
+    assert_eq!(Sexp::List(vec![a(), Sexp::List(vec![Sexp::List(vec![a()])])]).depth(), 3);
+
+    // (a b . c) is a single level however long the chain of pairs.
+    let chain = cons(a(), cons(a(), cons(a(), num(1))));
+    assert_eq!(chain.depth(), 1);
+    let nested = cons(a(), cons(Sexp::List(vec![a()]), num(1)));
+    assert_eq!(nested.depth(), 2);
+}
+
+#[test]
+fn test_sexp_from_char() {
+    let x: Sexp = 'a'.into();
+    assert_eq!(x, Sexp::Atom(Atom::into_string(String::from("a"))));
+    assert_eq!(to_string(&x).unwrap(), to_string(&'a').unwrap());
+
+    let x: Sexp = Some('a').into();
+    assert_eq!(x, Sexp::Atom(Atom::into_string(String::from("a"))));
+
+    let x: Sexp = None::<char>.into();
+    assert_eq!(x, Sexp::Nil);
+
+    let x: Sexp = std::ffi::OsStr::new("a b").into();
+    assert_eq!(x, Sexp::Atom(Atom::into_string(String::from("a b"))));
+}
+
+#[test]
+fn test_sexp_from_f64() {
+    let x: Sexp = f64::NAN.into();
+    assert_eq!(x, Sexp::Nil);
+
+    let x: Sexp = 13.37f64.into();
+    assert_eq!(x, Sexp::Number(Number::from_f64(13.37).unwrap()));
+
+    let x: Sexp = 2u8.into();
+    assert_eq!(x, num(2));
+}
+
+#[test]
+fn test_deserialize_struct_shapes() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+        label: String,
+    }
+
+    let expected = Point { x: 1, y: -2, label: String::from("origin") };
+
+    let alist = "((x . 1) (y . -2) (label . \"origin\"))";
+    let plist = "(x 1 y -2 label \"origin\")";
+    let nested = "((x 1) (y -2) (label \"origin\"))";
+    let mixed = "((x . 1) (y -2) (label . \"origin\"))";
+
+    for s in &[alist, plist, nested, mixed] {
+        assert_eq!(from_str::<Point>(s).unwrap(), expected);
+
+        let v: Sexp = from_str(s).unwrap();
+        assert_eq!(<Point as serde::Deserialize>::deserialize(&v).unwrap(), expected);
+        assert_eq!(sexpr::from_value::<Point>(v).unwrap(), expected);
+    }
+
+    assert!(from_str::<Point>("(x 1 y -2 label)").is_err());
+}
+
+#[test]
+fn test_skip_none_fields() {
+    #[derive(Serialize)]
+    struct Config {
+        name: String,
+        port: Option<u16>,
+        verbose: bool,
+    }
+
+    let value = Config { name: String::from("srv"), port: None, verbose: true };
+
+    let s = to_string(&value).unwrap();
+    assert_eq!(s, "((\"name\" . \"srv\") (\"port\" . #nil) (\"verbose\" . #t))");
+
+    let mut config = SerializeConfig::default();
+    config.skip_none_fields = true;
+    let s = to_string_with_config(&value, config.clone()).unwrap();
+    assert_eq!(s, "((\"name\" . \"srv\") (\"verbose\" . #t))");
+
+    let value = Config { port: Some(80), ..value };
+    let s = to_string_with_config(&value, config.clone()).unwrap();
+    assert_eq!(s, "((\"name\" . \"srv\") (\"port\" . 80) (\"verbose\" . #t))");
+
+    // A field is looked at once before it is written, and a struct only as
+    // far as its opening, so nesting does not multiply the work.
+    struct Counted<'a>(&'a std::cell::Cell<u32>);
+    impl<'a> serde::Serialize for Counted<'a> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.set(self.0.get() + 1);
+            serializer.serialize_u8(1)
+        }
+    }
+    #[derive(Serialize)]
+    struct Inner<'a> {
+        counted: Counted<'a>,
+        missing: Option<Option<u8>>,
+    }
+    #[derive(Serialize)]
+    struct Outer<'a> {
+        inner: Inner<'a>,
+    }
+
+    let calls = std::cell::Cell::new(0);
+    let value = Outer { inner: Inner { counted: Counted(&calls), missing: Some(None) } };
+    let s = to_string_with_config(&value, config).unwrap();
+    assert_eq!(s, "((\"inner\" . ((\"counted\" . 1))))");
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_duration_with() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Timing {
+        #[serde(with = "sexpr::duration")]
+        timeout: Duration,
+        #[serde(with = "sexpr::system_time")]
+        started: SystemTime,
+    }
+
+    let value = Timing {
+        timeout: Duration::new(90, 500),
+        started: UNIX_EPOCH + Duration::new(1500000000, 0),
+    };
+
+    let s = to_string(&value).unwrap();
+    assert_eq!(s, "((\"timeout\" . (90 500)) (\"started\" . (1500000000 0)))");
+    assert_eq!(from_str::<Timing>(&s).unwrap(), value);
+
+    let mut config = SerializeConfig::default();
+    config.two_tuples_as_pairs = true;
+    let s = to_string_with_config(&value, config).unwrap();
+    assert_eq!(s, "((\"timeout\" . (90 . 500)) (\"started\" . (1500000000 . 0)))");
+    assert_eq!(from_str::<Timing>(&s).unwrap(), value);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_rfc3339_with() {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        #[serde(with = "sexpr::rfc3339")]
+        logged_at: DateTime<Utc>,
+    }
+
+    let value = Entry { logged_at: Utc.with_ymd_and_hms(2017, 6, 1, 12, 30, 0).unwrap() };
+
+    let s = to_string(&value).unwrap();
+    assert_eq!(s, "((\"logged_at\" . \"2017-06-01T12:30:00Z\"))");
+    assert_eq!(from_str::<Entry>(&s).unwrap(), value);
+
+    let offset = from_str::<Entry>("((\"logged_at\" . \"2017-06-01T14:30:00+02:00\"))").unwrap();
+    assert_eq!(offset, value);
+
+    assert!(from_str::<Entry>("((\"logged_at\" . \"yesterday\"))").is_err());
+}
+
+#[test]
+fn test_sexp_car_cdr() {
+    let sym = |s: &str| Sexp::Atom(Atom::into_symbol(String::from(s)));
+
+    let pair: Sexp = from_str("(a . b)").unwrap();
+    assert_eq!(pair.car(), Some(&sym("a")));
+    assert_eq!(pair.cdr().unwrap().into_owned(), sym("b"));
+
+    let empty = Sexp::Pair(None, None);
+    assert_eq!(empty.car(), Some(&Sexp::Nil));
+    assert_eq!(empty.cdr().unwrap().into_owned(), Sexp::Nil);
+
+    let list: Sexp = from_str("(a b c)").unwrap();
+    assert_eq!(list.car(), Some(&sym("a")));
+    assert_eq!(list.cdr().unwrap().into_owned(), Sexp::List(vec![sym("b"), sym("c")]));
+
+    let single: Sexp = from_str("(a)").unwrap();
+    assert_eq!(single.cdr().unwrap().into_owned(), Sexp::List(vec![]));
+
+    assert_eq!(Sexp::Nil.car(), None);
+    assert_eq!(Sexp::Nil.cdr(), None);
+    assert_eq!(Sexp::List(vec![]).car(), None);
+    assert_eq!(Sexp::List(vec![]).cdr(), None);
+}
+
+#[test]
+fn test_sexp_as_pair() {
+    let sym = |s: &str| Sexp::Atom(Atom::into_symbol(String::from(s)));
+
+    let pair: Sexp = from_str("(a . 1)").unwrap();
+    assert_eq!(pair.as_pair(), Some((&sym("a"), &Sexp::from(1))));
+
+    let car_only = Sexp::Pair(Some(Box::new(sym("a"))), None);
+    assert_eq!(car_only.as_pair(), Some((&sym("a"), &Sexp::Nil)));
+
+    let list: Sexp = from_str("(a 1)").unwrap();
+    assert_eq!(list.as_pair(), None);
+}
+
+#[test]
+fn test_to_canonical() {
+    let v: Sexp = from_str("(define (square x) (mul x x))").unwrap();
+    assert_eq!(to_canonical(&v), "(6:define(6:square1:x)(3:mul1:x1:x))");
+
+    let v: Sexp = from_str("(\"hello world\" 42 #t \"\" (a . b))").unwrap();
+    assert_eq!(to_canonical(&v), "(11:hello world2:422:#t0:(1:a.1:b))");
+}
+
+#[cfg(feature = "sha2")]
+#[test]
+fn test_canonical_hash() {
+    use sha2::{Digest, Sha256};
+
+    let v: Sexp = from_str("(define (square x) (mul x x) (\"s\" . 1.5))").unwrap();
+    assert_eq!(v.canonical_hash::<Sha256>(), Sha256::digest(to_canonical(&v).as_bytes()));
+}
+
+#[cfg(feature = "intern")]
+#[test]
+fn test_symbol_interning() {
+    fn symbols<'a>(v: &'a Sexp, out: &mut Vec<&'a str>) {
+        match *v {
+            Sexp::Atom(ref a) => out.extend(a.as_symbol()),
+            Sexp::List(ref elts) => {
+                for elt in elts {
+                    symbols(elt, out);
+                }
+            }
+            Sexp::Pair(ref car, ref cdr) => {
+                for cell in &[car, cdr] {
+                    if let Some(ref v) = **cell {
+                        symbols(v, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let v: Sexp = from_str("(assert (assert x) (check . assert))").unwrap();
+    let mut found = Vec::new();
+    symbols(&v, &mut found);
+    let asserts: Vec<_> = found.iter().filter(|s| **s == "assert").collect();
+    assert_eq!(asserts.len(), 3);
+    for s in &asserts {
+        assert_eq!(s.as_ptr(), asserts[0].as_ptr());
+    }
+
+    // The bytes needed to store every symbol name separately, compared with
+    // the bytes actually stored once names are shared.
+    let doc = format!("({})", vec!["(assert (f x))"; 1000].join(" "));
+    let v: Sexp = from_str(&doc).unwrap();
+    let mut found = Vec::new();
+    symbols(&v, &mut found);
+    let separate: usize = found.iter().map(|s| s.len()).sum();
+    let mut shared: Vec<_> = found.iter().map(|s| (s.as_ptr(), s.len())).collect();
+    shared.sort();
+    shared.dedup();
+    let shared: usize = shared.iter().map(|&(_, len)| len).sum();
+    assert_eq!(separate, 8000);
+    assert_eq!(shared, 8);
+}
+
+#[cfg(feature = "intern-keywords")]
+#[test]
+fn test_keyword_interning() {
+    let doc = format!("({})", vec!["(#:type circle #:radius 2)"; 100].join(" "));
+    let v: Sexp = from_str(&doc).unwrap();
+
+    let mut types = Vec::new();
+    for shape in 0..100 {
+        if let Sexp::Atom(ref a) = v[shape][0] {
+            types.extend(a.as_keyword());
+        }
+    }
+    assert_eq!(types.len(), 100);
+    for ty in &types {
+        assert_eq!(*ty, "type");
+        assert_eq!(ty.as_ptr(), types[0].as_ptr());
+    }
+}
+
+#[test]
+fn test_tuple_struct_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TupleStruct(i32, i32, i32);
+
+    let value = TupleStruct(1, 2, 3);
+
+    let s = to_string(&value).unwrap();
+    assert_eq!(s, "(1 2 3)");
+    assert_eq!(from_str::<TupleStruct>(&s).unwrap(), value);
+
+    let v = to_value(&value).unwrap();
+    assert_eq!(v, Sexp::List(vec![num(1), num(2), num(3)]));
+    assert_eq!(sexpr::from_value::<TupleStruct>(v).unwrap(), value);
+
+    // A tuple and a tuple struct of the same arity read the same text.
+    let tuple: (i32, i32, i32) = from_str(&s).unwrap();
+    assert_eq!(tuple, (1, 2, 3));
+}
+
+#[test]
+fn test_parse_numeric_underscores() {
+    let mut config = ParseConfig::default();
+    config.numeric_underscores = true;
+
+    assert_eq!(from_str_with_config::<u64>("1_000", config.clone()).unwrap(), 1000);
+    assert_eq!(from_str_with_config::<i64>("-1_000_000", config.clone()).unwrap(), -1000000);
+    assert_eq!(from_str_with_config::<f64>("1_0.2_5", config.clone()).unwrap(), 10.25);
+    assert!(from_str_with_config::<u64>("1__0", config.clone()).is_err());
+    assert!(from_str_with_config::<u64>("1_", config.clone()).is_err());
+    assert!(from_str_with_config::<u64>("0_1", config.clone()).is_err());
+    assert!(from_str_with_config::<Sexp>("_1", config.clone()).is_err());
+
+    assert!(from_str::<u64>("1_000").is_err());
+}
+
+#[test]
+fn test_parse_number_suffix() {
+    fn suffix(suffix: &str, n: Number) -> Option<Number> {
+        match suffix {
+            "u" if n.is_u64() => Some(n),
+            "f" => n.as_f64().and_then(Number::from_f64),
+            _ => None,
+        }
+    }
+
+    let mut config = ParseConfig::default();
+    config.number_suffix = Some(suffix);
+
+    assert_eq!(from_str_with_config::<u64>("42u", config.clone()).unwrap(), 42);
+    assert_eq!(from_str_with_config::<f64>("3.0f", config.clone()).unwrap(), 3.0);
+    let v: Sexp = from_str_with_config("(1 2f)", config.clone()).unwrap();
+    assert_eq!(v, Sexp::List(vec![num(1), Sexp::Number(Number::from_f64(2.0).unwrap())]));
+    assert!(from_str_with_config::<i64>("-1u", config.clone()).is_err());
+    assert!(from_str_with_config::<u64>("42x", config).is_err());
+
+    assert!(from_str::<u64>("42u").is_err());
+}
+
+#[test]
+fn test_number_suffix_after_exponent() {
+    fn any_suffix(_: &str, n: Number) -> Option<Number> {
+        Some(n)
+    }
+
+    let mut config = ParseConfig::default();
+    config.number_suffix = Some(any_suffix);
+
+    let v: Sexp = from_str_with_config("(2em 3.0e 2px 1e3 2e+1em 5E-1x)", config).unwrap();
+    let float = |f| Sexp::Number(Number::from_f64(f).unwrap());
+    assert_eq!(v,
+               Sexp::List(vec![num(2), float(3.0), num(2), float(1000.0), float(20.0), float(0.5)]));
+
+    assert!(from_str::<Sexp>("2em").is_err());
+}
+
+#[test]
+fn test_parse_config_eq_with_number_suffix() {
+    fn keep(_: &str, n: Number) -> Option<Number> {
+        Some(n)
+    }
+    fn reject(_: &str, _: Number) -> Option<Number> {
+        None
+    }
+
+    let mut a = ParseConfig::default();
+    let mut b = ParseConfig::default();
+    a.number_suffix = Some(keep);
+    assert!(a != b);
+    b.number_suffix = Some(reject);
+    assert_eq!(a, b);
+    b.square_brackets = true;
+    assert!(a != b);
+}
+
+#[test]
+fn test_sexp_replace_symbols() {
+    use std::collections::HashMap;
+
+    let mut mapping = HashMap::new();
+    mapping.insert(String::from("x"), String::from("y"));
+    mapping.insert(String::from("f"), String::from("g"));
+
+    let mut v: Sexp = from_str("(f (x) (f x) \"x\" (x . \"f\"))").unwrap();
+    v.replace_symbols(&mapping);
+    let expected: Sexp = from_str("(g (y) (g y) \"x\" (y . \"f\"))").unwrap();
+    assert_eq!(v, expected);
+}
+
+#[test]
+fn test_parse_line_comment_prefixes() {
+    let mut config = ParseConfig::default();
+    config.line_comment_prefixes = vec![String::from("//"), String::from("#")];
+
+    let v: Sexp = from_str_with_config(
+        "// leading comment\n(a // trailing comment\n #t # hash comment\n #f)\n// eof",
+        config.clone(),
+    ).unwrap();
+    assert_eq!(v, from_str::<Sexp>("(a #t #f)").unwrap());
+
+    let v: Sexp = from_str("(1 ; semicolon\n 2)").unwrap();
+    assert_eq!(v, from_str::<Sexp>("(1 2)").unwrap());
+
+    config.line_comment_prefixes.clear();
+    assert!(from_str_with_config::<Sexp>("(1 ; semicolon\n 2)", config).is_err());
+}
+
+#[test]
+fn test_deserialize_tagged_variant() {
+    let frog = Animal::Frog(String::from("x"), vec![1, 2]);
+    assert_eq!(from_str::<Animal>("((variant \"Frog\") \"x\" (1 2))").unwrap(), frog);
+
+    let v: Sexp = from_str("((variant Frog) \"x\" (1 2))").unwrap();
+    assert_eq!(serde::Deserialize::deserialize(&v).ok(), Some(frog.clone()));
+    assert_eq!(sexpr::from_value::<Animal>(v).unwrap(), frog);
+
+    assert_eq!(from_str::<Animal>("Dog").unwrap(), Animal::Dog);
+    assert_eq!(
+        from_str::<Animal>("((variant Cat) (age . 3) (name . \"Kate\"))").unwrap(),
+        Animal::Cat { age: 3, name: String::from("Kate") }
+    );
+    assert!(from_str::<Animal>("((variant Dog) 1)").is_err());
+    assert!(from_str::<Animal>("(Frog \"x\" (1 2))").is_err());
+}
+
+#[test]
+fn test_tuple_variant_round_trip() {
+    let frog = Animal::Frog("x".into(), vec![1, 2]);
+
+    let s = to_string(&frog).unwrap();
+    assert_eq!(s, "((variant Frog) \"x\" (1 2))");
+    assert_eq!(from_str::<Animal>(&s).unwrap(), frog);
+
+    let v = to_value(&frog).unwrap();
+    assert_eq!(v[0], from_str::<Sexp>("(variant Frog)").unwrap());
+    assert_eq!(sexpr::from_value::<Animal>(v).unwrap(), frog);
+}
+
+#[test]
+fn test_deserialize_keyword_plist() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Slot {
+        init_value: Option<u64>,
+        settable: bool,
+    }
+
+    let slot: Slot = from_str("(#:init-value #nil #:settable #t)").unwrap();
+    assert_eq!(slot, Slot { init_value: None, settable: true });
+
+    let map: BTreeMap<String, Sexp> = from_str("(#:init-value 1 #:settable #f)").unwrap();
+    assert_eq!(map["init-value"], Sexp::from(1));
+    assert_eq!(map["settable"], Sexp::Boolean(false));
+
+    let v: Sexp = from_str("(width #:init-value #nil)").unwrap();
+    match v[1] {
+        Sexp::Atom(ref a) => assert_eq!(a.as_keyword(), Some("init-value")),
+        ref other => panic!("expected a keyword, got {:?}", other),
+    }
+    assert_eq!(v[2], Sexp::Nil);
+}
+
+#[test]
+fn test_unit_struct_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Unit;
+
+    assert_eq!(to_string(&Unit).unwrap(), "()");
+    assert_eq!(to_value(&Unit).unwrap(), Sexp::List(vec![]));
+    assert_eq!(from_str::<Unit>("()").unwrap(), Unit);
+    assert_eq!(sexpr::from_value::<Unit>(to_value(&Unit).unwrap()).unwrap(), Unit);
+
+    let mut config = SerializeConfig::default();
+    config.unit_struct_as_name = true;
+    assert_eq!(to_string_with_config(&Unit, config.clone()).unwrap(), "Unit");
+    let v = sexpr::to_value_with_config(&Unit, config).unwrap();
+    assert_eq!(v.to_string(), "Unit");
+    assert_eq!(from_str::<Unit>("Unit").unwrap(), Unit);
+    assert_eq!(sexpr::from_value::<Unit>(v).unwrap(), Unit);
+
+    assert_eq!(from_str::<Unit>("#nil").unwrap(), Unit);
+    assert!(from_str::<Unit>("Other").is_err());
+}
+
+#[test]
+fn test_parse_bool_symbols() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Settings {
+        enabled: bool,
+        verbose: bool,
+        mode: String,
+    }
+
+    let mut config = ParseConfig::default();
+    config.true_symbols = vec![String::from("yes"), String::from("true")];
+    config.false_symbols = vec![String::from("no"), String::from("false")];
+
+    let settings: Settings = from_str_with_config(
+        "((enabled . yes) (verbose . no) (mode . yes))",
+        config.clone(),
+    ).unwrap();
+    assert_eq!(settings, Settings { enabled: true, verbose: false, mode: String::from("yes") });
+
+    let bools: Vec<bool> = from_str_with_config("(true false #t yes no)", config.clone()).unwrap();
+    assert_eq!(bools, vec![true, false, true, true, false]);
+    assert_eq!(from_str_with_config::<bool>("false", config.clone()).unwrap(), false);
+    assert!(from_str_with_config::<bool>("maybe", config).is_err());
+
+    assert!(from_str::<Settings>("((enabled . yes) (verbose . no) (mode . yes))").is_err());
+}
+
+#[test]
+fn test_sexp_dedup_keys() {
+    use sexpr::error::ErrorCode;
+    use sexpr::sexp::DedupPolicy;
+
+    let alist: Sexp = from_str("((a . 1) (b . 2) (a . 3) c)").unwrap();
+
+    let mut v = alist.clone();
+    v.dedup_keys(DedupPolicy::FirstWins).unwrap();
+    assert_eq!(v, from_str::<Sexp>("((a . 1) (b . 2) c)").unwrap());
+
+    let mut v = alist.clone();
+    v.dedup_keys(DedupPolicy::LastWins).unwrap();
+    assert_eq!(v, from_str::<Sexp>("((b . 2) (a . 3) c)").unwrap());
+
+    let mut v = alist.clone();
+    let err = v.dedup_keys(DedupPolicy::Error).unwrap_err();
+    match *err.code() {
+        ErrorCode::DuplicateKey(ref key) => assert_eq!(key, "a"),
+        ref code => panic!("unexpected error code {:?}", code),
+    }
+    assert!(err.is_data());
+    assert_eq!(v, alist);
+}
+
+#[test]
+fn test_parse_square_brackets() {
+    let mut config = ParseConfig::default();
+    config.square_brackets = true;
+
+    let doc = "(defn f [a b] (g [a (b)] []))";
+    let v: Sexp = from_str_with_config(doc, config.clone()).unwrap();
+    assert_eq!(v[2], Sexp::Vector(vec![from_str("a").unwrap(), from_str("b").unwrap()]));
+    assert_eq!(v[3][0], from_str::<Sexp>("g").unwrap());
+    assert_eq!(to_string(&v).unwrap(), doc);
+    assert_eq!(to_value(&v).unwrap(), v);
+    assert_eq!(from_str_with_config::<Sexp>(&to_string(&v).unwrap(), config.clone()).unwrap(), v);
+
+    let nested: Vec<Vec<u32>> = from_str_with_config("([1 2] [3])", config).unwrap();
+    assert_eq!(nested, vec![vec![1, 2], vec![3]]);
+
+    assert!(from_str::<Sexp>("[a b]").is_err());
+}
+
+#[test]
+fn test_error_position() {
+    let err = from_str::<Sexp>("(1\n2 @)").unwrap_err();
+    assert_eq!((err.line(), err.column()), (2, 3));
+
+    let err = sexpr::from_reader::<_, Sexp>("(1\n2 @)".as_bytes()).unwrap_err();
+    assert_eq!((err.line(), err.column()), (2, 3));
+
+    // Columns count characters rather than bytes.
+    let err = from_str::<Sexp>("(1\n\"é\" @)").unwrap_err();
+    assert_eq!((err.line(), err.column()), (2, 5));
+
+    let err = sexpr::from_reader::<_, Sexp>("(1\n\"é\" @)".as_bytes()).unwrap_err();
+    assert_eq!((err.line(), err.column()), (2, 5));
+}
+
+#[test]
+fn test_sexp_to_pretty_alist() {
+    let v: Sexp = from_str(
+        r#"((name . "sexpr") (version . 1) (build . ((opt-level . 3) (lto . #t))) (features . #nil))"#,
+    ).unwrap();
+    let expected = r#"((name     . "sexpr")
+ (version  . 1)
+ (build    . ((opt-level . 3)
+              (lto       . #t)))
+ (features . #nil))"#;
+    assert_eq!(v.to_pretty_alist(), expected);
+
+    assert_eq!(from_str::<Sexp>("42").unwrap().to_pretty_alist(), "42");
+}
+
+#[test]
+fn test_renamed_fields_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Settings {
+        max_depth: u32,
+        #[serde(rename = "Name")]
+        display_name: String,
+        use_tabs: bool,
+    }
+
+    let settings = Settings {
+        max_depth: 3,
+        display_name: "zv".to_string(),
+        use_tabs: false,
+    };
+    let s = to_string(&settings).unwrap();
+    assert_eq!(s, r#"(("max-depth" . 3) ("Name" . "zv") ("use-tabs" . #f))"#);
+    assert_eq!(from_str::<Settings>(&s).unwrap(), settings);
+    assert_eq!(sexpr::from_value::<Settings>(to_value(&settings).unwrap()).unwrap(), settings);
+
+    let reordered: Settings = from_str(r#"((use-tabs . #f) (Name . "zv") (max-depth . 3))"#).unwrap();
+    assert_eq!(reordered, settings);
+    assert!(from_str::<Settings>(r#"((max_depth . 3) (Name . "zv") (use-tabs . #f))"#).is_err());
+}
+
+#[test]
+fn test_map_style_round_trip() {
+    use std::collections::BTreeMap;
+    use sexpr::MapStyle;
+
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), 1);
+    map.insert("b".to_string(), 2);
+
+    let cases = [
+        (MapStyle::DottedPair, r#"(("a" . 1) ("b" . 2))"#),
+        (MapStyle::NestedList, r#"(("a" 1) ("b" 2))"#),
+    ];
+    for &(style, expected) in &cases {
+        let mut config = SerializeConfig::default();
+        config.map_style = style;
+
+        let s = to_string_with_config(&map, config.clone()).unwrap();
+        assert_eq!(s, expected);
+        assert_eq!(from_str::<BTreeMap<String, i32>>(&s).unwrap(), map);
+
+        let v = sexpr::to_value_with_config(&map, config).unwrap();
+        assert_eq!(sexpr::from_value::<BTreeMap<String, i32>>(v).unwrap(), map);
+    }
+}
+
+#[test]
+fn test_write_quoted_symbol() {
+    let v = Sexp::List(vec![
+        Sexp::Atom(Atom::into_symbol("weird sym".to_string())),
+        Sexp::Atom(Atom::into_symbol("a|b)".to_string())),
+        Sexp::Atom(Atom::into_symbol("plain".to_string())),
+    ]);
+    let s = to_string(&v).unwrap();
+    assert_eq!(s, r#"(|weird sym| |a\|b)| plain)"#);
+    assert_eq!(from_str::<Sexp>(&s).unwrap(), v);
+    assert_eq!(
+        from_str::<Sexp>("|weird sym|").unwrap(),
+        Sexp::Atom(Atom::into_symbol("weird sym".to_string()))
+    );
+}
+
+#[test]
+fn test_sexp_count_nodes() {
+    assert_eq!(from_str::<Sexp>("1").unwrap().count_nodes(), 1);
+    assert_eq!(from_str::<Sexp>("(a b)").unwrap().count_nodes(), 3);
+    assert_eq!(from_str::<Sexp>("()").unwrap().count_nodes(), 1);
+
+    let nested: Sexp = from_str(r#"((name . "x") (tags "a" ("b" #t)) #nil)"#).unwrap();
+    assert_eq!(nested.count_nodes(), 11);
+    assert_eq!(Sexp::Pair(None, None).count_nodes(), 3);
+}
+
+#[test]
+fn test_from_slice_borrowed() {
+    use std::borrow::Cow;
+    use sexpr::BorrowedSexp;
+
+    let mut input = String::from("(");
+    for i in 0..1000 {
+        input.push_str(&format!("(entry-{} . \"value {}\") #:key-{} ", i, i, i));
+    }
+    input.push_str("\"esc\\naped\")");
+    let bytes = input.as_bytes();
+
+    fn check_borrowed(s: &Cow<str>, bytes: &[u8]) {
+        match *s {
+            Cow::Borrowed(s) => {
+                let start = bytes.as_ptr() as usize;
+                let p = s.as_ptr() as usize;
+                assert!(p >= start && p + s.len() <= start + bytes.len());
+            }
+            Cow::Owned(ref s) => panic!("{:?} was copied", s),
+        }
+    }
+
+    let v = sexpr::from_slice_borrowed(bytes).unwrap();
+    let elts = match v {
+        BorrowedSexp::List(ref elts) => elts,
+        ref other => panic!("expected a list, got {:?}", other),
+    };
+    assert_eq!(elts.len(), 2001);
+    for elt in &elts[..2000] {
+        match *elt {
+            BorrowedSexp::Pair(Some(ref car), Some(ref cdr)) => match (&**car, &**cdr) {
+                (&BorrowedSexp::Symbol(ref k), &BorrowedSexp::String(ref v)) => {
+                    check_borrowed(k, bytes);
+                    check_borrowed(v, bytes);
+                }
+                other => panic!("unexpected entry {:?}", other),
+            },
+            BorrowedSexp::Keyword(ref k) => check_borrowed(k, bytes),
+            ref other => panic!("unexpected element {:?}", other),
+        }
+    }
+    assert_eq!(elts[2000], BorrowedSexp::String(Cow::Owned("esc\naped".to_string())));
+
+    assert_eq!(v.into_owned(), from_str::<Sexp>(&input).unwrap());
+}
+
+#[test]
+fn test_elisp_serializer() {
+    use serde::Serialize;
+    use sexpr::MapStyle;
+
+    fn elisp<T: Serialize>(value: &T) -> String {
+        let mut ser = sexpr::Serializer::elisp(Vec::new());
+        value.serialize(&mut ser).unwrap();
+        String::from_utf8(ser.into_inner()).unwrap()
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Face {
+        family: String,
+        height: u32,
+        bold: bool,
+        slant: Option<String>,
+    }
+
+    let face = Face { family: "Iosevka".to_string(), height: 120, bold: true, slant: None };
+
+    assert_eq!(elisp(&true), "t");
+    assert_eq!(elisp(&false), "nil");
+    assert_eq!(elisp(&None::<u32>), "nil");
+    assert_eq!(elisp(&face), r#"(:family "Iosevka" :height 120 :bold t :slant nil)"#);
+
+    // The same layout with this crate's own spelling reads back.
+    let mut config = SerializeConfig::default();
+    config.map_style = MapStyle::Plist;
+    let s = to_string_with_config(&face, config.clone()).unwrap();
+    assert_eq!(s, r#"(#:family "Iosevka" #:height 120 #:bold #t #:slant #nil)"#);
+    assert_eq!(from_str::<Face>(&s).unwrap(), face);
+    let v = sexpr::to_value_with_config(&face, config).unwrap();
+    assert_eq!(sexpr::from_value::<Face>(v).unwrap(), face);
+}
+
+#[test]
+fn test_sexp_flatten() {
+    let v: Sexp = from_str("((1 2) (3 4) 5)").unwrap();
+    assert_eq!(v.flatten(), from_str::<Sexp>("(1 2 3 4 5)").unwrap());
+
+    let nested: Sexp = from_str("((a (b)) () c)").unwrap();
+    assert_eq!(nested.flatten(), from_str::<Sexp>("(a (b) c)").unwrap());
+
+    let atom: Sexp = from_str("a").unwrap();
+    assert_eq!(atom.flatten(), atom);
+}
+
+#[test]
+fn test_parse_dots() {
+    let sym = |s: &str| Sexp::Atom(Atom::into_symbol(String::from(s)));
+
+    let pair: Sexp = from_str("(a . b)").unwrap();
+    assert_eq!(pair, Sexp::Pair(Some(Box::new(sym("a"))), Some(Box::new(sym("b")))));
+
+    let dotted: Sexp = from_str("(a.b)").unwrap();
+    assert_eq!(dotted, Sexp::List(vec![sym("a.b")]));
+
+    let float: Sexp = from_str("(.5)").unwrap();
+    assert_eq!(float, Sexp::List(vec![Sexp::from(0.5)]));
+
+    let mixed: Sexp = from_str("(my.module .b -.25)").unwrap();
+    assert_eq!(mixed, Sexp::List(vec![sym("my.module"), sym(".b"), Sexp::from(-0.25)]));
+}
+
+#[test]
+fn test_wrap_width() {
+    let mut config = SerializeConfig::default();
+    config.wrap_width = Some(20);
+
+    let numbers: Vec<u32> = (1..16).collect();
+    let s = to_string_with_config(&numbers, config.clone()).unwrap();
+    assert_eq!(s, "(1 2 3 4 5 6 7 8 9\n 10 11 12 13 14 15)");
+    assert!(s.lines().all(|line| line.len() <= 20));
+    assert_eq!(from_str::<Vec<u32>>(&s).unwrap(), numbers);
+
+    let nested = vec![vec!["a b c d e".to_string(), "f".to_string()], vec!["g".to_string()]];
+    let s = to_string_with_config(&nested, config).unwrap();
+    assert_eq!(s, "((\"a b c d e\" \"f\")\n (\"g\"))");
+}
+
+#[test]
+fn test_parse_hash_vectors() {
+    let mut config = ParseConfig::default();
+    config.hash_vectors = true;
+
+    let vector: Sexp = from_str_with_config("#(1 2 3)", config.clone()).unwrap();
+    let list: Sexp = from_str_with_config("(1 2 3)", config.clone()).unwrap();
+    assert_eq!(vector, Sexp::Vector(vec![Sexp::from(1), Sexp::from(2), Sexp::from(3)]));
+    assert!(vector != list);
+
+    let nested: Sexp = from_str_with_config("(a #(b #()) #t)", config.clone()).unwrap();
+    assert_eq!(nested[1], Sexp::Vector(vec![from_str("b").unwrap(), Sexp::Vector(vec![])]));
+
+    let v: Vec<u32> = from_str_with_config("#(4 5)", config.clone()).unwrap();
+    assert_eq!(v, vec![4, 5]);
+
+    assert!(from_str::<Sexp>("#(1 2 3)").is_err());
+
+    // Vectors are written with brackets, which this dialect cannot read,
+    // unless the serializer is asked for the `#(` form.
+    assert_eq!(to_string(&nested).unwrap(), "(a [b []] #t)");
+    assert!(from_str_with_config::<Sexp>("(a [b []] #t)", config.clone()).is_err());
+    let mut hashes = SerializeConfig::default();
+    hashes.hash_vectors = true;
+    let s = to_string_with_config(&nested, hashes).unwrap();
+    assert_eq!(s, "(a #(b #()) #t)");
+    assert_eq!(from_str_with_config::<Sexp>(&s, config).unwrap(), nested);
+}
+
+#[test]
+fn test_datum_labels_round_trip() {
+    let value: Sexp = from_str("((a b) x (a b) ((a b) . y) ((a b) . y))").unwrap();
+    let labeled = to_string_with_labels(&value);
+    assert_eq!(labeled, "(#1=(a b) x #1# #2=(#1# . y) #2#)");
+
+    let mut config = ParseConfig::default();
+    config.datum_labels = true;
+    let parsed: Sexp = from_str_with_config(&labeled, config.clone()).unwrap();
+    assert_eq!(parsed, value);
+
+    let unshared: Sexp = from_str("(1 (2) 3)").unwrap();
+    assert_eq!(to_string_with_labels(&unshared), "(1 (2) 3)");
+
+    for text in &[r#"((a b) ("a" "b"))"#, "((1 2) (1 2.0))", r#"((#:a) ("a"))"#] {
+        let distinct: Sexp = from_str(text).unwrap();
+        let labeled = to_string_with_labels(&distinct);
+        assert_eq!(labeled, *text);
+        assert_eq!(from_str_with_config::<Sexp>(&labeled, config.clone()).unwrap(), distinct);
+    }
+
+    let mut brackets = config.clone();
+    brackets.square_brackets = true;
+    let vectors: Sexp = from_str_with_config("([1 2] ([1 2]))", brackets.clone()).unwrap();
+    let labeled = to_string_with_labels(&vectors);
+    assert_eq!(labeled, "(#1=[1 2] (#1#))");
+    assert_eq!(from_str_with_config::<Sexp>(&labeled, brackets).unwrap(), vectors);
+
+    assert!(from_str_with_config::<Sexp>("(#1# #1=(a))", config).is_err());
+    assert!(from_str::<Sexp>("(#1=(a) #1#)").is_err());
+}
+
+#[test]
+fn test_sexp_is_alist() {
+    assert!(from_str::<Sexp>("((a . 1)(b . 2))").unwrap().is_alist());
+    assert!(!from_str::<Sexp>("(a b c)").unwrap().is_alist());
+    assert!(!from_str::<Sexp>("()").unwrap().is_alist());
+    assert!(!from_str::<Sexp>("((a . 1) b)").unwrap().is_alist());
+    assert!(!from_str::<Sexp>("(a . 1)").unwrap().is_alist());
+}
+
+#[test]
+fn test_sexp_as_symbol_list() {
+    let v: Sexp = from_str("(a b c)").unwrap();
+    assert_eq!(v.as_symbol_list(), Some(vec!["a", "b", "c"]));
+
+    let v: Sexp = from_str("(a 1 b)").unwrap();
+    assert_eq!(v.as_symbol_list(), None);
+
+    let v: Sexp = from_str("(a \"b\")").unwrap();
+    assert_eq!(v.as_symbol_list(), None);
+
+    assert_eq!(Sexp::List(vec![]).as_symbol_list(), Some(vec![]));
+    assert_eq!(Sexp::from("a").as_symbol_list(), None);
+}
+
+#[test]
+fn test_sexp_iter_mut() {
+    let mut v: Sexp = from_str("(1 2 3)").unwrap();
+    for elt in v.iter_mut() {
+        if let Sexp::Number(ref mut n) = *elt {
+            *n = Number::from(n.as_i64().unwrap() * 2);
+        }
+    }
+    assert_eq!(v, from_str::<Sexp>("(2 4 6)").unwrap());
+
+    let mut pair: Sexp = from_str("(1 . 2)").unwrap();
+    assert_eq!(pair.iter_mut().count(), 2);
+    for elt in pair.iter_mut() {
+        *elt = Sexp::from(0);
+    }
+    assert_eq!(pair, from_str::<Sexp>("(0 . 0)").unwrap());
+
+    assert_eq!(Sexp::from(1).iter_mut().count(), 0);
+}
+
+#[test]
+fn test_sexp_get_path() {
+    use sexpr::sexp::PathSegment::{Index, Key};
+
+    let v: Sexp = from_str(r#"(
+                                (name . "John Doe")
+                                (age . 43)
+                                (phones . (
+                                  "+44 1234567"
+                                  "+44 2345678"))
+                              )"#).unwrap();
+
+    let phone = Sexp::Atom(Atom::into_string("+44 1234567".to_string()));
+    assert_eq!(v.get_path(&[Key("phones"), Index(0)]), Some(&phone));
+    assert_eq!(v.get_path(&[Key("age")]), Some(&Sexp::from(43)));
+    assert_eq!(v.get_path(&[]), Some(&v));
+    assert_eq!(v.get_path(&[Key("phones"), Index(2)]), None);
+    assert_eq!(v.get_path(&[Key("email")]), None);
+    assert_eq!(v.get_path(&[Key("age"), Index(0)]), None);
+}
+
+#[test]
+fn test_from_slice_invalid_utf8() {
+    // `\xC3` opens a two byte sequence but `(` is not a continuation byte.
+    let err = sexpr::from_slice::<Sexp>(b"(a \"\xC3(\")").unwrap_err();
+    assert_eq!(err.to_string(), "invalid UTF-8 at byte offset 4 at line 1 column 5");
+
+    let err = sexpr::from_slice::<Sexp>(b"(a\nb \x80)").unwrap_err();
+    assert_eq!(err.to_string(), "invalid UTF-8 at byte offset 5 at line 2 column 3");
+
+    assert!(sexpr::from_slice::<Sexp>("(\"\u{e9}t\u{e9}\")".as_bytes()).is_ok());
+}
+
+#[test]
+fn test_option_none_distinct_from_empty_list() {
+    let none: Option<Vec<i32>> = None;
+    let empty: Option<Vec<i32>> = Some(vec![]);
+
+    assert_eq!(to_string(&none).unwrap(), "#nil");
+    assert_eq!(to_string(&empty).unwrap(), "()");
+
+    assert_eq!(from_str::<Option<Vec<i32>>>("#nil").unwrap(), none);
+    assert_eq!(from_str::<Option<Vec<i32>>>("()").unwrap(), empty);
+    assert_eq!(from_str::<Option<Vec<i32>>>("(1 2)").unwrap(), Some(vec![1, 2]));
+
+    assert_eq!(sexpr::from_value::<Option<Vec<i32>>>(to_value(&none).unwrap()).unwrap(), none);
+    assert_eq!(sexpr::from_value::<Option<Vec<i32>>>(to_value(&empty).unwrap()).unwrap(), empty);
+}
+
+#[test]
+fn test_parse_commas_as_whitespace() {
+    let mut config = ParseConfig::default();
+    config.commas_are_whitespace = true;
+
+    let v: Sexp = from_str_with_config("(a, b, c)", config.clone()).unwrap();
+    assert_eq!(v, from_str::<Sexp>("(a b c)").unwrap());
+
+    let v: Sexp = from_str_with_config("(a,b ,c,)", config.clone()).unwrap();
+    assert_eq!(v, from_str::<Sexp>("(a b c)").unwrap());
+
+    let v: Vec<i32> = from_str_with_config("(1, 2, 3)", config).unwrap();
+    assert_eq!(v, vec![1, 2, 3]);
+
+    let v: Sexp = from_str("(a, b,c)").unwrap();
+    assert_eq!(v, Sexp::List(vec![Sexp::from("a,"), Sexp::from("b,c")]));
+}
+
+#[test]
+fn test_sexp_map_values() {
+    let mut v: Sexp = from_str(r#"((name . "sexpr") (port . 80) (host . "localhost"))"#).unwrap();
+    v.map_values(|_, value| {
+        let upper = match *value {
+            Sexp::Atom(ref atom) if atom.is_string() => atom.as_str().to_uppercase(),
+            _ => return,
+        };
+        *value = Sexp::Atom(Atom::into_string(upper));
+    });
+    let expected: Sexp = from_str(r#"((name . "SEXPR") (port . 80) (host . "LOCALHOST"))"#).unwrap();
+    assert_eq!(v, expected);
+
+    let mut keys = Vec::new();
+    v.map_values(|key, _| keys.push(key.to_string()));
+    assert_eq!(keys, vec!["name", "port", "host"]);
+}
+
+#[test]
+fn test_alist_macro() {
+    let value = alist! {
+        "name" => "John",
+        "age" => 43,
+        "phones" => vec!["+44 1"],
+    };
+    let expected = Sexp::List(vec![
+        Sexp::new_entry("name", "John"),
+        Sexp::new_entry("age", 43),
+        Sexp::new_entry("phones", Sexp::List(vec![Sexp::from("+44 1")])),
+    ]);
+    assert_eq!(value, expected);
+
+    assert_eq!(alist! { "a" => 1 }, Sexp::List(vec![Sexp::new_entry("a", 1)]));
+    assert_eq!(alist! {}, Sexp::List(vec![]));
+}
+
+#[test]
+fn test_dotted_tail_as_last_element() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Args(i32, i32, Sexp);
+
+    assert_eq!(from_str::<Args>("(1 2 . 3)").unwrap(), Args(1, 2, Sexp::from(3)));
+    let rest: Sexp = from_str("(3 4)").unwrap();
+    assert_eq!(from_str::<Args>("(1 2 . (3 4))").unwrap(), Args(1, 2, rest.clone()));
+
+    let v: Sexp = from_str("(1 2 . 3)").unwrap();
+    assert_eq!(sexpr::from_value::<Args>(v.clone()).unwrap(), Args(1, 2, Sexp::from(3)));
+    assert_eq!(sexpr::from_value::<Vec<i32>>(v).unwrap(), vec![1, 2, 3]);
+    let v: Sexp = from_str("(1 2 . (3 4))").unwrap();
+    assert_eq!(sexpr::from_value::<Args>(v).unwrap(), Args(1, 2, rest));
+
+    assert_eq!(from_str::<Vec<i32>>("(1 2 . 3)").unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_parse_empty_list_is_nil() {
+    let v: Sexp = sexpr::from_str("()").unwrap();
+    assert_eq!(v, Sexp::List(vec![]));
+
+    let mut config = ParseConfig::default();
+    config.empty_list_is_nil = true;
+    let v: Sexp = sexpr::from_str_with_config("()", config.clone()).unwrap();
+    assert_eq!(v, Sexp::Nil);
+
+    let v: Sexp = sexpr::from_str_with_config("(a ())", config).unwrap();
+    assert_eq!(v, Sexp::List(vec![Sexp::from("a"), Sexp::Nil]));
+}
+
+#[test]
+fn test_parse_arbitrary_bytes_never_panics() {
+    // A fixed xorshift sequence, so failures are reproducible.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let alphabet = b"()[]#.\"\\'`,;:|-+eE0123456789abxu_= \n";
+
+    let mut config = ParseConfig::default();
+    config.square_brackets = true;
+    config.hash_vectors = true;
+    config.datum_labels = true;
+    config.numeric_underscores = true;
+
+    for _ in 0..20000 {
+        let len = (next() % 24) as usize;
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| {
+                let r = next();
+                if r % 10 == 0 {
+                    (r >> 8) as u8
+                } else {
+                    alphabet[(r >> 8) as usize % alphabet.len()]
+                }
+            })
+            .collect();
+
+        let _ = sexpr::from_slice::<Sexp>(&bytes);
+        let _ = sexpr::from_slice::<Vec<(String, i64)>>(&bytes);
+        if let Ok(s) = std::str::from_utf8(&bytes) {
+            let _ = sexpr::from_str_with_config::<Sexp>(s, config.clone());
+        }
+    }
+
+    let nested = "#0=".repeat(100000);
+    assert!(sexpr::from_str_with_config::<Sexp>(&nested, config).is_err());
+}
+
+#[test]
+fn test_nonzero_round_trip() {
+    use std::num::{NonZeroI64, NonZeroU32, NonZeroU64};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Limits {
+        workers: NonZeroU32,
+        offset: NonZeroI64,
+    }
+
+    let limits = Limits {
+        workers: NonZeroU32::new(8).unwrap(),
+        offset: NonZeroI64::new(-3).unwrap(),
+    };
+    let s = sexpr::to_string(&limits).unwrap();
+    assert_eq!(sexpr::from_str::<Limits>(&s).unwrap(), limits);
+
+    assert!(sexpr::from_str::<NonZeroU32>("0").is_err());
+    assert_eq!(Number::from(NonZeroU64::new(7).unwrap()), Number::from(7u64));
+    assert_eq!(Number::from(NonZeroI64::new(-7).unwrap()), Number::from(-7i64));
+}
+
+#[test]
+fn test_sexp_coerce_number() {
+    let v: Sexp = from_str(r#"(8080 "2.5" "localhost")"#).unwrap();
+    assert_eq!(v[0].coerce_number(), Some(Number::from(8080)));
+    assert_eq!(v[1].coerce_number(), Number::from_f64(2.5));
+    assert_eq!(v[2].coerce_number(), None);
+    assert_eq!(Sexp::from("-12").coerce_number(), Some(Number::from(-12)));
+}
+
+#[test]
+fn test_deserialize_number_as_string() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Payment {
+        amount: String,
+    }
+
+    let s: String = from_str("3.14159265358979323846264338327950288").unwrap();
+    assert_eq!(s, "3.14159265358979323846264338327950288");
+
+    let v: Vec<String> = from_str("(12345678901234567890123 -0.10000000000000000001)").unwrap();
+    assert_eq!(v, vec!["12345678901234567890123", "-0.10000000000000000001"]);
+
+    let p: Payment = from_str("((amount . 19.99))").unwrap();
+    assert_eq!(p.amount, "19.99");
+
+    let s: String = from_str("3:abc").unwrap();
+    assert_eq!(s, "abc");
+    assert!(from_str::<String>("1.2.3").is_err());
+    assert!(from_str::<String>("5.").is_err());
+
+    let mut config = ParseConfig::default();
+    config.numeric_underscores = true;
+    let s: String = from_str_with_config("1_000.000_1", config).unwrap();
+    assert_eq!(s, "1_000.000_1");
+}
+
+#[test]
+fn test_parse_raw_strings() {
+    let mut config = ParseConfig::default();
+    config.raw_strings = true;
+
+    let text = r#"(doc """He said "hi".
+  C:\path\n""")"#;
+    let v: Sexp = from_str_with_config(text, config.clone()).unwrap();
+    assert_eq!(v[1], Sexp::Atom(Atom::into_string("He said \"hi\".\n  C:\\path\\n".to_string())));
+
+    let s: String = from_str_with_config(r#""""""""#, config).unwrap();
+    assert_eq!(s, "");
+
+    assert!(from_str::<Sexp>(r#""""a""""#).is_err());
+}
+
+#[test]
+fn test_reformat() {
+    let messy = r#"; Package metadata
+(package   (name "sexpr")   ; crate name
+      (version
+   "0.7.0")
+ (keywords (sexp s-exp
+            sexpr))
+  ; where to find it
+ (repository "https://github.com/zv/sexpr"))
+(a
+   b)"#;
+    let expected = r#"; Package metadata
+(package
+  (name "sexpr") ; crate name
+  (version "0.7.0")
+  (keywords (sexp s-exp sexpr))
+  ; where to find it
+  (repository "https://github.com/zv/sexpr"))
+(a b)
+"#;
+    let config = sexpr::PrettyConfig::default();
+    assert_eq!(sexpr::reformat(messy, &config).unwrap(), expected);
+    assert_eq!(sexpr::reformat(expected, &config).unwrap(), expected);
+
+    let mut narrow = config.clone();
+    narrow.width = 10;
+    assert_eq!(sexpr::reformat("(define (f x) x)", &narrow).unwrap(),
+               "(define\n  (f x)\n  x)\n");
+
+    let mut verbatim = config.clone();
+    verbatim.width = 12;
+    let input = "(greeting 11:hello world (x))";
+    let tidy = sexpr::reformat(input, &verbatim).unwrap();
+    assert_eq!(tidy, "(greeting\n  11:hello world\n  (x))\n");
+    assert_eq!(from_str::<Sexp>(&tidy).unwrap(), from_str::<Sexp>(input).unwrap());
+
+    assert!(sexpr::reformat("(a b", &config).is_err());
+
+    // The dialect decides which comments and brackets there are.
+    assert!(sexpr::reformat("(a #| c |# b)", &config).is_err());
+    assert!(sexpr::reformat("(a #(1 2) b)", &config).is_err());
+    let mut scheme = config.clone();
+    scheme.dialect.block_comments = true;
+    scheme.dialect.datum_comments = true;
+    scheme.dialect.hash_vectors = true;
+    assert_eq!(sexpr::reformat("(a  #| c |#\n b)", &scheme).unwrap(), "(a #| c |# b)\n");
+    assert_eq!(sexpr::reformat("(a #(1\n 2)   b)", &scheme).unwrap(), "(a #(1 2) b)\n");
+    assert_eq!(sexpr::reformat("(a #;  (x ; gone\n y) b)", &scheme).unwrap(),
+               "(a #;  (x ; gone\n y)\n  b)\n");
+    assert_eq!(sexpr::reformat("(a #; #; x y b)", &scheme).unwrap(), "(a #; #; x y b)\n");
+
+    let mut hashes = config.clone();
+    hashes.dialect.line_comment_prefixes = vec!["#".to_owned()];
+    assert_eq!(sexpr::reformat("(a # note\n b #t)", &hashes).unwrap(), "(a # note\n  b\n  #t)\n");
+}
+
+#[test]
+fn test_flatten_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Meta {
+        version: u32,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Package {
+        name: String,
+        #[serde(flatten)]
+        meta: Meta,
+    }
+
+    let package = Package {
+        name: "sexpr".to_string(),
+        meta: Meta {
+            version: 7,
+            tags: vec!["sexp".to_string(), "smtlib".to_string()],
+        },
+    };
+    let s = to_string(&package).unwrap();
+    assert_eq!(s, r#"(("name" . "sexpr") ("version" . 7) ("tags" . ("sexp" "smtlib")))"#);
+    assert_eq!(from_str::<Package>(&s).unwrap(), package);
+}
+
+#[test]
+fn test_to_string_canonical_sorted() {
+    let a: Sexp = from_str("((name . sexpr) (deps . ((serde . 1) (itoa . 0))) (version . 7))").unwrap();
+    let b: Sexp = from_str("((version . 7) (name . sexpr) (deps . ((itoa . 0) (serde . 1))))").unwrap();
+    assert!(to_canonical(&a) != to_canonical(&b));
+    assert_eq!(a.to_string_canonical_sorted(), b.to_string_canonical_sorted());
+    assert_eq!(a.to_string_canonical_sorted(),
+               "((4:deps.((4:itoa.1:0)(5:serde.1:1)))(4:name.5:sexpr)(7:version.1:7))");
+
+    // Lists that are not alists keep their order.
+    let list: Sexp = from_str("(c b a)").unwrap();
+    assert_eq!(list.to_string_canonical_sorted(), "(1:c1:b1:a)");
+}
+
+#[test]
+fn test_parse_aggressive_number_detection() {
+    assert!(from_str::<Sexp>("+5").is_err());
+    assert!(from_str::<Sexp>("1a").is_err());
+
+    let mut config = ParseConfig::default();
+    config.aggressive_number_detection = true;
+    let parse = |s: &str| from_str_with_config::<Sexp>(s, config.clone()).unwrap();
+
+    assert_eq!(parse("+5"), Sexp::Number(Number::from(5)));
+    assert_eq!(parse(".5"), Sexp::Number(Number::from_f64(0.5).unwrap()));
+    assert_eq!(parse("-1e3"), Sexp::Number(Number::from_f64(-1000.0).unwrap()));
+    assert_eq!(parse("1a"), Sexp::from("1a"));
+    // There are no rationals, so this is a symbol.
+    assert_eq!(parse("1/2"), Sexp::from("1/2"));
+    assert_eq!(parse("(+ 1 -2)"),
+               Sexp::List(vec![Sexp::from("+"), Sexp::from(1), Sexp::from(-2)]));
+    assert_eq!(parse("3:a b"), Sexp::Atom(Atom::into_string("a b".to_string())));
+}
+
+#[test]
+fn test_sexp_contains() {
+    let v: Sexp = from_str(r#"((name . "John Doe") (age . 43))"#).unwrap();
+    assert!(v.contains_key("age"));
+    assert!(!v.contains_key("phones"));
+
+    let v: Sexp = from_str("(sexp s-exp sexpr)").unwrap();
+    assert!(v.contains(&Sexp::from("sexpr")));
+    assert!(!v.contains(&Sexp::from("json")));
+    assert!(!v.contains_key("sexp"));
+}
+
+#[test]
+fn test_integer_keyed_map_round_trip() {
+    use std::collections::BTreeMap;
+
+    let mut map = BTreeMap::new();
+    map.insert(1u32, "one".to_string());
+    map.insert(20u32, "twenty".to_string());
+
+    let s = to_string(&map).unwrap();
+    assert_eq!(s, r#"((1 . "one") (20 . "twenty"))"#);
+    assert_eq!(from_str::<BTreeMap<u32, String>>(&s).unwrap(), map);
+
+    let v = to_value(&map).unwrap();
+    assert_eq!(v[0].car(), Some(&Sexp::from(1)));
+    assert_eq!(sexpr::from_value::<BTreeMap<u32, String>>(v).unwrap(), map);
+}
+
+#[test]
+fn test_sexp_overlay() {
+    let mut v: Sexp = from_str("(a b c d)").unwrap();
+    v.overlay(&from_str("(1 nil 3)").unwrap());
+    assert_eq!(v, from_str("(1 b 3 d)").unwrap());
+
+    let mut v: Sexp = from_str("(a b)").unwrap();
+    v.overlay(&from_str("(#nil x y)").unwrap());
+    assert_eq!(v, from_str("(a x y)").unwrap());
+}
+
+#[test]
+fn test_streaming_map_matches_sexp_path() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Record {
+        id: u64,
+        name: String,
+        score: f64,
+        tags: Vec<String>,
+        parent: Option<u64>,
+    }
+
+    let mut text = String::from("(");
+    for i in 0..2000 {
+        let parent = if i % 3 == 0 { String::from("#nil") } else { (i / 2).to_string() };
+        text.push_str(&format!(
+            "((id . {}) (name . \"record {}\") (score . {}.5) (tags . (a |b c| #:d)) (parent . {}))",
+            i, i, i, parent
+        ));
+    }
+    text.push(')');
+
+    // Alists are deserialized as they are read, without building a Sexp.
+    let streamed: Vec<Record> = from_str(&text).unwrap();
+    let via_sexp: Vec<Record> = sexpr::from_value(from_str::<Sexp>(&text).unwrap()).unwrap();
+    assert_eq!(streamed.len(), 2000);
+    assert_eq!(streamed, via_sexp);
+    assert_eq!(streamed[1].tags, vec!["a", "b c", "d"]);
+    assert_eq!(streamed[1].parent, Some(0));
+    assert_eq!(streamed[3].parent, None);
+}
+
+#[test]
+fn test_sexp_get_or_insert_with() {
+    let mut v: Sexp = from_str("((port . 80))").unwrap();
+
+    // Existing key: the closure is not called and the value is unchanged.
+    assert_eq!(*v.get_or_insert_with("port", || panic!("port exists")).unwrap(), Sexp::from(80));
+    *v.get_or_insert_with("port", || Sexp::Nil).unwrap() = Sexp::from(8080);
+
+    // Missing key: a new entry is appended.
+    *v.get_or_insert_with("timeout", || Sexp::from(5)).unwrap() = Sexp::from(30);
+    assert_eq!(v, from_str("((port . 8080) (timeout . 30))").unwrap());
+
+    let mut v = Sexp::Nil;
+    v.get_or_insert_with("name", || Sexp::from("sexpr")).unwrap();
+    assert_eq!(v, from_str("((name . sexpr))").unwrap());
+
+    // A key that looks like a keyword is found again rather than duplicated.
+    let mut v = Sexp::Nil;
+    *v.get_or_insert_with("#:x", || Sexp::from(1)).unwrap() = Sexp::from(2);
+    assert_eq!(*v.get_or_insert_with("#:x", || panic!("#:x exists")).unwrap(), Sexp::from(2));
+    let key = Sexp::Atom(Atom::into_symbol("#:x".to_string()));
+    assert_eq!(v, Sexp::List(vec![Sexp::Pair(Some(Box::new(key)), Some(Box::new(Sexp::from(2))))]));
+
+    let mut v = Sexp::from(1);
+    assert!(v.get_or_insert_with("port", || Sexp::Nil).is_err());
+    assert_eq!(v, Sexp::from(1));
+}
+
+#[test]
+fn test_parse_exponents_and_lenient_numbers() {
+    assert_eq!(from_str::<f64>("1e10").unwrap(), 1e10);
+    assert_eq!(from_str::<f64>("-2.5E-3").unwrap(), -2.5e-3);
+    assert_eq!(from_str::<Sexp>("e10").unwrap(), Sexp::from("e10"));
+    assert!(from_str::<Sexp>("1e").is_err());
+    assert!(from_str::<Sexp>("1.2.3").is_err());
+    assert!(from_str::<f64>("1e400").is_err());
+
+    let mut config = ParseConfig::default();
+    config.aggressive_number_detection = true;
+    let parse = |s: &str| from_str_with_config::<Sexp>(s, config.clone()).unwrap();
+
+    assert_eq!(parse("1e10"), Sexp::Number(Number::from_f64(1e10).unwrap()));
+    assert_eq!(parse("1e"), Sexp::from("1e"));
+    assert_eq!(parse("1.2.3"), Sexp::from("1.2.3"));
+    assert_eq!(parse("(1e e1)"), Sexp::List(vec![Sexp::from("1e"), Sexp::from("e1")]));
+}
+
+#[test]
+fn test_sexp_write_to() {
+    let mut text = String::from("(");
+    for i in 0..5000 {
+        text.push_str(&format!("((id {}) (label \"line\\n\\\"{}\\\"\") (ratio {}.25) #(x #t #nil)) ", i, i, i));
+    }
+    text.push(')');
+    let mut config = ParseConfig::default();
+    config.hash_vectors = true;
+    let v: Sexp = from_str_with_config(&text, config).unwrap();
+
+    let mut out = Vec::new();
+    v.write_to(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), to_string(&v).unwrap());
+}
+
+#[test]
+fn test_bool_style() {
+    let v = Sexp::List(vec![Sexp::Boolean(true), Sexp::Boolean(false)]);
+    assert_eq!(to_string(&v).unwrap(), "(#t #f)");
+    assert_eq!(v.to_string(), "(#t #f)");
+
+    let styles = [
+        (BoolStyle::HashTF, "(#t #f)"),
+        (BoolStyle::TrueFalse, "(true false)"),
+        (BoolStyle::TNil, "(t nil)"),
+    ];
+    for &(style, expected) in &styles {
+        let mut config = SerializeConfig::default();
+        config.bool_style = Some(style);
+        assert_eq!(to_string_with_config(&v, config.clone()).unwrap(), expected);
+        assert_eq!(v.display_with(config.clone()).to_string(), expected);
+        assert_eq!(format!("{:#}", Sexp::Boolean(true).display_with(config)),
+                   style.spelling(true));
+    }
+}
+
+#[test]
+fn test_accept_bool_style() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Flags {
+        on: bool,
+        off: bool,
+    }
+
+    let inputs = [
+        (BoolStyle::HashTF, "((on . #t) (off . #f))"),
+        (BoolStyle::TrueFalse, "((on . true) (off . false))"),
+        (BoolStyle::TNil, "((on . t) (off . nil))"),
+    ];
+    for &(style, input) in &inputs {
+        let mut config = ParseConfig::default();
+        config.accept_bool_style(style);
+        config.accept_bool_style(style);
+        let flags: Flags = from_str_with_config(input, config.clone()).unwrap();
+        assert_eq!(flags, Flags { on: true, off: false });
+        assert!(config.true_symbols.len() <= 1);
+    }
+
+    assert!(from_str::<Flags>("((on . t) (off . nil))").is_err());
+
+    let mut config = ParseConfig::default();
+    config.accept_bool_style(BoolStyle::TrueFalse);
+    config.accept_bool_style(BoolStyle::TNil);
+    let flags: Vec<bool> = from_str_with_config("(t false #t nil true)", config).unwrap();
+    assert_eq!(flags, vec![true, false, true, false, true]);
+}
+
+#[test]
+fn test_sexp_splice() {
+    let mut v: Sexp = from_str("(a b c d)").unwrap();
+    let removed = v.splice(1..3, vec![Sexp::from("x"), Sexp::from("y"), Sexp::from("z")]).unwrap();
+    assert_eq!(removed, vec![Sexp::from("b"), Sexp::from("c")]);
+    assert_eq!(v, from_str("(a x y z d)").unwrap());
+
+    assert!(v.splice(5..5, vec![Sexp::from("end")]).unwrap().is_empty());
+    assert!(v.splice(0..0, vec![Sexp::from("start")]).unwrap().is_empty());
+    assert_eq!(v, from_str("(start a x y z d end)").unwrap());
+
+    assert!(v.splice(3..8, Vec::new()).is_err());
+    let (start, end) = (3, 2);
+    assert!(v.splice(start..end, Vec::new()).is_err());
+    assert_eq!(v, from_str("(start a x y z d end)").unwrap());
+    assert!(Sexp::from(1).splice(0..0, Vec::new()).is_err());
+}
+
+#[test]
+fn test_symbol_quoting_config() {
+    let v = Sexp::List(vec![
+        Sexp::from("list->vector"),
+        Sexp::from("has space"),
+        Sexp::from("+"),
+        Sexp::from("a.b"),
+    ]);
+    assert_eq!(to_string(&v).unwrap(), "(list->vector |has space| |+| a.b)");
+
+    let mut config = SerializeConfig::default();
+    config.bare_symbols = vec![String::from("+"), String::from("*")];
+    config.quote_symbol_chars = vec!['.'];
+    assert_eq!(to_string_with_config(&v, config.clone()).unwrap(),
+               "(list->vector |has space| + |a.b|)");
+
+    config.quote_symbol_chars.push('>');
+    assert_eq!(to_string_with_config(&Sexp::from("list->vector"), config).unwrap(),
+               "|list->vector|");
+}
+
+#[test]
+fn test_from_reader_auto() {
+    let text: &[u8] = b"  (\"abc\" (\"de\") \"\")";
+    let canonical: &[u8] = b"(3:abc(2:de)0:)";
+    let transport: &[u8] = b"{KDM6YWJj\nKDI6ZGUpMDop}\n";
+
+    let expected: Sexp = from_str(r#"("abc" ("de") "")"#).unwrap();
+    assert_eq!(sexpr::from_reader_auto::<_, Sexp>(text).unwrap(), expected);
+    assert_eq!(sexpr::from_reader_auto::<_, Sexp>(canonical).unwrap(), expected);
+    assert_eq!(sexpr::from_reader_auto::<_, Sexp>(transport).unwrap(), expected);
+
+    let v: Vec<String> = sexpr::from_reader_auto(&b"{KDE6YTE6Yik=}"[..]).unwrap();
+    assert_eq!(v, vec!["a", "b"]);
+
+    let octets: Vec<serde_bytes::ByteBuf> =
+        sexpr::from_reader_auto(&b"(3:\xff\x00\x011:a[4:hint]2:\xc3()\n"[..]).unwrap();
+    let octets: Vec<&[u8]> = octets.iter().map(|b| &b[..]).collect();
+    assert_eq!(octets, vec![&b"\xff\x00\x01"[..], b"a", b"\xc3("]);
+    let v: Vec<String> = sexpr::from_reader_auto(&b"\n(1:a1:b)"[..]).unwrap();
+    assert_eq!(v, vec!["a", "b"]);
+    assert!(sexpr::from_reader_auto::<_, Sexp>(&b"(3:\xff\x00\x01)"[..]).is_err());
+    assert!(sexpr::from_reader_auto::<_, Sexp>(&b"(3:ab)"[..]).is_err());
+
+    assert!(sexpr::from_reader_auto::<_, Sexp>(&b"{KDM6YWJj"[..]).is_err());
+    assert!(sexpr::from_reader_auto::<_, Sexp>(&b"{KDM6!WJj}"[..]).is_err());
+    assert!(sexpr::from_reader_auto::<_, Sexp>(&b"{KDE6YSk=} x"[..]).is_err());
+}
+
+#[test]
+fn test_sexp_simplify() {
+    fn pair(car: Option<Sexp>, cdr: Option<Sexp>) -> Sexp {
+        Sexp::Pair(car.map(Box::new), cdr.map(Box::new))
+    }
+    let sym = |s: &str| Sexp::from(s);
+    let list = Sexp::List;
+
+    let cases = vec![
+        (pair(Some(sym("x")), None), list(vec![sym("x")])),
+        (pair(Some(sym("x")), Some(Sexp::Nil)), list(vec![sym("x")])),
+        (pair(None, Some(sym("y"))), pair(Some(Sexp::Nil), Some(sym("y")))),
+        (pair(None, None), Sexp::Nil),
+        (pair(Some(sym("x")), Some(list(vec![sym("y"), sym("z")]))),
+         list(vec![sym("x"), sym("y"), sym("z")])),
+        // (a . (b . #nil)) is (a b), built up from the inside.
+        (pair(Some(sym("a")), Some(pair(Some(sym("b")), Some(Sexp::Nil)))),
+         list(vec![sym("a"), sym("b")])),
+        (pair(Some(sym("a")), Some(sym("b"))), pair(Some(sym("a")), Some(sym("b")))),
+        (list(vec![sym("f"), pair(Some(sym("x")), None)]),
+         list(vec![sym("f"), list(vec![sym("x")])])),
+        (Sexp::Vector(vec![pair(None, None)]), Sexp::Vector(vec![Sexp::Nil])),
+        (Sexp::from(1), Sexp::from(1)),
+    ];
+    for (input, expected) in cases {
+        let simple = input.simplify();
+        assert_eq!(simple, expected);
+        assert_eq!(simple.clone().simplify(), simple);
+    }
+}
+
+#[test]
+fn test_unit_variant_style() {
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Color {
+        Red,
+        LightBlue,
+    }
+
+    let colors = vec![Color::Red, Color::LightBlue];
+    let cases = [
+        (UnitVariantStyle::String, r#"("red" "light-blue")"#),
+        (UnitVariantStyle::Symbol, "(red light-blue)"),
+        (UnitVariantStyle::Keyword, "(#:red #:light-blue)"),
+    ];
+    for &(style, expected) in &cases {
+        let mut config = SerializeConfig::default();
+        config.unit_variant_style = style;
+        let s = to_string_with_config(&colors, config.clone()).unwrap();
+        assert_eq!(s, expected);
+        assert_eq!(from_str::<Vec<Color>>(&s).unwrap(), colors);
+
+        let v = sexpr::to_value_with_config(&Color::LightBlue, config).unwrap();
+        assert_eq!(sexpr::from_value::<Color>(v).unwrap(), Color::LightBlue);
+    }
+
+    assert_eq!(from_str::<Color>("#:red").unwrap(), Color::Red);
+    assert!(from_str::<Color>("#:Red").is_err());
+}
+
+#[test]
+fn test_tuple_length_errors() {
+    #[derive(Debug, Deserialize)]
+    struct Triple(u8, u8, u8);
+
+    let too_few = "too few elements for a tuple of 3: found 2";
+    let too_many = "too many elements for a tuple of 3: found 4";
+
+    let err = from_str::<(u8, u8, u8)>("(1 2)").unwrap_err().to_string();
+    assert!(err.starts_with(too_few), "{}", err);
+    let err = from_str::<(u8, u8, u8)>("(1 2 3 4)").unwrap_err().to_string();
+    assert!(err.starts_with(too_many), "{}", err);
+    let err = from_str::<Triple>("(1 2 3 (4 5))").unwrap_err().to_string();
+    assert!(err.starts_with(too_many), "{}", err);
+    assert_eq!(from_str::<(u8, u8, u8)>("(1 2 3)").unwrap(), (1, 2, 3));
+
+    for &(input, expected) in &[("(1 2)", too_few), ("(1 2 3 4)", too_many)] {
+        let v: Sexp = from_str(input).unwrap();
+        let err = sexpr::from_value::<(u8, u8, u8)>(v).unwrap_err().to_string();
+        assert_eq!(err, expected);
+    }
+}
+
+#[test]
+fn test_deserializer_with_config() {
+    use sexpr::de::{Deserializer, StrRead, StreamDeserializer};
+
+    let input = "[a b] (c)";
+    let mut config = ParseConfig::default();
+    config.square_brackets = true;
+
+    let values: Vec<Sexp> = Deserializer::with_config(StrRead::new(input), config.clone())
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values,
+               vec![Sexp::Vector(vec![Sexp::from("a"), Sexp::from("b")]),
+                    Sexp::List(vec![Sexp::from("c")])]);
+
+    let stream = StreamDeserializer::<_, Sexp>::with_config(StrRead::new(input), config);
+    assert_eq!(stream.map(Result::unwrap).collect::<Vec<_>>(), values);
+
+    // The default dialect has no brackets.
+    let mut stream = StreamDeserializer::<_, Sexp>::new(StrRead::new(input));
+    assert!(stream.next().unwrap().is_err());
+}
+
+#[test]
+fn test_sexp_as_alist() {
+    let v: Sexp = from_str(r#"((name . "sexpr") (port . 8080) (#:debug . #t))"#).unwrap();
+    let entries = v.as_alist().unwrap();
+    let keys: Vec<&str> = entries.iter().map(|&(key, _)| key).collect();
+    assert_eq!(keys, vec!["name", "port", "debug"]);
+    assert_eq!(*entries[1].1, Sexp::from(8080));
+
+    assert_eq!(from_str::<Sexp>("((a . 1) b)").unwrap().as_alist(), None);
+    assert_eq!(from_str::<Sexp>("((1 . a))").unwrap().as_alist(), None);
+    assert_eq!(from_str::<Sexp>("()").unwrap().as_alist(), None);
+}
+
+#[test]
+fn test_serialize_improper_list() {
+    fn cons(car: Sexp, cdr: Sexp) -> Sexp {
+        Sexp::Pair(Some(Box::new(car)), Some(Box::new(cdr)))
+    }
+    let one = || Sexp::Number(1.into());
+    let two = || Sexp::Number(2.into());
+
+    let dotted = cons(one(), cons(two(), Sexp::Number(3.into())));
+    assert_eq!(to_string(&dotted).unwrap(), "(1 2 . 3)");
+    assert_eq!(dotted.to_string(), "(1 2 . 3)");
+    assert_eq!(to_value(&dotted).unwrap(), dotted);
+
+    let pair = cons(Sexp::from("a"), Sexp::from("b"));
+    assert_eq!(to_string(&pair).unwrap(), "(a . b)");
+
+    // A chain ending in nil is a proper list, but a single cell keeps its
+    // dot, as an alist entry does.
+    assert_eq!(to_string(&cons(one(), cons(two(), Sexp::Nil))).unwrap(), "(1 2)");
+    assert_eq!(to_string(&cons(one(), Sexp::List(vec![two()]))).unwrap(), "(1 . (2))");
+    assert_eq!(to_string(&cons(one(), Sexp::Nil)).unwrap(), "(1 . #nil)");
+}
+
+#[test]
+fn test_stream_max_forms() {
+    use sexpr::de::{StrRead, StreamDeserializer};
+
+    let mut config = ParseConfig::default();
+    config.max_forms = Some(3);
+
+    let read = StrRead::new("(a) (b) (c) (d) (e)");
+    let mut stream = StreamDeserializer::<_, Sexp>::with_config(read, config);
+    for _ in 0..3 {
+        assert!(stream.next().unwrap().is_ok());
+    }
+    let err = stream.next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "too many top-level forms at line 1 column 13");
+}
+
+#[test]
+fn test_sexp_replace_at() {
+    use sexpr::sexp::PathSegment::Key;
+
+    fn entry(key: &str, value: Sexp) -> Sexp {
+        Sexp::new_entry(key, value)
+    }
+    let num = |n: u64| Sexp::Number(n.into());
+
+    let mut v = Sexp::List(vec![
+        entry("server", Sexp::List(vec![
+            entry("ports", Sexp::List(vec![num(80), num(443)])),
+        ])),
+    ]);
+
+    // An existing deep value.
+    assert_eq!(v.replace_at("/server/ports/1", num(8443)).unwrap(), num(443));
+    assert_eq!(v.get_path(&[Key("server"), Key("ports")]),
+               Some(&Sexp::List(vec![num(80), num(8443)])));
+
+    // A missing intermediate entry is created.
+    assert_eq!(v.replace_at("/client/timeout", num(30)).unwrap(), Sexp::Nil);
+    assert_eq!(v.get_path(&[Key("client"), Key("timeout")]), Some(&num(30)));
+
+    assert!(v.replace_at("/server/ports/5", num(1)).is_err());
+    assert!(v.replace_at("server", num(1)).is_err());
+}
+
+#[test]
+fn test_ascii_only_strings() {
+    let mut config = SerializeConfig::default();
+    config.ascii_only = true;
+
+    let s = "caf\u{e9} \u{1f600}".to_owned();
+    let out = to_string_with_config(&s, config).unwrap();
+    assert_eq!(out, r#""caf\u00e9 \ud83d\ude00""#);
+    assert!(out.is_ascii());
+    assert_eq!(from_str::<String>(&out).unwrap(), s);
+
+    assert_eq!(to_string(&s).unwrap(), "\"caf\u{e9} \u{1f600}\"");
+}
+
+#[test]
+fn test_number_from_str() {
+    assert_eq!("42".parse::<Number>().unwrap(), Number::from(42u64));
+    assert!("42".parse::<Number>().unwrap().is_u64());
+    assert_eq!("-7".parse::<Number>().unwrap(), Number::from(-7i64));
+    let pi: f64 = "3.14".parse().unwrap();
+    assert_eq!("3.14".parse::<Number>().unwrap(), Number::from_f64(pi).unwrap());
+    assert_eq!("1e9".parse::<Number>().unwrap(), Number::from_f64(1e9).unwrap());
+
+    let err = "abc".parse::<Number>().unwrap_err();
+    assert_eq!(err.to_string(), "invalid number");
+    assert!("inf".parse::<Number>().is_err());
+
+    // The same text is a number to both, or to neither.
+    let accepted = ["5", " 5", "5\n", "-5", "0", "-0", ".5", "-.5", "1.5e3", "1E-2",
+                    "18446744073709551616"];
+    let rejected = ["", "-", "+5", "5.", ".", "05", "5e", "1_000", "1.2.3", "5 6", "0x10",
+                    "NaN", "1e400"];
+    for s in &accepted {
+        let n = s.parse::<Number>().unwrap();
+        assert_eq!(from_str::<Sexp>(s).unwrap(), Sexp::Number(n), "{:?}", s);
+    }
+    for s in &rejected {
+        assert!(s.parse::<Number>().is_err(), "{:?}", s);
+        match from_str::<Sexp>(s) {
+            Ok(Sexp::Number(_)) => panic!("{:?} read as a number", s),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_map_and_struct_round_trip() {
+    use std::collections::BTreeMap;
+    use sexpr::from_value;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Service {
+        name: String,
+        tags: Vec<String>,
+        limit: Option<u32>,
+        env: BTreeMap<String, String>,
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert("ports".to_owned(), vec![80, 443]);
+    map.insert("none".to_owned(), vec![]);
+    map.insert("one".to_owned(), vec![1]);
+
+    let text = to_string(&map).unwrap();
+    assert_eq!(from_str::<BTreeMap<String, Vec<u32>>>(&text).unwrap(), map);
+    let value = to_value(&map).unwrap();
+    assert_eq!(from_str::<BTreeMap<String, Vec<u32>>>(&value.to_string()).unwrap(), map);
+    assert_eq!(from_value::<BTreeMap<String, Vec<u32>>>(value).unwrap(), map);
+
+    let mut env = BTreeMap::new();
+    env.insert("HOME".to_owned(), "/srv".to_owned());
+    let service = Service {
+        name: "web".to_owned(),
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        limit: None,
+        env: env,
+    };
+
+    let text = to_string(&service).unwrap();
+    assert_eq!(from_str::<Service>(&text).unwrap(), service);
+    let value = to_value(&service).unwrap();
+    assert_eq!(from_str::<Service>(&value.to_string()).unwrap(), service);
+    assert_eq!(from_value::<Service>(value).unwrap(), service);
+}
+
+#[test]
+fn test_sexp_is_truthy() {
+    assert!(!Sexp::Nil.is_truthy());
+    assert!(!Sexp::Boolean(false).is_truthy());
+    assert!(!Sexp::List(vec![]).is_truthy());
+
+    assert!(Sexp::Boolean(true).is_truthy());
+    assert!(Sexp::Number(0.into()).is_truthy());
+    assert!(Sexp::Atom(Atom::into_string(String::new())).is_truthy());
+    assert!(Sexp::List(vec![Sexp::Nil]).is_truthy());
+    assert!(Sexp::Vector(vec![]).is_truthy());
+
+    let parsed: Sexp = from_str("()").unwrap();
+    assert!(!parsed.is_truthy());
+}
+
+#[test]
+fn test_retain_all_comment_kinds() {
+    use serde::de::Deserialize;
+    use sexpr::de::{Comment, CommentBody, Deserializer, StrRead};
+
+    let mut config = ParseConfig::default();
+    config.block_comments = true;
+    config.datum_comments = true;
+    config.retain_comments = true;
+
+    let input = "; head\n(a #| block #| nested |# |# b\n   #;(c d) e) ; tail";
+    let mut de = Deserializer::with_config(StrRead::new(input), config);
+    let value = Sexp::deserialize(&mut de).unwrap();
+    de.end().unwrap();
+    assert_eq!(value, Sexp::List(vec![Sexp::from("a"), Sexp::from("b"), Sexp::from("e")]));
+
+    let comment = |line, column, body| Comment { line: line, column: column, body: body };
+    assert_eq!(
+        de.comments(),
+        &[
+            comment(1, 1, CommentBody::Line("; head".to_owned())),
+            comment(2, 4, CommentBody::Block("#| block #| nested |# |#".to_owned())),
+            comment(3, 4, CommentBody::Datum(Sexp::List(vec![Sexp::from("c"), Sexp::from("d")]))),
+            comment(3, 15, CommentBody::Line("; tail".to_owned())),
+        ][..]
+    );
+
+    // Without the flags `#|` and `#;` are not comments.
+    assert!(from_str::<Sexp>("(a #| b |#)").is_err());
+    let err = from_str_with_config::<Sexp>("(a #| b", {
+        let mut config = ParseConfig::default();
+        config.block_comments = true;
+        config
+    });
+    assert_eq!(err.unwrap_err().to_string(), "EOF while parsing a comment at line 1 column 7");
+}
+
+#[test]
+fn test_nested_datum_comments_are_depth_limited() {
+    let mut config = ParseConfig::default();
+    config.datum_comments = true;
+
+    let value: Sexp = from_str_with_config("#; #; a b c", config.clone()).unwrap();
+    assert_eq!(value, Sexp::from("c"));
+
+    let deep = "#;".repeat(100_000) + "a";
+    let err = from_str_with_config::<Sexp>(&deep, config).unwrap_err();
+    assert_eq!(err.to_string(), "recursion limit exceeded at line 1 column 255");
+}
+
+#[test]
+fn test_transcode_to_json() {
+    let input = r#"((name . "web") (ports . (80 443)) (tls . #t) (ratio . 0.5) (proxy . #nil)
+                    (aliases . (www api)))"#;
+
+    let mut de = sexpr::Deserializer::from_str(input);
+    let mut out = Vec::new();
+    serde_transcode::transcode(&mut de, &mut serde_json::Serializer::new(&mut out)).unwrap();
+    de.end().unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"{"name":"web","ports":[80,443],"tls":true,"ratio":0.5,"proxy":null,"aliases":["www","api"]}"#
+    );
+}
+
+#[test]
+fn test_deserialize_any_visits_symbols_as_strings() {
+    let value: serde_json::Value = from_str(r#"(a #:b "c" (d 1))"#).unwrap();
+    assert_eq!(value.to_string(), r#"["a","b","c",["d",1]]"#);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Words<'a> {
+        Borrowed(#[serde(borrow)] Vec<&'a str>),
+    }
+    assert_eq!(from_str::<Words>(r#"(alpha beta "gamma")"#).unwrap(),
+               Words::Borrowed(vec!["alpha", "beta", "gamma"]));
+
+    let value: Sexp = from_str(r#"(a #:b "c" ((k . 1)))"#).unwrap();
+    let k = Sexp::Pair(Some(Box::new(Sexp::Atom(Atom::into_symbol("k".to_string())))),
+                       Some(Box::new(Sexp::Number(1.into()))));
+    assert_eq!(value, Sexp::List(vec![
+        Sexp::Atom(Atom::into_symbol("a".to_string())),
+        Sexp::Atom(Atom::into_keyword("b".to_string())),
+        Sexp::Atom(Atom::into_string("c".to_string())),
+        Sexp::List(vec![k]),
+    ]));
+}
+
+#[test]
+fn test_atom_deserializer_keeps_keywords_apart() {
+    use serde::Deserialize;
+
+    let keyword = Atom::into_keyword("key".to_string());
+    assert_eq!(String::deserialize(keyword.clone()).unwrap(), "key");
+    assert_eq!(String::deserialize(&keyword).unwrap(), "key");
+    assert_eq!(Sexp::deserialize(keyword.clone()).unwrap(), Sexp::Atom(keyword.clone()));
+    assert_eq!(Atom::deserialize(&keyword).unwrap(), keyword);
+
+    let symbol = Atom::into_symbol("key".to_string());
+    assert_eq!(Atom::deserialize(symbol.clone()).unwrap(), symbol);
+
+    let string = Atom::into_string("#:key".to_string());
+    assert_eq!(Atom::deserialize(&string).unwrap(), string);
+}
+
+#[test]
+fn test_alist_keyed_by_private_tokens_round_trips() {
+    for text in &[r#"(("$sexpr::private::Pair" . (1 2)))"#,
+                  r#"(("$sexpr::private::Vector" . (1 2)) (b . 3))"#] {
+        let value: Sexp = from_str(text).unwrap();
+        assert!(value.is_alist());
+        assert_eq!(from_str::<Sexp>(&to_string(&value).unwrap()).unwrap(), value);
+        assert_eq!(sexpr::from_value::<Sexp>(value.clone()).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_sexp_shrink_to_fit() {
+    use sexpr::sexp::PathSegment::Index;
+
+    fn capacity(sexp: &Sexp) -> usize {
+        match *sexp {
+            Sexp::List(ref elts) | Sexp::Vector(ref elts) => elts.capacity(),
+            _ => 0,
+        }
+    }
+    let small = |n: &Sexp| match *n {
+        Sexp::Number(ref n) => n.as_u64().map_or(false, |n| n < 2),
+        _ => true,
+    };
+
+    let numbers = (0..100u64).map(|n| Sexp::Number(n.into())).collect::<Vec<_>>();
+    let mut inner = Sexp::List(numbers.clone());
+    inner.retain(small);
+    let mut v = Sexp::List(numbers);
+    v.retain(small);
+    v.append(Sexp::List(vec![inner])).unwrap();
+    assert!(capacity(&v) >= 100);
+    assert!(capacity(v.get_path(&[Index(2)]).unwrap()) >= 100);
+
+    v.shrink_to_fit();
+    assert_eq!(capacity(&v), 3);
+    assert_eq!(capacity(v.get_path(&[Index(2)]).unwrap()), 2);
+}
+
+#[test]
+fn test_unknown_hash_policy() {
+    let err = from_str::<Sexp>("(a #zap b)").unwrap_err();
+    assert_eq!(err.to_string(), "expected ident at line 1 column 6");
+
+    let mut config = ParseConfig::default();
+    config.unknown_hash_policy = UnknownHashPolicy::Symbol;
+    let v: Sexp = from_str_with_config("(a #zap #z b)", config.clone()).unwrap();
+    assert_eq!(v, Sexp::List(vec![Sexp::from("a"), Sexp::from("#zap"), Sexp::from("#z"), Sexp::from("b")]));
+
+    // Recognized dispatches keep their meaning.
+    let v: Sexp = from_str_with_config("(#t #f #nil)", config).unwrap();
+    assert_eq!(v, Sexp::List(vec![Sexp::Boolean(true), Sexp::Boolean(false), Sexp::Nil]));
+}
+
+#[test]
+fn test_parse_lone_atoms() {
+    let config = ParseConfig::default();
+
+    let keyword = Atom::parse("#:verbose", &config).unwrap();
+    assert_eq!(keyword.as_keyword(), Some("verbose"));
+    let symbol = Atom::parse(" define ", &config).unwrap();
+    assert_eq!(symbol.as_symbol(), Some("define"));
+    let string = Atom::parse("\"a b\"", &config).unwrap();
+    assert_eq!(string.as_string(), Some("a b"));
+
+    assert_eq!(Number::parse("42", &config).unwrap(), Number::from(42));
+    assert_eq!(Number::parse("-7", &config).unwrap(), Number::from(-7));
+
+    assert!(Atom::parse("42", &config).is_err());
+    assert!(Atom::parse("a b", &config).is_err());
+    assert!(Number::parse("abc", &config).is_err());
+    assert!(Number::parse("1_000", &config).is_err());
+
+    let mut config = ParseConfig::default();
+    config.numeric_underscores = true;
+    assert_eq!(Number::parse("1_000", &config).unwrap(), Number::from(1000));
+}
+
+#[test]
+fn test_tuple_struct_through_pair() {
+    use sexpr::from_value;
+    use sexpr::to_value_with_config;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point(i32, i32);
+
+    let mut config = SerializeConfig::default();
+    config.two_tuples_as_pairs = true;
+
+    let text = to_string_with_config(&Point(1, -2), config.clone()).unwrap();
+    assert_eq!(text, "(1 . -2)");
+    assert_eq!(from_str::<Point>(&text).unwrap(), Point(1, -2));
+
+    let value = to_value_with_config(&Point(1, -2), config).unwrap();
+    assert_eq!(
+        value,
+        Sexp::Pair(Some(Box::new(Sexp::Number(1.into()))), Some(Box::new(Sexp::Number((-2).into()))))
+    );
+    assert_eq!(from_value::<Point>(value).unwrap(), Point(1, -2));
+}
+
+#[test]
+fn test_concat_adjacent_strings() {
+    let string = |s: &str| Sexp::Atom(Atom::into_string(s.to_owned()));
+    let input = r#"("a" "b" c "d")"#;
+
+    let v: Sexp = from_str(input).unwrap();
+    assert_eq!(v, Sexp::List(vec![string("a"), string("b"), Sexp::from("c"), string("d")]));
+
+    let mut config = ParseConfig::default();
+    config.concat_adjacent_strings = true;
+    let v: Sexp = from_str_with_config(input, config.clone()).unwrap();
+    assert_eq!(v, Sexp::List(vec![string("ab"), Sexp::from("c"), string("d")]));
+
+    let v: Sexp = from_str_with_config(r#"(("x" "y" "z") 1 "w")"#, config).unwrap();
+    assert_eq!(
+        v,
+        Sexp::List(vec![Sexp::List(vec![string("xyz")]), Sexp::Number(1.into()), string("w")])
+    );
+}
+
+#[test]
+fn test_sexp_try_from() {
+    use std::convert::TryFrom;
+
+    assert_eq!(i64::try_from(Sexp::from(-3)).unwrap(), -3);
+    assert_eq!(u64::try_from(Sexp::from(3)).unwrap(), 3);
+    assert_eq!(f64::try_from(Sexp::from(1.5)).unwrap(), 1.5);
+    assert_eq!(bool::try_from(Sexp::Boolean(true)).unwrap(), true);
+    assert_eq!(String::try_from(Sexp::from("sym")).unwrap(), "sym");
+    assert_eq!(Atom::try_from(Sexp::from("sym")).unwrap().as_symbol(), Some("sym"));
+    assert_eq!(Number::try_from(Sexp::from(7)).unwrap(), Number::from(7));
+    assert_eq!(Vec::<Sexp>::try_from(Sexp::List(vec![Sexp::Nil])).unwrap(), vec![Sexp::Nil]);
+    assert_eq!(Vec::<Sexp>::try_from(Sexp::Nil).unwrap(), vec![]);
+
+    let err = i64::try_from(Sexp::Boolean(true)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: boolean `true`, expected an i64");
+    let err = u64::try_from(Sexp::from(-1)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value: integer `-1`, expected a u64");
+    assert!(f64::try_from(Sexp::from("x")).is_err());
+    assert!(bool::try_from(Sexp::Nil).is_err());
+    assert!(String::try_from(Sexp::from(1)).is_err());
+    assert!(Atom::try_from(Sexp::List(vec![])).is_err());
+    assert!(Number::try_from(Sexp::Boolean(false)).is_err());
+    assert!(Vec::<Sexp>::try_from(Sexp::from(1)).is_err());
+}
+
+#[test]
+fn test_to_string_with_header() {
+    let value = vec![1, 2, 3];
+    let s = sexpr::to_string_with_header(&value, "generated file\n\ndo not edit").unwrap();
+    assert_eq!(s, "; generated file\n;\n; do not edit\n(1 2 3)");
+    assert_eq!(from_str::<Vec<i32>>(&s).unwrap(), value);
+
+    assert_eq!(sexpr::to_string_with_header(&value, "").unwrap(), "(1 2 3)");
+}
+
+#[test]
+fn test_sexp_partition_alist() {
+    let v: Sexp = from_str(r#"((meta-version . 2) (name . "db") (meta-owner . ops) (port . 5432))"#)
+        .unwrap();
+    let (meta, data) = v.partition_alist(|key, _| key.starts_with("meta-"));
+    assert_eq!(meta, from_str::<Sexp>("((meta-version . 2) (meta-owner . ops))").unwrap());
+    assert_eq!(data, from_str::<Sexp>(r#"((name . "db") (port . 5432))"#).unwrap());
+
+    let (numeric, _) = v.partition_alist(|_, value| value.coerce_number().is_some());
+    assert_eq!(numeric.as_alist().unwrap().len(), 2);
+
+    let (none, all) = v.partition_alist(|_, _| false);
+    assert_eq!(none, Sexp::List(vec![]));
+    assert_eq!(all, v);
+
+    let (a, b) = Sexp::from(1).partition_alist(|_, _| true);
+    assert_eq!((a, b), (Sexp::List(vec![]), Sexp::List(vec![])));
+}
+
+#[test]
+fn test_colon_keyword_styles() {
+    let parse = |s: &str, style| {
+        let mut config = ParseConfig::default();
+        config.keyword_style = style;
+        from_str_with_config::<Sexp>(s, config)
+    };
+    let keyword = |name: &str| Sexp::Atom(Atom::into_keyword(name.to_string()));
+    let symbol = |name: &str| Sexp::Atom(Atom::into_symbol(name.to_string()));
+
+    let v = parse("(:name #:other name:)", KeywordStyle::LeadingColon).unwrap();
+    assert_eq!(v, Sexp::List(vec![keyword("name"), keyword("other"), symbol("name:")]));
+    assert!(parse("(: a)", KeywordStyle::LeadingColon).is_err());
+
+    let v = parse("(name: #:other a:b)", KeywordStyle::TrailingColon).unwrap();
+    assert_eq!(v, Sexp::List(vec![keyword("name"), keyword("other"), symbol("a:b")]));
+    assert!(parse(":name", KeywordStyle::TrailingColon).is_err());
+
+    let atom = Atom::parse("name:", &ParseConfig { keyword_style: KeywordStyle::TrailingColon,
+                                                   ..ParseConfig::default() }).unwrap();
+    assert_eq!(atom.as_keyword(), Some("name"));
+
+    assert!(parse(":name", KeywordStyle::Hash).is_err());
+    assert_eq!(parse("name:", KeywordStyle::Hash).unwrap(), symbol("name:"));
+}
+
+#[test]
+fn test_sexp_as_f64_lossy() {
+    let v: Sexp = from_str(r#"(42 -0.25 "2.5" " -7 " 18446744073709551615 word "word" #t)"#).unwrap();
+    let values: Vec<Option<f64>> = (0..8).map(|i| v[i].as_f64_lossy()).collect();
+    assert_eq!(values,
+               vec![Some(42.0),
+                    Some(-0.25),
+                    Some(2.5),
+                    Some(-7.0),
+                    Some(u64::max_value() as f64),
+                    None,
+                    None,
+                    None]);
+    assert_eq!(Sexp::Nil.as_f64_lossy(), None);
+}
+
+#[test]
+fn test_serializer_reuses_buffer() {
+    let mut ser = sexpr::Serializer::with_buffer(String::from("stale contents"));
+    let mut capacity = 0;
+    for i in 0..1000u32 {
+        let value = (i, format!("item {}", i), vec![i; (i % 4) as usize]);
+        let written = ser.serialize_into(&value).unwrap();
+        assert_eq!(written, to_string(&value).unwrap());
+        capacity = capacity.max(written.len());
+    }
+
+    ser.reset();
+    let buffer = ser.into_inner();
+    assert!(buffer.is_empty());
+    assert!(buffer.capacity() >= capacity);
+}
+
+#[test]
+fn test_serialize_into_rejects_invalid_utf8() {
+    struct Latin1Nil;
+
+    impl sexpr::ser::Formatter for Latin1Nil {
+        fn write_null<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+        where
+            W: io::Write,
+        {
+            writer.write_all(b"n\xefl")
+        }
+    }
+
+    let mut ser = sexpr::Serializer::with_formatter(Vec::new(), Latin1Nil);
+    assert_eq!(ser.serialize_into(&1).unwrap(), "1");
+    let err = ser.serialize_into(&()).unwrap_err();
+    assert_eq!(err.to_string(), "invalid UTF-8 at byte offset 1");
+}
+
 // ///
 // /// ```rust
 // /// # #[macro_use]